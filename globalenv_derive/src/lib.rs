@@ -0,0 +1,73 @@
+//! `#[derive(GlobalEnv)]` for the [globalenv](https://docs.rs/globalenv) crate, enabled by its `derive` feature.
+
+use proc_macro::TokenStream;
+use quote::quote;
+use syn::{parse_macro_input, Data, DeriveInput, Error, Fields, LitStr};
+
+/// Adds `persist`, `persist_in`, `load_global` and `load_global_in` methods mapping each field to
+/// the variable named after it in uppercase, with the prefix given by `#[globalenv(prefix = "...")]`.
+/// Fields are written with `ToString` and read with `FromStr`.
+#[proc_macro_derive(GlobalEnv, attributes(globalenv))]
+pub fn derive_global_env(input: TokenStream) -> TokenStream {
+    let input = parse_macro_input!(input as DeriveInput);
+    expand(input).unwrap_or_else(Error::into_compile_error).into()
+}
+
+fn expand(input: DeriveInput) -> Result<proc_macro2::TokenStream, Error> {
+    let mut prefix = String::new();
+    for attr in input.attrs.iter().filter(|a| a.path().is_ident("globalenv")) {
+        attr.parse_nested_meta(|meta| {
+            if meta.path.is_ident("prefix") {
+                prefix = meta.value()?.parse::<LitStr>()?.value();
+                Ok(())
+            } else {
+                Err(meta.error("unsupported globalenv attribute"))
+            }
+        })?;
+    }
+
+    let fields = match &input.data {
+        Data::Struct(data) => match &data.fields {
+            Fields::Named(fields) => &fields.named,
+            _ => return Err(Error::new_spanned(&input.ident, "GlobalEnv needs named fields")),
+        },
+        _ => return Err(Error::new_spanned(&input.ident, "GlobalEnv can only be derived for structs")),
+    };
+    let idents: Vec<_> = fields.iter().filter_map(|f| f.ident.as_ref()).collect();
+    let vars: Vec<_> = idents
+        .iter()
+        .map(|ident| format!("{}{}", prefix, ident.to_string().trim_start_matches("r#").to_uppercase()))
+        .collect();
+
+    let name = &input.ident;
+    let (impl_generics, ty_generics, where_clause) = input.generics.split_for_impl();
+    Ok(quote! {
+        impl #impl_generics #name #ty_generics #where_clause {
+            /// Sets globally the variable of each field.
+            pub fn persist(&self) -> ::std::result::Result<(), ::globalenv::EnvError> {
+                self.persist_in(&::globalenv::Target::default())
+            }
+
+            /// Sets the variable of each field in the given target.
+            pub fn persist_in(&self, target: &::globalenv::Target) -> ::std::result::Result<(), ::globalenv::EnvError> {
+                #( ::globalenv::set_var_in(target, #vars, &::std::string::ToString::to_string(&self.#idents))?; )*
+                Ok(())
+            }
+
+            /// Reads each field from its globally persisted variable.
+            pub fn load_global() -> ::std::result::Result<Self, ::globalenv::EnvError> {
+                Self::load_global_in(&::globalenv::Target::default())
+            }
+
+            /// Reads each field from its variable persisted in the given target.
+            pub fn load_global_in(target: &::globalenv::Target) -> ::std::result::Result<Self, ::globalenv::EnvError> {
+                Ok(Self {
+                    #( #idents: ::globalenv::get_var_in(target, #vars)?
+                        .ok_or(::globalenv::EnvError::VarError)?
+                        .parse()
+                        .map_err(|_| ::globalenv::EnvError::ParseError)?, )*
+                })
+            }
+        }
+    })
+}
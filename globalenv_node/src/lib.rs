@@ -0,0 +1,45 @@
+//! Node.js binding of [globalenv](https://docs.rs/globalenv), for Electron apps and Node CLIs:
+//! ```js
+//! const globalenv = require('globalenv');
+//! globalenv.setVar('MYTOOL_HOME', '/opt/mytool');
+//! ```
+//! Errors are thrown with the message of the `EnvError`.
+
+use napi::{Error, Result};
+use napi_derive::napi;
+
+fn js_error(e: globalenv::EnvError) -> Error {
+    Error::from_reason(e.to_string())
+}
+
+/// A persisted variable.
+#[napi(object)]
+pub struct Var {
+    pub name: String,
+    pub value: String,
+}
+
+/// Sets a global environment variable.
+#[napi]
+pub fn set_var(var: String, value: String) -> Result<()> {
+    globalenv::set_var(&var, &value).map_err(js_error)
+}
+
+/// Unsets a global environment variable.
+#[napi]
+pub fn unset_var(var: String) -> Result<()> {
+    globalenv::unset_var(&var).map_err(js_error)
+}
+
+/// Gets the globally persisted value of a variable, or `null`.
+#[napi]
+pub fn get_var(var: String) -> Result<Option<String>> {
+    globalenv::get_var(&var).map_err(js_error)
+}
+
+/// Lists the globally persisted variables.
+#[napi]
+pub fn vars() -> Result<Vec<Var>> {
+    let vars = globalenv::vars().map_err(js_error)?;
+    Ok(vars.into_iter().map(|var| Var { name: var.name, value: var.value }).collect())
+}
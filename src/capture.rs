@@ -0,0 +1,81 @@
+//! Import of environments captured with POSIX `env` or Windows `set` (e.g. on another machine, to migrate it).
+
+use crate::{dotenv, ImportOptions, ImportResult, Target};
+
+/// Variables of the shell or session the output was captured in, which mean nothing elsewhere.
+const VOLATILE: &[&str] = &["_", "PWD", "OLDPWD", "SHLVL"];
+
+/// A name the output of `env` or `set` holds a variable of: `cmd` also prints `=C:`-like hidden variables, and
+/// `env` prints the functions exported by bash as `BASH_FUNC_name%%`, which are skipped.
+fn is_var(name: &str) -> bool {
+    name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_')
+        && name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '(' | ')'))
+        && !VOLATILE.contains(&name)
+}
+
+/// Parses the output of POSIX `env` or of Windows `cmd /c set`: one `NAME=value` line per variable, values
+/// being printed as they are. A value spanning several lines can't be told apart from the next variable with
+/// certainty: a line is taken as the start of a variable when what precedes its first `=` has no whitespace,
+/// as the next line of the previous value otherwise. The hidden variables of cmd, the functions exported by
+/// bash and the variables of the capturing shell itself (`PWD`, `SHLVL`...) are skipped.
+/// Example:
+/// ```rust
+/// let vars = globalenv::parse_env_output("HOME=/home/me\nMOTD=first line\nsecond line\nSHLVL=1\n");
+/// assert_eq!(vars, [
+///     (String::from("HOME"), String::from("/home/me")),
+///     (String::from("MOTD"), String::from("first line\nsecond line")),
+/// ]);
+/// ```
+pub fn parse_env_output(output: &str) -> Vec<(String, String)> {
+    let mut entries: Vec<(String, String)> = Vec::new();
+    for line in output.lines() {
+        match line.split_once('=') {
+            Some((name, value)) if !name.contains(char::is_whitespace) => entries.push((name.to_string(), value.to_string())),
+            _ => match entries.last_mut() {
+                Some((_, value)) => {
+                    value.push('\n');
+                    value.push_str(line);
+                }
+                None if line.trim().is_empty() => (),
+                None => entries.push((String::new(), line.to_string())),
+            },
+        }
+    }
+    entries.retain(|(name, _)| is_var(name));
+    entries
+}
+
+/// Sets globally every variable of the output of `env` or `set` (see [`parse_env_output`]).
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{import_env_output, ImportOptions};
+/// let captured = std::fs::read_to_string("old-laptop-env.txt").unwrap();
+/// let results = import_env_output(&captured, &ImportOptions::new().overwrite(false));
+/// ```
+pub fn import_env_output(output: &str, options: &ImportOptions) -> Vec<ImportResult> {
+    import_env_output_in(&Target::default(), output, options)
+}
+
+/// Sets in the given target every variable of the output of `env` or `set`, reporting what happened to each.
+pub fn import_env_output_in(target: &Target, output: &str, options: &ImportOptions) -> Vec<ImportResult> {
+    dotenv::apply(target, parse_env_output(output), options)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_captured_environments() {
+        let env = "BASH_FUNC_greet%%=() {  echo hello\n}\nPATH=/usr/bin:/bin\nNOTE=a = b\n c\n_=/usr/bin/env\n";
+        assert_eq!(parse_env_output(env), [
+            (String::from("PATH"), String::from("/usr/bin:/bin")),
+            (String::from("NOTE"), String::from("a = b\n c")),
+        ]);
+        let set = "=C:=C:\\Users\\me\r\nProgramFiles(x86)=C:\\Program Files (x86)\r\nPROMPT=$P$G\r\n";
+        assert_eq!(parse_env_output(set), [
+            (String::from("ProgramFiles(x86)"), String::from("C:\\Program Files (x86)")),
+            (String::from("PROMPT"), String::from("$P$G")),
+        ]);
+    }
+}
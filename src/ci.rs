@@ -0,0 +1,178 @@
+//! Propagation of variables to the next steps or jobs of CI pipelines.
+
+use crate::{set_var_in, EnvError, Target};
+use std::{env, fs::OpenOptions, io::Write};
+
+/// Dotenv report file declared as `artifacts:reports:dotenv` in `.gitlab-ci.yml`.
+pub const GITLAB_DOTENV_FILE: &str = "build.env";
+
+/// CI systems whose next steps can receive variables.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum CiBackend {
+    /// GitHub Actions: `$GITHUB_ENV` and `$GITHUB_PATH` files
+    GitHub,
+    /// GitLab CI: dotenv report artifact (`build.env`, in the working directory),
+    /// passed to the later jobs
+    GitLab,
+    /// Azure Pipelines: `##vso[task.setvariable]` logging commands on the standard output
+    Azure,
+}
+
+impl CiBackend {
+    /// The CI system the process runs in, if any.
+    pub fn detect() -> Option<CiBackend> {
+        if env::var("GITHUB_ACTIONS").as_deref() == Ok("true") {
+            return Some(CiBackend::GitHub);
+        }
+        if env::var_os("GITLAB_CI").is_some() {
+            return Some(CiBackend::GitLab);
+        }
+        if env::var("TF_BUILD").is_ok_and(|v| v.eq_ignore_ascii_case("true")) {
+            return Some(CiBackend::Azure);
+        }
+        None
+    }
+
+    /// Sets a variable for the next steps of the job, and in the current process.
+    pub fn set_var(&self, var: &str, value: &str) -> Result<(), EnvError> {
+        self.set_var_in(&Target::default(), var, value)
+    }
+
+    /// Sets a variable for the next steps of the job if the name is valid and the policy of the given target
+    /// permits it.
+    pub fn set_var_in(&self, target: &Target, var: &str, value: &str) -> Result<(), EnvError> {
+        target.check(var)?;
+        match self {
+            CiBackend::GitHub => {
+                if is_path(var) {
+                    // Directories written to $GITHUB_PATH are prepended to PATH by the runner
+                    let mut added = String::new();
+                    for dir in path_additions(value)? {
+                        added.push_str(&dir);
+                        added.push('\n');
+                    }
+                    append(&env::var("GITHUB_PATH")?, &added)?;
+                } else {
+                    append(&env::var("GITHUB_ENV")?, &github_env_entry(var, value))?;
+                }
+            }
+            CiBackend::GitLab => append(GITLAB_DOTENV_FILE, &gitlab_dotenv_entry(var, value)?)?,
+            CiBackend::Azure => {
+                let mut stdout = std::io::stdout().lock();
+                if is_path(var) {
+                    for dir in path_additions(value)? {
+                        writeln!(stdout, "##vso[task.prependpath]{}", azure_escape(&dir))?;
+                    }
+                } else {
+                    writeln!(stdout, "{}", azure_set_variable(var, value))?;
+                }
+            }
+        }
+        env::set_var(var, value);
+        Ok(())
+    }
+}
+
+fn is_path(var: &str) -> bool {
+    var == "PATH" || cfg!(target_os = "windows") && var.eq_ignore_ascii_case("PATH")
+}
+
+/// Directories of the new PATH value which aren't in the current one.
+fn path_additions(value: &str) -> Result<Vec<String>, EnvError> {
+    let current: Vec<_> = env::var_os("PATH").map(|p| env::split_paths(&p).collect()).unwrap_or_default();
+    env::split_paths(value)
+        .filter(|dir| !current.contains(dir))
+        .map(|dir| dir.to_str().map(String::from).ok_or(EnvError::VarError))
+        .collect()
+}
+
+fn append(path: &str, content: &str) -> Result<(), EnvError> {
+    OpenOptions::new().append(true).create(true).open(path)?.write_all(content.as_bytes())?;
+    Ok(())
+}
+
+/// `NAME<<DELIMITER` heredoc, with a delimiter which doesn't appear in the value.
+fn github_env_entry(var: &str, value: &str) -> String {
+    let mut delimiter = String::from("GLOBALENV_EOF");
+    let mut n = 0;
+    while value.lines().any(|line| line == delimiter) {
+        n += 1;
+        delimiter = format!("GLOBALENV_EOF_{}", n);
+    }
+    format!("{}<<{}\n{}\n{}\n", var, delimiter, value, delimiter)
+}
+
+/// `KEY=value` line of a GitLab dotenv report, which takes values literally and supports
+/// neither multiline values nor names other than letters, digits and underscores.
+fn gitlab_dotenv_entry(var: &str, value: &str) -> Result<String, EnvError> {
+    if var.is_empty() || !var.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+        return Err(EnvError::InvalidName(String::from("not made of letters, digits and underscores")));
+    }
+    if value.contains(['\n', '\r']) {
+        return Err(EnvError::ValidationError(String::from("a GitLab dotenv report can't store multiline values")));
+    }
+    Ok(format!("{}={}\n", var, value))
+}
+
+/// Escapes the data of an Azure Pipelines logging command.
+fn azure_escape(s: &str) -> String {
+    s.replace('%', "%AZP25").replace('\r', "%0D").replace('\n', "%0A")
+}
+
+fn azure_set_variable(var: &str, value: &str) -> String {
+    let var = azure_escape(var).replace(';', "%3B").replace(']', "%5D");
+    format!("##vso[task.setvariable variable={}]{}", var, azure_escape(value))
+}
+
+/// Sets a variable for the next steps of the CI job when running in one (see [`CiBackend::detect`]),
+/// globally otherwise: the same code works on dev machines and CI runners.
+/// Example:
+/// ```rust,no_run
+/// globalenv::set_var_ci("ENVTEST", "TESTVALUE").unwrap();
+/// ```
+pub fn set_var_ci(var: &str, value: &str) -> Result<(), EnvError> {
+    set_var_ci_in(&Target::default(), var, value)
+}
+
+/// Sets a variable for the next steps of the CI job, or in the given target outside of CI.
+pub fn set_var_ci_in(target: &Target, var: &str, value: &str) -> Result<(), EnvError> {
+    match CiBackend::detect() {
+        Some(backend) => backend.set_var_in(target, var, value),
+        None => set_var_in(target, var, value),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn github_heredoc_avoids_delimiter() {
+        assert_eq!(github_env_entry("A", "1"), "A<<GLOBALENV_EOF\n1\nGLOBALENV_EOF\n");
+        assert_eq!(
+            github_env_entry("A", "x\nGLOBALENV_EOF\ny"),
+            "A<<GLOBALENV_EOF_1\nx\nGLOBALENV_EOF\ny\nGLOBALENV_EOF_1\n"
+        );
+    }
+
+    #[test]
+    fn gitlab_dotenv_is_literal() {
+        assert_eq!(gitlab_dotenv_entry("URL", "http://host/?a=1 b").unwrap(), "URL=http://host/?a=1 b\n");
+        assert!(matches!(gitlab_dotenv_entry("A", "x\ny"), Err(EnvError::ValidationError(_))));
+        assert!(matches!(gitlab_dotenv_entry("A.B", "1"), Err(EnvError::InvalidName(_))));
+    }
+
+    #[test]
+    fn checks_names() {
+        let target = Target::new().policy(crate::Policy::new().deny(["DENIED_*"]));
+        assert!(matches!(CiBackend::Azure.set_var_in(&target, "A=B", "1"), Err(EnvError::InvalidName(_))));
+        assert_eq!(CiBackend::Azure.set_var_in(&target, "DENIED_CI", "1"), Err(EnvError::PolicyError));
+    }
+
+    #[test]
+    fn azure_logging_command_is_escaped() {
+        assert_eq!(azure_set_variable("A", "1"), "##vso[task.setvariable variable=A]1");
+        assert_eq!(azure_set_variable("A]", "50%\nx"), "##vso[task.setvariable variable=A%5D]50%AZP25%0Ax");
+    }
+}
@@ -0,0 +1,166 @@
+//! Conditional definitions, for startup files shared across machines (e.g. dotfiles): the definition is
+//! wrapped in an `if` guard on the same line, which the shell evaluates.
+
+use crate::{
+    meta::Meta,
+    syntax::{quote_fish, quote_posix, quote_powershell, unquote_fish, unquote_posix, unquote_powershell, Syntax},
+    EnvError,
+};
+use std::{env, fmt};
+
+/// Where a definition applies, see [`SetOptions::condition`](crate::SetOptions::condition).
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Condition {
+    /// On this operating system, named as [`std::env::consts::OS`] does (`macos`, `linux`, `freebsd`...)
+    Os(String),
+    /// On the machine with this host name
+    Hostname(String),
+}
+
+/// `uname -s` of the operating systems, and their name for .NET (`RuntimeInformation.IsOSPlatform`).
+const SYSTEMS: &[(&str, &str, Option<&str>)] = &[
+    ("macos", "Darwin", Some("OSX")),
+    ("linux", "Linux", Some("Linux")),
+    ("freebsd", "FreeBSD", Some("FreeBSD")),
+    ("netbsd", "NetBSD", None),
+    ("openbsd", "OpenBSD", None),
+    ("dragonfly", "DragonFly", None),
+    ("illumos", "SunOS", None),
+    ("haiku", "Haiku", None),
+    ("windows", "Windows_NT", Some("Windows")),
+];
+
+/// Host name of this machine.
+pub(crate) fn hostname() -> Option<String> {
+    #[cfg(target_family = "unix")]
+    {
+        let mut name = [0u8; 256];
+        if unsafe { libc::gethostname(name.as_mut_ptr() as *mut libc::c_char, name.len()) } != 0 {
+            return None;
+        }
+        let len = name.iter().position(|b| *b == 0).unwrap_or(name.len());
+        String::from_utf8(name[..len].to_vec()).ok()
+    }
+    #[cfg(not(target_family = "unix"))]
+    env::var("COMPUTERNAME").ok()
+}
+
+impl Condition {
+    /// Whether the condition holds on this machine.
+    pub fn holds(&self) -> bool {
+        match self {
+            Condition::Os(os) => os == env::consts::OS,
+            Condition::Hostname(name) => hostname().is_some_and(|host| host.eq_ignore_ascii_case(name)),
+        }
+    }
+
+    /// Parses the `os:<name>` and `host:<name>` form of the metadata comments.
+    pub(crate) fn parse(s: &str) -> Option<Condition> {
+        match s.split_once(':')? {
+            ("os", os) => Some(Condition::Os(os.to_string())),
+            ("host", name) => Some(Condition::Hostname(name.to_string())),
+            _ => None,
+        }
+    }
+}
+
+impl fmt::Display for Condition {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Condition::Os(os) => write!(f, "os:{}", os),
+            Condition::Hostname(name) => write!(f, "host:{}", name),
+        }
+    }
+}
+
+/// Text of the guard before the condition's value, between it and the definition, and after the definition.
+fn parts(syntax: Syntax, host: bool) -> Option<(&'static str, &'static str, &'static str)> {
+    match (syntax, host) {
+        (Syntax::Posix, false) => Some((r#"if [ "$(uname -s)" = "#, " ]; then ", "; fi")),
+        (Syntax::Posix, true) => Some((r#"if [ "$(uname -n)" = "#, " ]; then ", "; fi")),
+        (Syntax::Csh, false) => Some(("if ( \"`uname -s`\" == ", " ) ", "")),
+        (Syntax::Csh, true) => Some(("if ( \"`uname -n`\" == ", " ) ", "")),
+        (Syntax::Fish, false) => Some(("if test (uname -s) = ", "; ", "; end")),
+        (Syntax::Fish, true) => Some(("if test (uname -n) = ", "; ", "; end")),
+        (Syntax::PowerShell, false) => Some(("if ([Runtime.InteropServices.RuntimeInformation]::IsOSPlatform(", ")) { ", " }")),
+        (Syntax::PowerShell, true) => Some(("if ([Environment]::MachineName -eq ", ") { ", " }")),
+        (Syntax::Ion, _) => None,
+    }
+}
+
+/// The definition in an `if` guard, `None` if the shell (ion) or the operating system can't be tested.
+pub(crate) fn guard(syntax: Syntax, condition: &Condition, definition: &str) -> Option<String> {
+    let (value, host) = match condition {
+        Condition::Os(os) => {
+            let (_, uname, dotnet) = SYSTEMS.iter().find(|(name, ..)| name == os)?;
+            (if syntax == Syntax::PowerShell { (*dotnet)? } else { uname }, false)
+        }
+        Condition::Hostname(name) => (name.as_str(), true),
+    };
+    let (before, between, after) = parts(syntax, host)?;
+    let quoted = match syntax {
+        Syntax::Fish => quote_fish(value),
+        Syntax::PowerShell => quote_powershell(value),
+        _ => quote_posix(value),
+    };
+    Some(format!("{}{}{}{}{}", before, quoted, between, definition, after))
+}
+
+/// The condition and the definition of a line written by [`guard`], `None` for other lines.
+pub(crate) fn unguard(syntax: Syntax, line: &str) -> Option<(Condition, &str)> {
+    let line = line.trim();
+    [false, true].into_iter().find_map(|host| {
+        let (before, between, after) = parts(syntax, host)?;
+        let (value, definition) = line.strip_prefix(before)?.strip_suffix(after)?.split_once(between)?;
+        let value = match syntax {
+            Syntax::Fish => unquote_fish(value),
+            Syntax::PowerShell => unquote_powershell(value),
+            _ => unquote_posix(value),
+        }
+        .unwrap_or_else(|| value.to_string());
+        let condition = match host {
+            true => Condition::Hostname(value),
+            false => {
+                let (os, ..) = SYSTEMS.iter().find(|(_, uname, dotnet)| match syntax {
+                    Syntax::PowerShell => *dotnet == Some(value.as_str()),
+                    _ => *uname == value,
+                })?;
+                Condition::Os(os.to_string())
+            }
+        };
+        Some((condition, definition))
+    })
+}
+
+/// Whether a definition with this metadata applies on this machine, its condition (if any) being checked
+/// against the syntax of the file it is written to: ion can't test it.
+pub(crate) fn applies(meta: &Meta, syntax: Option<Syntax>) -> Result<bool, EnvError> {
+    let Some(condition) = &meta.when else { return Ok(true) };
+    if let Some(syntax) = syntax {
+        guard(syntax, condition, "").ok_or(EnvError::UnsupportedShell)?;
+    }
+    Ok(condition.holds())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn guards_definitions() {
+        let macos = Condition::Os(String::from("macos"));
+        let guarded = guard(Syntax::Posix, &macos, "export A=1").unwrap();
+        assert_eq!(guarded, r#"if [ "$(uname -s)" = Darwin ]; then export A=1; fi"#);
+        assert_eq!(unguard(Syntax::Posix, &guarded), Some((macos.clone(), "export A=1")));
+        let host = Condition::Hostname(String::from("work laptop"));
+        let guarded = guard(Syntax::Fish, &host, "set -gx A 1").unwrap();
+        assert_eq!(guarded, "if test (uname -n) = 'work laptop'; set -gx A 1; end");
+        assert_eq!(unguard(Syntax::Fish, &guarded), Some((host, "set -gx A 1")));
+        let guarded = guard(Syntax::PowerShell, &macos, "$env:A = '1'").unwrap();
+        assert_eq!(unguard(Syntax::PowerShell, &guarded).map(|(c, _)| c), Some(macos.clone()));
+        assert_eq!(guard(Syntax::Ion, &macos, "export A = 1"), None);
+        assert_eq!(unguard(Syntax::Posix, "if [ -d /opt ]; then export A=1; fi"), None);
+        assert_eq!(Condition::parse(&macos.to_string()), Some(macos));
+    }
+}
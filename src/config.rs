@@ -0,0 +1,131 @@
+//! Defaults of the process, configured once by the application instead of being passed to every call.
+
+use crate::{EnvError, Scope};
+use std::{fs, path::Path, sync::OnceLock};
+
+/// How values are quoted in the startup files.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum QuoteStyle {
+    /// Only the values with characters the shell would interpret (`export A=1`, `export B='a b'`)
+    #[default]
+    Minimal,
+    /// Every value (`export A='1'`), as some linters and style guides require
+    Always,
+}
+
+/// Whether a startup file is backed up to `<file>.globalenv.bak` before it is rewritten.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Backup {
+    #[default]
+    Never,
+    /// Before its first rewrite: the backup keeps the file as it was before this crate changed it
+    Once,
+    /// Before each rewrite: the backup is the file as it was before the last change
+    Always,
+}
+
+/// Where new definitions go in a startup file.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum BlockMode {
+    /// In the block delimited by the `# >>> globalenv >>>` markers, created at the end of the file
+    #[default]
+    Managed,
+    /// Without markers, for files kept by hand or by another tool: an existing definition is replaced where it
+    /// is, a new one appended at the end. The variables written so are not told apart from the user's own,
+    /// e.g. by [`managed_vars`](crate::managed_vars). A file which has a managed block keeps using it.
+    Inline,
+}
+
+/// Defaults of the operations of this process, see [`GlobalEnvConfig::set_defaults`].
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct GlobalEnvConfig {
+    pub(crate) quote_style: QuoteStyle,
+    pub(crate) backup: Backup,
+    pub(crate) block_mode: BlockMode,
+    pub(crate) scope: Scope,
+}
+
+/// Configuration used until (and unless) the application sets its own.
+const DEFAULT: GlobalEnvConfig =
+    GlobalEnvConfig { quote_style: QuoteStyle::Minimal, backup: Backup::Never, block_mode: BlockMode::Managed, scope: Scope::User };
+
+static DEFAULTS: OnceLock<GlobalEnvConfig> = OnceLock::new();
+
+impl GlobalEnvConfig {
+    pub fn new() -> GlobalEnvConfig {
+        GlobalEnvConfig::default()
+    }
+
+    pub fn quote_style(mut self, quote_style: QuoteStyle) -> GlobalEnvConfig {
+        self.quote_style = quote_style;
+        self
+    }
+
+    pub fn backup(mut self, backup: Backup) -> GlobalEnvConfig {
+        self.backup = backup;
+        self
+    }
+
+    pub fn block_mode(mut self, block_mode: BlockMode) -> GlobalEnvConfig {
+        self.block_mode = block_mode;
+        self
+    }
+
+    /// Scope of the default target, i.e. of the functions without a `Target` (and of [`Target::new`](crate::Target::new)).
+    pub fn scope(mut self, scope: Scope) -> GlobalEnvConfig {
+        self.scope = scope;
+        self
+    }
+
+    /// Sets the defaults of the process, once, preferably at startup: the operations which already ran used the
+    /// crate's. Returns the given configuration as an error if the defaults were already set.
+    /// Example:
+    /// ```rust
+    /// use globalenv::{Backup, GlobalEnvConfig, QuoteStyle};
+    /// GlobalEnvConfig::set_defaults(GlobalEnvConfig::new().quote_style(QuoteStyle::Always).backup(Backup::Once)).unwrap();
+    /// assert_eq!(GlobalEnvConfig::defaults(), &GlobalEnvConfig::new().quote_style(QuoteStyle::Always).backup(Backup::Once));
+    /// ```
+    pub fn set_defaults(config: GlobalEnvConfig) -> Result<(), GlobalEnvConfig> {
+        DEFAULTS.set(config)
+    }
+
+    /// Defaults of the process: the ones set with [`GlobalEnvConfig::set_defaults`], the crate's otherwise.
+    pub fn defaults() -> &'static GlobalEnvConfig {
+        DEFAULTS.get().unwrap_or(&DEFAULT)
+    }
+}
+
+/// Backs the file up before it is rewritten, as configured.
+pub(crate) fn backup(path: &Path) -> Result<(), EnvError> {
+    let backup = GlobalEnvConfig::defaults().backup;
+    let mut file_name = path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(".globalenv.bak");
+    let backup_path = path.with_file_name(file_name);
+    let needed = match backup {
+        Backup::Never => false,
+        Backup::Once => !backup_path.exists(),
+        Backup::Always => true,
+    };
+    if needed && path.is_file() {
+        fs::copy(path, &backup_path)?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sets_defaults_once() {
+        assert_eq!(GlobalEnvConfig::defaults(), &GlobalEnvConfig::new());
+        assert!(GlobalEnvConfig::set_defaults(GlobalEnvConfig::new()).is_ok());
+        let config = GlobalEnvConfig::new().block_mode(BlockMode::Inline).scope(Scope::System);
+        assert_eq!(GlobalEnvConfig::set_defaults(config.clone()), Err(config));
+        assert_eq!(GlobalEnvConfig::defaults().block_mode, BlockMode::Managed);
+    }
+}
@@ -0,0 +1,235 @@
+//! Definitions of a variable across the startup files of the usual shells, and the one a new shell ends up with.
+
+use crate::{stored_var_in, syntax::Syntax, target::Store, EnvError, Scope, Shell, Target};
+use std::{fs, path::{Path, PathBuf}};
+
+/// A line defining the variable.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Definition {
+    pub path: PathBuf,
+    /// Line number, from 1
+    pub line: usize,
+    pub value: String,
+    /// The file is read after the target's one by the shell, and overrides its value
+    pub overrides: bool,
+}
+
+const SYSTEM_FILES: [&str; 10] = [
+    "/etc/environment",
+    "/etc/profile",
+    "/etc/bash.bashrc",
+    "/etc/bashrc",
+    "/etc/zshenv",
+    "/etc/zsh/zshenv",
+    "/etc/zprofile",
+    "/etc/zsh/zprofile",
+    "/etc/zshrc",
+    "/etc/zsh/zshrc",
+];
+
+const HOME_FILES: [&str; 8] = [".profile", ".bash_profile", ".bash_login", ".bashrc", ".zshenv", ".zprofile", ".zshrc", ".zlogin"];
+
+/// Files in the order an interactive login shell reads them.
+fn read_order(shell: Shell, home: &Path) -> Vec<PathBuf> {
+    let (system, user): (&[&str], &[&str]) = match shell {
+        Shell::Sh | Shell::Ksh => (&["/etc/environment", "/etc/profile"], &[".profile"]),
+        Shell::Bash => (&["/etc/environment", "/etc/profile"], &[]),
+        Shell::Zsh => (&["/etc/environment", "/etc/zshenv", "/etc/zsh/zshenv"], &[".zshenv"]),
+        Shell::Tcsh | Shell::Fish | Shell::PowerShell | Shell::Ion => (&[], &[]),
+    };
+    let mut files: Vec<PathBuf> = system.iter().map(PathBuf::from).chain(user.iter().map(|f| home.join(f))).collect();
+    match shell {
+        Shell::Bash => {
+            // Login shells only read the first existing of these files
+            let login = [".bash_profile", ".bash_login", ".profile"].iter().map(|f| home.join(f)).find(|p| p.exists());
+            files.extend(login);
+            files.extend(["/etc/bash.bashrc", "/etc/bashrc"].iter().map(PathBuf::from));
+            files.push(home.join(".bashrc"));
+        }
+        Shell::Zsh => {
+            files.extend(["/etc/zprofile", "/etc/zsh/zprofile"].iter().map(PathBuf::from));
+            files.push(home.join(".zprofile"));
+            files.extend(["/etc/zshrc", "/etc/zsh/zshrc"].iter().map(PathBuf::from));
+            files.extend([".zshrc", ".zlogin"].iter().map(|f| home.join(f)));
+        }
+        _ => (),
+    }
+    files
+}
+
+/// Every definition in these files, the ones read after the target file by the shell (in `order`) with
+/// another value being flagged.
+fn scan(files: &[PathBuf], order: &[PathBuf], target_file: Option<&Path>, value: Option<&str>, var: &str) -> Vec<Definition> {
+    let position = |path: &Path| order.iter().position(|p| p == path);
+    let target_position = target_file.and_then(position);
+    let mut definitions = Vec::new();
+    for path in files {
+        let Ok(content) = fs::read_to_string(path) else { continue };
+        let later = matches!((target_position, position(path)), (Some(target), Some(p)) if p > target);
+        for (i, line) in content.lines().enumerate() {
+            if let Some((_, found)) = Syntax::Posix.parse(line).filter(|(name, _)| *name == var) {
+                let overrides = later && value != Some(found.as_str());
+                definitions.push(Definition { path: path.clone(), line: i + 1, value: found, overrides });
+            }
+        }
+    }
+    definitions
+}
+
+/// Reports every definition of the variable in the startup files of sh, bash and zsh (in the home directory
+/// and in `/etc`), flagging those which override, for the detected shell, the value persisted by the crate.
+/// Example:
+/// ```rust,no_run
+/// for definition in globalenv::find_conflicts("JAVA_HOME").unwrap().iter().filter(|d| d.overrides) {
+///     println!("{}:{} overrides JAVA_HOME", definition.path.display(), definition.line);
+/// }
+/// ```
+pub fn find_conflicts(var: &str) -> Result<Vec<Definition>, EnvError> {
+    find_conflicts_in(&Target::default(), var)
+}
+
+/// Reports every definition of the variable in the startup files, flagging those which override, for the
+/// target's shell, the value persisted in the target.
+pub fn find_conflicts_in(target: &Target, var: &str) -> Result<Vec<Definition>, EnvError> {
+    let home = target.get_home()?;
+    let mut files: Vec<PathBuf> = SYSTEM_FILES.iter().map(PathBuf::from).collect();
+    files.extend(HOME_FILES.iter().map(|f| home.join(f)));
+    let target_file = match target.store()? {
+        Store::File(path, _) => {
+            if !files.contains(&path) {
+                files.push(path.clone());
+            }
+            Some(path)
+        }
+        #[cfg(target_os = "windows")]
+        Store::Registry(_) => None,
+    };
+    let order = match target.get_shell().or_else(|| Shell::detect().ok()) {
+        Some(shell) => read_order(shell, &home),
+        None => Vec::new(),
+    };
+    let value = stored_var_in(target, var)?;
+    Ok(scan(&files, &order, target_file.as_deref(), value.as_deref(), var))
+}
+
+/// Files a new shell reads for the target, in order, with their syntax: the startup files of the target's
+/// (or detected) shell which include the target's file, or only the target's file.
+pub(crate) fn read_files(target: &Target, path: PathBuf, syntax: Syntax) -> Vec<(PathBuf, Syntax)> {
+    if target.has_file() || target.get_scope() != Scope::User {
+        return vec![(path, syntax)];
+    }
+    let order = match (target.get_shell().or_else(|| Shell::detect().ok()), target.get_home()) {
+        (Some(shell), Ok(home)) => read_order(shell, &home),
+        _ => Vec::new(),
+    };
+    if !order.contains(&path) {
+        return vec![(path, syntax)];
+    }
+    order.into_iter().map(|p| if p == path { (p, syntax) } else { (p, Syntax::Posix) }).collect()
+}
+
+/// The last definition, in reading order, of the variable in these files.
+pub(crate) fn last_definition(files: &[(PathBuf, Syntax)], var: &str) -> Option<Definition> {
+    let mut last = None;
+    for (path, syntax) in files {
+        let Ok(content) = fs::read_to_string(path) else { continue };
+        for (i, line) in content.lines().enumerate() {
+            if let Some((_, value)) = syntax.parse(line).filter(|(name, _)| *name == var) {
+                last = Some((path, i, value));
+            }
+        }
+    }
+    last.map(|(path, i, value)| Definition { path: path.clone(), line: i + 1, value, overrides: false })
+}
+
+/// Finds the definition of the variable a new shell ends up with (see [`get_var`](crate::get_var)), searching
+/// the startup files of the detected shell in the order it reads them. Only startup files are searched: on
+/// Windows, the registry is not.
+/// Example:
+/// ```rust,no_run
+/// if let Some(definition) = globalenv::locate_var("JAVA_HOME").unwrap() {
+///     println!("JAVA_HOME={} ({}:{})", definition.value, definition.path.display(), definition.line);
+/// }
+/// ```
+pub fn locate_var(var: &str) -> Result<Option<Definition>, EnvError> {
+    locate_var_in(&Target::default(), var)
+}
+
+/// Finds the definition of the variable a new shell ends up with, for the given target.
+pub fn locate_var_in(target: &Target, var: &str) -> Result<Option<Definition>, EnvError> {
+    match target.store()? {
+        Store::File(path, syntax) => Ok(last_definition(&read_files(target, path, syntax), var)),
+        #[cfg(target_os = "windows")]
+        Store::Registry(_) => Ok(None),
+    }
+}
+
+/// Variables whose user value is appended to the machine one, instead of replacing it, in the environment
+/// Windows gives new processes.
+const CONCATENATED: [&str; 3] = ["Path", "LibPath", "Os2LibPath"];
+
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn merged(var: &str, machine: Option<String>, user: Option<String>) -> Option<String> {
+    match (machine, user) {
+        (Some(machine), Some(user)) if CONCATENATED.iter().any(|v| v.eq_ignore_ascii_case(var)) => {
+            let sep = if machine.is_empty() || machine.ends_with(';') { "" } else { ";" };
+            Some(format!("{}{}{}", machine, sep, user))
+        }
+        (machine, user) => user.or(machine),
+    }
+}
+
+/// The value of the variable a new process gets. On Windows, it is the user value, or the machine one if
+/// there is none, except for `Path` whose user value is appended to the machine one (`%VAR%` references are not
+/// expanded). On Unix, it is the value of the definition found by [`locate_var`].
+/// Example:
+/// ```rust,no_run
+/// println!("{:?}", globalenv::resolve_var("Path").unwrap());
+/// ```
+pub fn resolve_var(var: &str) -> Result<Option<String>, EnvError> {
+    resolve_var_in(&Target::default(), var)
+}
+
+/// The value of the variable a new process gets, for the given target: on Windows, the service scope
+/// resolves the machine value with the one of `HKU\.DEFAULT`, the others with the one of the current user.
+pub fn resolve_var_in(target: &Target, var: &str) -> Result<Option<String>, EnvError> {
+    match target.store()? {
+        Store::File(..) => Ok(locate_var_in(target, var)?.map(|definition| definition.value)),
+        #[cfg(target_os = "windows")]
+        Store::Registry(scope) => {
+            let own = if scope == Scope::Service { Scope::Service } else { Scope::User };
+            Ok(merged(var, crate::registry::get(&Scope::System, var)?, crate::registry::get(&own, var)?))
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn merges_machine_and_user_values() {
+        let value = |s: &str| Some(String::from(s));
+        assert_eq!(merged("PATH", value(r"C:\Windows"), value(r"C:\bin")), value(r"C:\Windows;C:\bin"));
+        assert_eq!(merged("Path", value(r"C:\Windows;"), value(r"C:\bin")), value(r"C:\Windows;C:\bin"));
+        assert_eq!(merged("TEMP", value(r"C:\Temp"), value(r"D:\Temp")), value(r"D:\Temp"));
+        assert_eq!(merged("TEMP", value(r"C:\Temp"), None), value(r"C:\Temp"));
+    }
+
+    #[test]
+    fn flags_later_definitions() {
+        let home = std::env::temp_dir().join("globalenv_conflicts_home");
+        fs::create_dir_all(&home).unwrap();
+        fs::write(home.join(".zshenv"), "export EDITOR=vim\n").unwrap();
+        fs::write(home.join(".zshrc"), "# editor\nexport EDITOR=nano\nexport EDITOR=vim\n").unwrap();
+        let files = [home.join(".zshrc"), home.join(".zshenv")];
+        let definitions = scan(&files, &read_order(Shell::Zsh, &home), Some(&home.join(".zshenv")), Some("vim"), "EDITOR");
+        let flagged: Vec<_> = definitions.iter().map(|d| (d.line, d.overrides)).collect();
+        assert_eq!(flagged, [(2, true), (3, false), (1, false)]);
+        assert_eq!(definitions[0].value, "nano");
+        let files = [(home.join(".zshenv"), Syntax::Posix), (home.join(".zshrc"), Syntax::Posix)];
+        assert_eq!(last_definition(&files, "EDITOR").map(|d| (d.line, d.value)), Some((3, String::from("vim"))));
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
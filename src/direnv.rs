@@ -0,0 +1,93 @@
+//! Approval of the `.envrc` files written, which direnv doesn't load until they are allowed.
+
+use crate::{target::Store, EnvError, Target};
+use std::{
+    fmt, io,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// Why `direnv allow` didn't approve a file, which is written anyway but not loaded by direnv.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum DirenvWarning {
+    /// direnv isn't installed (or not in `PATH`)
+    NotInstalled,
+    /// `direnv allow` failed, with this exit code (`None` if killed by a signal) and error output
+    Failed(Option<i32>, String),
+}
+
+impl fmt::Display for DirenvWarning {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            DirenvWarning::NotInstalled => f.write_str("direnv is not installed, the .envrc is not allowed"),
+            DirenvWarning::Failed(Some(code), output) => write!(f, "direnv allow exited with code {}: {}", code, output),
+            DirenvWarning::Failed(None, output) => write!(f, "direnv allow failed: {}", output),
+        }
+    }
+}
+
+fn is_envrc(path: &Path) -> bool {
+    path.file_name().is_some_and(|name| name == ".envrc")
+}
+
+/// Runs `direnv allow` on the file.
+fn allow(path: &Path) -> Option<DirenvWarning> {
+    let output = Command::new("direnv").arg("allow").arg(path).stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::piped()).output();
+    match output {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Some(DirenvWarning::NotInstalled),
+        Err(e) => Some(DirenvWarning::Failed(None, e.to_string())),
+        Ok(output) if output.status.success() => None,
+        Ok(output) => Some(DirenvWarning::Failed(output.status.code(), String::from_utf8_lossy(&output.stderr).trim().to_string())),
+    }
+}
+
+/// Approves the `.envrc` just written if the target says so, returning why direnv didn't.
+pub(crate) fn approve(target: &Target, path: &Path) -> Option<DirenvWarning> {
+    (target.get_direnv() && is_envrc(path)).then(|| allow(path)).flatten()
+}
+
+/// Outcome of a write once it is applied: [`EnvError::NotApproved`] if direnv didn't approve the file.
+pub(crate) fn approved(warning: Option<DirenvWarning>) -> Result<(), EnvError> {
+    warning.map_or(Ok(()), |warning| Err(EnvError::NotApproved(warning)))
+}
+
+/// Runs `direnv allow` on the `.envrc` of the default target (see [`Scope::Project`](crate::Scope::Project)), so
+/// that direnv loads what was written. Returns why it didn't approve it, if it didn't (e.g. direnv isn't
+/// installed); nothing is done for other files.
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{allow_direnv_in, set_var_in, Scope, Target};
+/// let project = Target::new().scope(Scope::Project(std::env::current_dir().unwrap()));
+/// set_var_in(&project, "DATABASE_URL", "postgres://localhost/dev").unwrap();
+/// if let Some(warning) = allow_direnv_in(&project).unwrap() {
+///     eprintln!("warning: {}", warning);
+/// }
+/// ```
+pub fn allow_direnv() -> Result<Option<DirenvWarning>, EnvError> {
+    allow_direnv_in(&Target::default())
+}
+
+/// Runs `direnv allow` on the `.envrc` of the given target.
+pub fn allow_direnv_in(target: &Target) -> Result<Option<DirenvWarning>, EnvError> {
+    match target.store()? {
+        Store::File(path, _) if is_envrc(&path) => Ok(allow(&path)),
+        _ => Ok(None),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn only_allows_envrc() {
+        assert!(is_envrc(Path::new("/src/app/.envrc")) && !is_envrc(Path::new("/src/app/.env")));
+        let target = Target::new().file(std::env::temp_dir().join("globalenv_direnv_env"));
+        assert_eq!(allow_direnv_in(&target), Ok(None));
+        assert_eq!(approve(&target.direnv(true), Path::new("/src/app/.env")), None);
+        let warning = DirenvWarning::NotInstalled;
+        assert_eq!(approved(Some(warning.clone())), Err(EnvError::NotApproved(warning)));
+        assert_eq!(DirenvWarning::Failed(Some(1), String::from("denied")).to_string(), "direnv allow exited with code 1: denied");
+    }
+}
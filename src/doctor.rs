@@ -0,0 +1,169 @@
+//! Diagnostics of the environment the crate operates in.
+
+use crate::{target::HAIKU_PROFILE, Scope, Shell, Target};
+use std::{env, fs, path::PathBuf};
+
+/// A shell startup file and the kinds of shells that read it.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct StartupFile {
+    pub path: PathBuf,
+    pub exists: bool,
+    /// Read by login shells
+    pub login: bool,
+    /// Read by interactive (non-login) shells
+    pub interactive: bool,
+}
+
+/// Report returned by [`doctor`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Diagnostics {
+    /// Value of the `SHELL` environment variable
+    pub shell_var: Option<String>,
+    /// Shell whose files are used (forced by the target, or detected)
+    pub shell: Option<Shell>,
+    /// Startup files of that shell
+    pub startup_files: Vec<StartupFile>,
+    /// File or registry key which would be written, if it can be resolved
+    pub target: Option<String>,
+    /// The target is read by new login shells
+    pub sourced_by_login: bool,
+    /// The target is read by new interactive shells
+    pub sourced_by_interactive: bool,
+    /// The environment registry key can be opened for writing (always `None` outside Windows)
+    pub registry_accessible: Option<bool>,
+    /// The process runs as root / with administrative privileges
+    pub elevated: bool,
+}
+
+/// Diagnoses the default target.
+pub fn doctor() -> Diagnostics {
+    doctor_in(&Target::default())
+}
+
+/// Diagnoses the given target: detected shell, existing startup files, file which would be written
+/// and whether new shells would actually read it, registry accessibility and elevation status.
+pub fn doctor_in(target: &Target) -> Diagnostics {
+    let shell = target.get_shell().or_else(|| Shell::detect().ok());
+    let startup_files = match (shell, target.get_home()) {
+        (Some(shell), Ok(home)) => startup_files(shell, &home),
+        _ => Vec::new(),
+    };
+
+    let location = target.location().ok();
+    let (sourced_by_login, sourced_by_interactive) = match location.as_deref() {
+        #[cfg(target_family = "unix")]
+        Some(location) if target.get_scope() != Scope::User && crate::target::system_file().to_str() == Some(location) => {
+            (true, cfg!(target_os = "linux"))
+        }
+        Some(location) => startup_files
+            .iter()
+            .find(|f| f.path.to_str() == Some(location))
+            .map_or((false, false), |f| (f.login, f.interactive)),
+        None => (false, false),
+    };
+    #[cfg(target_os = "windows")]
+    let (sourced_by_login, sourced_by_interactive) = match target.store() {
+        // Every new process inherits the registry environment
+        Ok(crate::target::Store::Registry(_)) => (true, true),
+        _ => (sourced_by_login, sourced_by_interactive),
+    };
+
+    Diagnostics {
+        shell_var: env::var("SHELL").ok(),
+        shell,
+        startup_files,
+        target: location,
+        sourced_by_login,
+        sourced_by_interactive,
+        registry_accessible: registry_accessible(target.get_scope()),
+        elevated: elevated(),
+    }
+}
+
+fn startup_files(shell: Shell, home: &std::path::Path) -> Vec<StartupFile> {
+    let file = |name: &str, login: bool, interactive: bool| {
+        let path = home.join(name);
+        StartupFile { exists: path.exists(), path, login, interactive }
+    };
+    match shell {
+        Shell::Sh | Shell::Bash if cfg!(target_os = "haiku") => vec![file(HAIKU_PROFILE, true, false)],
+        // Interactive shells only read the file named by $ENV (usually set in ~/.profile)
+        Shell::Sh | Shell::Ksh => {
+            let mut files = vec![file(".profile", true, false)];
+            if let Some(rc) = env::var_os("ENV").filter(|rc| !rc.is_empty()) {
+                files.push(file(&rc.to_string_lossy(), false, true));
+            }
+            files
+        }
+        Shell::Bash => {
+            // Login shells read the first existing of these files, which usually sources ~/.bashrc
+            let mut files = vec![
+                file(".bash_profile", false, false),
+                file(".bash_login", false, false),
+                file(".profile", false, false),
+            ];
+            let mut bashrc = file(".bashrc", false, true);
+            if let Some(login) = files.iter_mut().find(|f| f.exists) {
+                login.login = true;
+                bashrc.login = fs::read_to_string(&login.path).is_ok_and(|s| s.contains(".bashrc"));
+            }
+            files.push(bashrc);
+            files
+        }
+        Shell::Zsh => vec![
+            file(".zshenv", true, true),
+            file(".zprofile", true, false),
+            file(".zshrc", false, true),
+            file(".zlogin", true, false),
+        ],
+        Shell::Tcsh => {
+            // ~/.cshrc is only read when there is no ~/.tcshrc
+            let tcshrc = file(".tcshrc", true, true);
+            let cshrc_read = !tcshrc.exists;
+            vec![tcshrc, file(".cshrc", cshrc_read, cshrc_read), file(".login", true, false)]
+        }
+        Shell::Fish | Shell::PowerShell | Shell::Ion => vec![file(shell.env_file(), true, true)],
+    }
+}
+
+#[cfg(target_os = "windows")]
+fn registry_accessible(scope: Scope) -> Option<bool> {
+    Some(crate::registry::writable(&scope))
+}
+
+#[cfg(not(target_os = "windows"))]
+fn registry_accessible(_scope: Scope) -> Option<bool> {
+    None
+}
+
+#[cfg(target_os = "windows")]
+pub(crate) fn elevated() -> bool {
+    // Only administrators may write the machine environment
+    crate::registry::writable(&Scope::System)
+}
+
+#[cfg(target_family = "unix")]
+pub(crate) fn elevated() -> bool {
+    unsafe { libc::geteuid() == 0 }
+}
+
+#[cfg(not(any(target_os = "windows", target_family = "unix")))]
+pub(crate) fn elevated() -> bool {
+    false
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn explicit_file_is_not_sourced() {
+        let diagnostics = doctor_in(&Target::new().shell(Shell::Zsh).file("/tmp/globalenv_doctor"));
+        assert_eq!(diagnostics.shell, Some(Shell::Zsh));
+        assert_eq!(diagnostics.target.as_deref(), Some("/tmp/globalenv_doctor"));
+        assert!(!diagnostics.sourced_by_login && !diagnostics.sourced_by_interactive);
+        assert_eq!(diagnostics.startup_files.len(), 4);
+    }
+}
@@ -0,0 +1,341 @@
+//! Import and export of `.env` files, and of Docker env files.
+
+use crate::{filtered_vars_in, set_var_in, stored_var_in, EnvError, Filter, Target};
+use std::{fs, path::Path};
+
+/// Parses a `.env` file: `KEY=value` lines (optionally prefixed by `export`), blank lines and
+/// `#` comments. Values may be unquoted (with trailing ` # comments`), single-quoted (literal) or
+/// double-quoted (with `\n`, `\t`, `\"`... escapes), quoted values may span several lines.
+pub(crate) fn parse(content: &str) -> Result<Vec<(String, String)>, EnvError> {
+    let mut vars = Vec::new();
+    let mut chars = content.chars().peekable();
+    loop {
+        // Blank lines and comments
+        while chars.next_if(|c| c.is_whitespace()).is_some() {}
+        match chars.peek() {
+            None => break,
+            Some('#') => {
+                while chars.next_if(|c| *c != '\n').is_some() {}
+                continue;
+            }
+            Some(_) => (),
+        }
+
+        let mut name = String::new();
+        while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.')) {
+            name.push(c);
+        }
+        if name == "export" && chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {
+            name.clear();
+            while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+            while let Some(c) = chars.next_if(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.')) {
+                name.push(c);
+            }
+        }
+        while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+        if name.is_empty() || chars.next() != Some('=') {
+            return Err(EnvError::ParseError);
+        }
+        while chars.next_if(|c| *c == ' ' || *c == '\t').is_some() {}
+
+        let mut value = String::new();
+        match chars.peek() {
+            Some('\'') => {
+                chars.next();
+                loop {
+                    match chars.next().ok_or(EnvError::ParseError)? {
+                        '\'' => break,
+                        c => value.push(c),
+                    }
+                }
+            }
+            Some('"') => {
+                chars.next();
+                loop {
+                    match chars.next().ok_or(EnvError::ParseError)? {
+                        '"' => break,
+                        '\\' => match chars.next().ok_or(EnvError::ParseError)? {
+                            'n' => value.push('\n'),
+                            'r' => value.push('\r'),
+                            't' => value.push('\t'),
+                            c @ ('"' | '\\' | '$' | '\'') => value.push(c),
+                            c => { value.push('\\'); value.push(c) }
+                        },
+                        c => value.push(c),
+                    }
+                }
+            }
+            _ => {
+                while let Some(c) = chars.next_if(|c| *c != '\n') {
+                    // An unquoted value ends at an inline comment
+                    if c == '#' && value.ends_with([' ', '\t']) {
+                        while chars.next_if(|c| *c != '\n').is_some() {}
+                        break;
+                    }
+                    value.push(c);
+                }
+                value.truncate(value.trim_end().len());
+                vars.push((name, value));
+                continue;
+            }
+        }
+
+        // Only a comment may follow a quoted value
+        while chars.next_if(|c| *c == ' ' || *c == '\t' || *c == '\r').is_some() {}
+        match chars.peek() {
+            None | Some('\n') => (),
+            Some('#') => while chars.next_if(|c| *c != '\n').is_some() {},
+            Some(_) => return Err(EnvError::ParseError),
+        }
+        vars.push((name, value));
+    }
+    Ok(vars)
+}
+
+/// Renders `KEY=value` lines, double-quoting values which need it, with `$` escaped for the readers which
+/// interpolate (docker compose, python-dotenv).
+pub(crate) fn render(vars: &[(String, String)]) -> String {
+    let mut dotenv = String::new();
+    for (name, value) in vars {
+        dotenv.push_str(name);
+        dotenv.push('=');
+        if value.chars().any(|c| c.is_whitespace() || matches!(c, '"' | '\'' | '#' | '\\' | '$')) {
+            dotenv.push('"');
+            for c in value.chars() {
+                match c {
+                    '"' => dotenv.push_str("\\\""),
+                    '\\' => dotenv.push_str("\\\\"),
+                    '$' => dotenv.push_str("\\$"),
+                    '\n' => dotenv.push_str("\\n"),
+                    '\r' => dotenv.push_str("\\r"),
+                    '\t' => dotenv.push_str("\\t"),
+                    c => dotenv.push(c),
+                }
+            }
+            dotenv.push('"');
+        } else {
+            dotenv.push_str(value);
+        }
+        dotenv.push('\n');
+    }
+    dotenv
+}
+
+/// Options of [`import_dotenv`], [`import_reg`](crate::import_reg) and the other importers.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImportOptions {
+    overwrite: bool,
+}
+
+impl Default for ImportOptions {
+    fn default() -> ImportOptions {
+        ImportOptions { overwrite: true }
+    }
+}
+
+impl ImportOptions {
+    pub fn new() -> ImportOptions {
+        ImportOptions::default()
+    }
+
+    /// Whether variables already persisted with another value are overwritten (the default) or kept.
+    pub fn overwrite(mut self, overwrite: bool) -> ImportOptions {
+        self.overwrite = overwrite;
+        self
+    }
+
+    pub(crate) fn get_overwrite(&self) -> bool {
+        self.overwrite
+    }
+}
+
+/// What happened to an imported variable.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum ImportOutcome {
+    /// Persisted
+    Set,
+    /// Already persisted with the same value
+    Unchanged,
+    /// Already persisted with another value, which was kept
+    Skipped,
+    /// Could not be persisted
+    Failed(EnvError),
+}
+
+/// Report of the import of one variable.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct ImportResult {
+    pub name: String,
+    pub outcome: ImportOutcome,
+}
+
+/// Sets globally every variable of a `.env` file.
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{import_dotenv, ImportOptions, ImportOutcome};
+/// for result in import_dotenv(".env", &ImportOptions::new().overwrite(false)).unwrap() {
+///     if let ImportOutcome::Failed(e) = result.outcome {
+///         eprintln!("{}: {}", result.name, e);
+///     }
+/// }
+/// ```
+pub fn import_dotenv<P: AsRef<Path>>(path: P, options: &ImportOptions) -> Result<Vec<ImportResult>, EnvError> {
+    import_dotenv_in(&Target::default(), path, options)
+}
+
+/// Sets in the given target every variable of a `.env` file. A variable which can't be persisted
+/// doesn't stop the import: the failure is recorded in its report.
+pub fn import_dotenv_in<P: AsRef<Path>>(target: &Target, path: P, options: &ImportOptions) -> Result<Vec<ImportResult>, EnvError> {
+    let content = fs::read_to_string(path)?;
+    Ok(apply(target, parse(&content)?, options))
+}
+
+/// Sets each variable in the target, reporting what happened to it.
+pub(crate) fn apply(target: &Target, vars: Vec<(String, String)>, options: &ImportOptions) -> Vec<ImportResult> {
+    let mut results = Vec::new();
+    for (name, value) in vars {
+        // Checked before the process environment is touched, even if the value is unchanged
+        if let Err(e) = target.check(&name) {
+            results.push(ImportResult { name, outcome: ImportOutcome::Failed(e) });
+            continue;
+        }
+        let outcome = match stored_var_in(target, &name) {
+            Ok(Some(old)) if old == value => {
+                std::env::set_var(&name, &value);
+                ImportOutcome::Unchanged
+            }
+            Ok(Some(_)) if !options.get_overwrite() => ImportOutcome::Skipped,
+            _ => match set_var_in(target, &name, &value) {
+                Ok(()) => ImportOutcome::Set,
+                Err(e) => ImportOutcome::Failed(e),
+            },
+        };
+        results.push(ImportResult { name, outcome });
+    }
+    results
+}
+
+/// Renders the globally persisted variables in `.env` format.
+pub fn render_dotenv(filter: Filter) -> Result<String, EnvError> {
+    render_dotenv_in(&Target::default(), filter)
+}
+
+/// Renders the variables persisted in the given target in `.env` format.
+pub fn render_dotenv_in(target: &Target, filter: Filter) -> Result<String, EnvError> {
+    Ok(render(&filtered_vars_in(target, filter)?))
+}
+
+/// Writes the globally persisted variables into a `.env` file, for sharing or backup.
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{export_dotenv, Filter};
+/// export_dotenv("backup.env", Filter::Managed).unwrap();
+/// ```
+pub fn export_dotenv<P: AsRef<Path>>(path: P, filter: Filter) -> Result<(), EnvError> {
+    export_dotenv_in(&Target::default(), path, filter)
+}
+
+/// Writes the variables persisted in the given target into a `.env` file.
+pub fn export_dotenv_in<P: AsRef<Path>>(target: &Target, path: P, filter: Filter) -> Result<(), EnvError> {
+    fs::write(path, render_dotenv_in(target, filter)?)?;
+    Ok(())
+}
+
+/// Renders `KEY=value` lines read identically by `docker run --env-file` (which takes values
+/// literally) and docker-compose `env_file` (which unquotes and interpolates them): values with
+/// newlines, surrounding whitespace or quotes, `$` or ` #` can't be written.
+pub(crate) fn render_env_file(vars: &[(String, String)]) -> Result<String, EnvError> {
+    let mut env_file = String::new();
+    for (name, value) in vars {
+        let valid_name = !name.is_empty() && !name.contains(|c: char| c == '=' || c.is_whitespace());
+        let valid_value = !value.contains(['\n', '\r', '$'])
+            && !value.contains(" #")
+            && value.trim() == value
+            && !value.starts_with(['"', '\'']);
+        if !valid_name || !valid_value {
+            return Err(EnvError::VarError);
+        }
+        env_file.push_str(&format!("{}={}\n", name, value));
+    }
+    Ok(env_file)
+}
+
+/// Writes the globally managed variables into a file for `docker run --env-file` or the
+/// `env_file` of docker-compose. Fails if a value can't be represented in that format.
+/// Example:
+/// ```rust,no_run
+/// globalenv::export_env_file("app.env").unwrap();
+/// ```
+pub fn export_env_file<P: AsRef<Path>>(path: P) -> Result<(), EnvError> {
+    export_env_file_in(&Target::default(), path)
+}
+
+/// Writes the variables managed in the given target into a Docker env file.
+pub fn export_env_file_in<P: AsRef<Path>>(target: &Target, path: P) -> Result<(), EnvError> {
+    fs::write(path, render_env_file(&filtered_vars_in(target, Filter::Managed)?)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parse_render_roundtrip() {
+        let vars = parse("# comment\n\nexport A=1\nB = \"two words\" # comment\nC='x # y'\nD=plain # comment\n").unwrap();
+        assert_eq!(vars, vec![
+            (String::from("A"), String::from("1")),
+            (String::from("B"), String::from("two words")),
+            (String::from("C"), String::from("x # y")),
+            (String::from("D"), String::from("plain")),
+        ]);
+        assert_eq!(render(&vars), "A=1\nB=\"two words\"\nC=\"x # y\"\nD=plain\n");
+        let multiline = vec![(String::from("KEY"), String::from("line 1\nline \"2\""))];
+        assert_eq!(parse(&render(&multiline)).unwrap(), multiline);
+        let dollar = vec![(String::from("PRICE"), String::from("$HOME costs $5"))];
+        assert_eq!(render(&dollar), "PRICE=\"\\$HOME costs \\$5\"\n");
+        assert_eq!(parse(&render(&dollar)).unwrap(), dollar);
+    }
+
+    #[test]
+    fn parses_multiline_values() {
+        let vars = parse("KEY='-----BEGIN-----\nabc\n-----END-----'\nNEXT=1").unwrap();
+        assert_eq!(vars[0].1, "-----BEGIN-----\nabc\n-----END-----");
+        assert_eq!(vars[1], (String::from("NEXT"), String::from("1")));
+        assert_eq!(parse("KEY=\"unterminated\n"), Err(EnvError::ParseError));
+        assert_eq!(parse("not a variable\n"), Err(EnvError::ParseError));
+    }
+
+    #[test]
+    fn env_file_rejects_ambiguous_values() {
+        let vars = vec![(String::from("URL"), String::from("postgres://u:p@db/x?a=1 b"))];
+        assert_eq!(render_env_file(&vars).unwrap(), "URL=postgres://u:p@db/x?a=1 b\n");
+        for value in ["a\nb", " padded", "'quoted'", "$HOME", "a #b"] {
+            assert_eq!(render_env_file(&[(String::from("V"), String::from(value))]), Err(EnvError::VarError));
+        }
+    }
+
+    #[test]
+    fn import_reports_each_variable() {
+        let envfile = std::env::temp_dir().join("globalenv_import_target");
+        let dotenv = std::env::temp_dir().join("globalenv_import.env");
+        fs::write(&envfile, "export IMPORT_KEPT=old\n").unwrap();
+        fs::write(&dotenv, "IMPORT_KEPT=new\nIMPORT_NEW=\"a b\"\n").unwrap();
+        let target = Target::new().file(&envfile);
+        let results = import_dotenv_in(&target, &dotenv, &ImportOptions::new().overwrite(false)).unwrap();
+        assert_eq!(results[0].outcome, ImportOutcome::Skipped);
+        assert_eq!(results[1].outcome, ImportOutcome::Set);
+        assert_eq!(crate::get_var_in(&target, "IMPORT_NEW").unwrap().as_deref(), Some("a b"));
+        assert_eq!(render_dotenv_in(&target, Filter::Managed).unwrap(), "IMPORT_NEW=\"a b\"\n");
+        let denied = target.clone().policy(crate::Policy::new().deny(["IMPORT_KEPT"]));
+        let results = apply(&denied, vec![(String::from("IMPORT_KEPT"), String::from("old")), (String::new(), String::from("1"))], &ImportOptions::new());
+        assert_eq!(results[0].outcome, ImportOutcome::Failed(EnvError::PolicyError));
+        assert!(matches!(results[1].outcome, ImportOutcome::Failed(EnvError::InvalidName(_))));
+        assert_eq!(std::env::var("IMPORT_KEPT").ok(), None);
+        fs::remove_file(&envfile).unwrap();
+        fs::remove_file(&dotenv).unwrap();
+    }
+}
@@ -0,0 +1,74 @@
+//! Differences between the current process environment and the persisted variables.
+
+use crate::{env_vars_in, plan::REDACTED, EnvError, Filter, Target};
+use std::env;
+
+/// A persisted variable whose value in the current process differs, e.g. because it was set since the
+/// shell started.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Drift {
+    pub name: String,
+    /// Value in the current process, `None` if it isn't set (or isn't Unicode)
+    pub process: Option<String>,
+    /// Value persisted, which new sessions will have
+    pub persisted: String,
+    /// The variable is secret (see [`SetOptions::secret`](crate::SetOptions::secret)): both values are redacted
+    pub secret: bool,
+}
+
+/// Lists the globally persisted variables whose value differs in (or is missing from) the current process:
+/// a non-empty list means the shell has to be restarted, or the changes reconciled.
+/// On Windows, the process value of a variable also defined for the system (e.g. `PATH`) is the merge of both,
+/// so it is reported as drifting.
+/// Example:
+/// ```rust,no_run
+/// if !globalenv::diff_process_vs_global().unwrap().is_empty() {
+///     println!("Restart your shell to apply the new environment");
+/// }
+/// ```
+pub fn diff_process_vs_global() -> Result<Vec<Drift>, EnvError> {
+    diff_process_vs_global_in(&Target::default())
+}
+
+/// Lists the variables persisted in the given target whose value differs in (or is missing from) the current process.
+pub fn diff_process_vs_global_in(target: &Target) -> Result<Vec<Drift>, EnvError> {
+    Ok(env_vars_in(target, Filter::All)?
+        .into_iter()
+        .filter_map(|var| {
+            let process = env::var(&var.name).ok();
+            if process.as_deref() == Some(var.value.as_str()) {
+                return None;
+            }
+            let redact = |value: String| if var.secret { String::from(REDACTED) } else { value };
+            Some(Drift { name: var.name, process: process.map(redact), persisted: redact(var.value), secret: var.secret })
+        })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn reports_drifting_variables() {
+        let file = env::temp_dir().join("globalenv_drift");
+        fs::write(&file, "export DRIFT_SAME=1\nexport DRIFT_CHANGED=new\nexport DRIFT_MISSING=1\n").unwrap();
+        env::set_var("DRIFT_SAME", "1");
+        env::set_var("DRIFT_CHANGED", "old");
+        env::remove_var("DRIFT_MISSING");
+        let target = Target::new().file(&file);
+        crate::set_var_with_in(&target, "DRIFT_SECRET", "hunter2", &crate::SetOptions::new().secret(true)).unwrap();
+        env::set_var("DRIFT_SECRET", "hunter1");
+        assert_eq!(
+            diff_process_vs_global_in(&target).unwrap(),
+            vec![
+                Drift { name: String::from("DRIFT_CHANGED"), process: Some(String::from("old")), persisted: String::from("new"), secret: false },
+                Drift { name: String::from("DRIFT_MISSING"), process: None, persisted: String::from("1"), secret: false },
+                Drift { name: String::from("DRIFT_SECRET"), process: Some(String::from(REDACTED)), persisted: String::from(REDACTED), secret: true },
+            ]
+        );
+        fs::remove_file(&file).unwrap();
+    }
+}
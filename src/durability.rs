@@ -0,0 +1,59 @@
+//! Durable writes: the rewritten files (and their directory) are synced to the disk before returning.
+
+use std::{
+    fs::File,
+    io,
+    path::Path,
+    sync::atomic::{AtomicBool, Ordering},
+};
+
+static DURABLE: AtomicBool = AtomicBool::new(false);
+
+/// Makes the writes of this process durable (or not, the default): a rewritten startup file is synced to the
+/// disk with its directory (`fsync`, `FlushFileBuffers` on Windows), and a changed registry key is flushed
+/// (`RegFlushKey`), before the operation returns. For callers which must guarantee the change survives an
+/// immediately following reboot, e.g. an installer about to restart the machine. Each write is then slower.
+/// Example:
+/// ```rust
+/// globalenv::set_durable(true);
+/// # globalenv::set_durable(false);
+/// ```
+pub fn set_durable(durable: bool) {
+    DURABLE.store(durable, Ordering::Relaxed);
+}
+
+pub(crate) fn durable() -> bool {
+    DURABLE.load(Ordering::Relaxed)
+}
+
+/// Syncs the file and the directory entry naming it, if writes are durable.
+pub(crate) fn sync(path: &Path) -> io::Result<()> {
+    if durable() {
+        sync_file(path)?;
+    }
+    Ok(())
+}
+
+fn sync_file(path: &Path) -> io::Result<()> {
+    File::options().write(true).open(path)?.sync_all()?;
+    // Directories can't be opened as files on Windows, where NTFS journals the rename itself
+    #[cfg(target_family = "unix")]
+    if let Some(dir) = path.parent().filter(|dir| !dir.as_os_str().is_empty()) {
+        File::open(dir)?.sync_all()?;
+    }
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn syncs_written_file() {
+        let file = std::env::temp_dir().join("globalenv_durable");
+        std::fs::write(&file, "export A=1\n").unwrap();
+        sync_file(&file).unwrap();
+        assert!(sync_file(&std::env::temp_dir().join("globalenv_durable_missing")).is_err());
+        std::fs::remove_file(&file).unwrap();
+    }
+}
@@ -0,0 +1,127 @@
+//! Setting system variables from an unprivileged process: the `globalenv` command is run elevated to do it.
+
+use crate::{set_var_in, EnvError, Scope, Target};
+use std::{env, path::PathBuf};
+
+/// The `globalenv` command: next to the current executable if it is there (e.g. shipped with an installer),
+/// else found in `PATH`.
+fn helper() -> PathBuf {
+    let name = if cfg!(target_os = "windows") { "globalenv.exe" } else { "globalenv" };
+    env::current_exe().ok().map(|exe| exe.with_file_name(name)).filter(|helper| helper.exists()).unwrap_or_else(|| PathBuf::from(name))
+}
+
+/// Argument quoted for `CommandLineToArgvW`: in double quotes if needed, backslashes being doubled before a
+/// double quote (escaped) or the closing one.
+#[cfg_attr(not(target_os = "windows"), allow(dead_code))]
+fn quote_arg(arg: &str) -> String {
+    if !arg.is_empty() && !arg.contains([' ', '\t', '\n', '"']) {
+        return arg.to_string();
+    }
+    let mut quoted = String::from("\"");
+    let mut backslashes = 0;
+    for c in arg.chars() {
+        match c {
+            '\\' => backslashes += 1,
+            '"' => {
+                quoted.push_str(&"\\".repeat(backslashes * 2 + 1));
+                quoted.push('"');
+                backslashes = 0;
+            }
+            _ => {
+                quoted.push_str(&"\\".repeat(backslashes));
+                quoted.push(c);
+                backslashes = 0;
+            }
+        }
+    }
+    quoted.push_str(&"\\".repeat(backslashes * 2));
+    quoted.push('"');
+    quoted
+}
+
+/// Runs the helper with a UAC prompt, waiting for it. A refused prompt is a [`EnvError::PolicyError`].
+#[cfg(target_os = "windows")]
+fn run_elevated(args: &[&str]) -> Result<(), EnvError> {
+    use std::io;
+    use windows_sys::Win32::{
+        Foundation::{CloseHandle, ERROR_CANCELLED},
+        System::Threading::{GetExitCodeProcess, WaitForSingleObject, INFINITE},
+        UI::{
+            Shell::{ShellExecuteExW, SEE_MASK_NOASYNC, SEE_MASK_NOCLOSEPROCESS, SHELLEXECUTEINFOW},
+            WindowsAndMessaging::SW_HIDE,
+        },
+    };
+    let wide = |s: &str| -> Vec<u16> { s.encode_utf16().chain(Some(0)).collect() };
+    let (verb, file) = (wide("runas"), wide(&helper().to_string_lossy()));
+    let parameters = wide(&args.iter().map(|arg| quote_arg(arg)).collect::<Vec<_>>().join(" "));
+    let mut info: SHELLEXECUTEINFOW = unsafe { std::mem::zeroed() };
+    info.cbSize = std::mem::size_of::<SHELLEXECUTEINFOW>() as u32;
+    info.fMask = SEE_MASK_NOCLOSEPROCESS | SEE_MASK_NOASYNC;
+    info.lpVerb = verb.as_ptr();
+    info.lpFile = file.as_ptr();
+    info.lpParameters = parameters.as_ptr();
+    info.nShow = SW_HIDE;
+    if unsafe { ShellExecuteExW(&mut info) } == 0 {
+        let e = io::Error::last_os_error();
+        return Err(if e.raw_os_error() == Some(ERROR_CANCELLED as i32) { EnvError::PolicyError } else { e.into() });
+    }
+    if info.hProcess.is_null() {
+        return Err(EnvError::IOError);
+    }
+    let mut code = 1;
+    unsafe {
+        WaitForSingleObject(info.hProcess, INFINITE);
+        GetExitCodeProcess(info.hProcess, &mut code);
+        CloseHandle(info.hProcess);
+    }
+    if code == 0 { Ok(()) } else { Err(EnvError::PolicyError) }
+}
+
+/// Runs the helper through sudo, or pkexec (which asks graphically) without a terminal.
+#[cfg(target_family = "unix")]
+fn run_elevated(args: &[&str]) -> Result<(), EnvError> {
+    let elevator = if unsafe { libc::isatty(libc::STDIN_FILENO) } == 1 { "sudo" } else { "pkexec" };
+    let mut command = std::process::Command::new(elevator);
+    if elevator == "sudo" {
+        command.arg("--");
+    }
+    let status = command.arg(helper()).args(args).status()?;
+    if status.success() { Ok(()) } else { Err(EnvError::PolicyError) }
+}
+
+#[cfg(not(any(target_os = "windows", target_family = "unix")))]
+fn run_elevated(_args: &[&str]) -> Result<(), EnvError> {
+    Err(EnvError::UnsupportedPlatform)
+}
+
+/// Sets a variable for the whole machine, asking for the privileges this requires if the process lacks them:
+/// the `globalenv` command is then run elevated, through a UAC prompt on Windows, and `sudo` (or `pkexec`
+/// without a terminal) on Unix. The command is looked up next to the current executable, then in `PATH`.
+/// A refused elevation, as any failure of the command, is a [`EnvError::PolicyError`].
+/// Example:
+/// ```rust,no_run
+/// globalenv::set_var_system_elevated("JAVA_HOME", "/usr/lib/jvm/default").unwrap();
+/// ```
+pub fn set_var_system_elevated(var: &str, value: &str) -> Result<(), EnvError> {
+    let target = Target::new().scope(Scope::System);
+    if crate::doctor::elevated() {
+        return set_var_in(&target, var, value);
+    }
+    target.check(var)?;
+    run_elevated(&["--scope", "system", "set", "--", var, value])?;
+    env::set_var(var, value);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn quotes_arguments() {
+        assert_eq!(quote_arg("JAVA_HOME"), "JAVA_HOME");
+        assert_eq!(quote_arg(r"C:\Program Files\Java\"), r#""C:\Program Files\Java\\""#);
+        assert_eq!(quote_arg(r#"say "hi""#), r#""say \"hi\"""#);
+        assert_eq!(quote_arg(""), r#""""#);
+    }
+}
@@ -0,0 +1,164 @@
+//! Values stored encrypted with age (X25519) in sidecar files, which the shell decrypts at startup:
+//! the startup file only holds the command, so it can be kept in a public repository.
+
+use crate::{envfile, paths, target::Store, tmux, unset_var_in, EnvError, Target};
+use age::{secrecy::ExposeSecret, x25519::Identity};
+use std::{env, fs, io, path::{Path, PathBuf}, str::FromStr};
+
+/// Directory of the identity and of the encrypted values.
+struct Vault {
+    dir: PathBuf,
+}
+
+impl Vault {
+    fn new() -> Result<Vault, EnvError> {
+        Ok(Vault { dir: paths()?.config_dir })
+    }
+
+    fn identity_path(&self) -> PathBuf {
+        self.dir.join("age-identity.txt")
+    }
+
+    fn value_path(&self, var: &str) -> Result<PathBuf, EnvError> {
+        if var.is_empty() || !var.chars().all(|c| c.is_ascii_alphanumeric() || c == '_') {
+            return Err(EnvError::VarError);
+        }
+        Ok(self.dir.join("secrets").join(format!("{}.age", var)))
+    }
+
+    /// Reads the identity, which is generated on first use.
+    fn identity(&self) -> Result<Identity, EnvError> {
+        let path = self.identity_path();
+        match fs::read_to_string(&path) {
+            Ok(content) => content
+                .lines()
+                .map(str::trim)
+                .find(|l| !l.is_empty() && !l.starts_with('#'))
+                .and_then(|l| Identity::from_str(l).ok())
+                .ok_or(EnvError::ParseError),
+            Err(e) if e.kind() == io::ErrorKind::NotFound => {
+                let identity = Identity::generate();
+                fs::create_dir_all(&self.dir)?;
+                let content = format!(
+                    "# created by globalenv, keep private\n# public key: {}\n{}\n",
+                    identity.to_public(),
+                    identity.to_string().expose_secret()
+                );
+                write_private(&path, &content)?;
+                Ok(identity)
+            }
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    fn store(&self, var: &str, value: &str) -> Result<PathBuf, EnvError> {
+        let recipient = self.identity()?.to_public();
+        let encrypted = age::encrypt_and_armor(&recipient, value.as_bytes()).map_err(|_| EnvError::IOError)?;
+        let path = self.value_path(var)?;
+        fs::create_dir_all(self.dir.join("secrets"))?;
+        fs::write(&path, encrypted)?;
+        Ok(path)
+    }
+
+    fn load(&self, var: &str) -> Result<Option<String>, EnvError> {
+        let encrypted = match fs::read(self.value_path(var)?) {
+            Ok(encrypted) => encrypted,
+            Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(e) => return Err(e.into()),
+        };
+        let decrypted = age::decrypt(&self.identity()?, &encrypted).map_err(|_| EnvError::ParseError)?;
+        Ok(Some(String::from_utf8(decrypted).map_err(|_| EnvError::ParseError)?))
+    }
+
+    fn remove(&self, var: &str) -> Result<(), EnvError> {
+        match fs::remove_file(self.value_path(var)?) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+            _ => Ok(()),
+        }
+    }
+}
+
+#[cfg(target_family = "unix")]
+fn write_private(path: &Path, content: &str) -> io::Result<()> {
+    use std::{io::Write, os::unix::fs::OpenOptionsExt};
+    fs::OpenOptions::new().write(true).create_new(true).mode(0o600).open(path)?.write_all(content.as_bytes())
+}
+
+#[cfg(not(target_family = "unix"))]
+fn write_private(path: &Path, content: &str) -> io::Result<()> {
+    fs::write(path, content)
+}
+
+/// Sets a variable globally, its value being encrypted into `secrets/<VAR>.age` with the identity of
+/// `age-identity.txt` (generated on first use), in the configuration directory (`~/.config/globalenv`, see
+/// [`paths`](fn@crate::paths)). The startup
+/// file gets a line running `age -d` to decrypt it, so the `age` tool must be installed.
+/// Only shell startup files are supported (not the Windows registry).
+/// Example:
+/// ```rust,no_run
+/// globalenv::set_var_encrypted("API_TOKEN", "s3cr3t").unwrap();
+/// ```
+pub fn set_var_encrypted(var: &str, value: &str) -> Result<(), EnvError> {
+    set_var_encrypted_in(&Target::default(), var, value)
+}
+
+/// Sets a variable in the given target, its value being encrypted into a sidecar file.
+pub fn set_var_encrypted_in(target: &Target, var: &str, value: &str) -> Result<(), EnvError> {
+    target.check(var)?;
+    let (path, syntax) = match target.store()? {
+        Store::File(path, syntax) => (path, syntax),
+        #[cfg(target_os = "windows")]
+        Store::Registry(_) => return Err(EnvError::UnsupportedShell),
+    };
+    let vault = Vault::new()?;
+    let encrypted = vault.store(var, value)?;
+    let mut meta = envfile::meta(&path, syntax, var)?;
+    meta.encrypted = true;
+    meta.touch();
+    let identity = vault.identity_path();
+    let command = ["age", "-d", "-i", path_str(&identity)?, path_str(&encrypted)?];
+    envfile::set_command(&path, syntax, var, &command, &meta)?;
+    env::set_var(var, value);
+    if target.get_tmux() {
+        tmux::set_environment(var, Some(value));
+    }
+    Ok(())
+}
+
+fn path_str(path: &Path) -> Result<&str, EnvError> {
+    path.to_str().ok_or(EnvError::VarError)
+}
+
+/// Decrypts the value of a variable set by [`set_var_encrypted`].
+pub fn get_var_encrypted(var: &str) -> Result<Option<String>, EnvError> {
+    Vault::new()?.load(var)
+}
+
+/// Unsets a variable globally, and deletes its encrypted value.
+pub fn unset_var_encrypted(var: &str) -> Result<(), EnvError> {
+    unset_var_encrypted_in(&Target::default(), var)
+}
+
+/// Unsets a variable from the given target, and deletes its encrypted value.
+pub fn unset_var_encrypted_in(target: &Target, var: &str) -> Result<(), EnvError> {
+    unset_var_in(target, var)?;
+    Vault::new()?.remove(var)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn encrypts_values_in_vault() {
+        let vault = Vault { dir: env::temp_dir().join("globalenv_vault") };
+        let _ = fs::remove_dir_all(&vault.dir);
+        let path = vault.store("TOKEN", "s3cr3t").unwrap();
+        assert!(fs::read_to_string(&path).unwrap().starts_with("-----BEGIN AGE ENCRYPTED FILE-----"));
+        assert_eq!(vault.load("TOKEN").unwrap().as_deref(), Some("s3cr3t"));
+        vault.remove("TOKEN").unwrap();
+        assert_eq!(vault.load("TOKEN").unwrap(), None);
+        assert!(vault.value_path("../x").is_err());
+        fs::remove_dir_all(&vault.dir).unwrap();
+    }
+}
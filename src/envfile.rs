@@ -0,0 +1,525 @@
+//! Shell startup file backend: variables are persisted as `export VAR=value` lines (or the
+//! equivalent for the shell), in a block delimited by marker comments which tells them apart from
+//! the user's own definitions.
+
+use crate::{condition, config, meta::Meta, retry, syntax::{appended, keep_self_reference, Syntax}, BlockMode, EnvError, GlobalEnvConfig};
+use std::{fs::{self, File}, io::{self, BufRead, BufReader, BufWriter, Write}, path::Path};
+
+/// Markers of the block holding the variables set by this crate.
+pub(crate) const BLOCK_START: &str = "# >>> globalenv >>>";
+pub(crate) const BLOCK_END: &str = "# <<< globalenv <<<";
+/// Comment preceding the line of a managed variable which has metadata, so that the definition itself
+/// stays a plain `export VAR=value` line.
+const META_MARKER: &str = "# globalenv: ";
+/// End of the definitions commented out instead of removed.
+const DISABLED_MARKER: &str = "  # disabled by globalenv";
+
+/// Line range of the managed block content (between the markers), if there is one.
+fn managed_block(lines: &[&str]) -> Option<(usize, usize)> {
+    let start = lines.iter().position(|l| l.trim() == BLOCK_START)?;
+    let end = start + lines[start..].iter().position(|l| l.trim() == BLOCK_END)?;
+    Some((start + 1, end))
+}
+
+/// Line endings of a file, which rewrites keep: CRLF ones (of files edited on Windows or with Git Bash), and a
+/// missing final newline.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) struct Endings {
+    crlf: bool,
+    final_newline: bool,
+}
+
+impl Endings {
+    /// Endings of this content, guessed from its first line. An empty content gets LF ones.
+    pub(crate) fn of(content: &str) -> Endings {
+        let crlf = content.find('\n').is_some_and(|i| content[..i].ends_with('\r'));
+        Endings { crlf, final_newline: content.is_empty() || content.ends_with('\n') }
+    }
+
+    pub(crate) fn newline(&self) -> &'static str {
+        if self.crlf { "\r\n" } else { "\n" }
+    }
+
+    pub(crate) fn join<S: AsRef<str>>(&self, lines: &[S]) -> String {
+        let mut content = lines.iter().map(AsRef::as_ref).collect::<Vec<_>>().join(self.newline());
+        if !lines.is_empty() && self.final_newline {
+            content.push_str(self.newline());
+        }
+        content
+    }
+}
+
+/// Writes lines with the endings of the file they come from, which are only known at its end for the last one.
+struct LineWriter<W: Write> {
+    writer: W,
+    newline: &'static str,
+    pending: Option<String>,
+}
+
+impl<W: Write> LineWriter<W> {
+    fn write_line(&mut self, line: String) -> io::Result<()> {
+        if let Some(previous) = self.pending.replace(line) {
+            write!(self.writer, "{}{}", previous, self.newline)?;
+        }
+        Ok(())
+    }
+
+    fn finish(mut self, final_newline: bool) -> io::Result<()> {
+        if let Some(last) = self.pending.take() {
+            write!(self.writer, "{}{}", last, if final_newline { self.newline } else { "" })?;
+        }
+        self.writer.flush()
+    }
+}
+
+fn is_meta_line(line: &str) -> bool {
+    line.trim_start().starts_with(META_MARKER)
+}
+
+/// Metadata of a `# globalenv: ...` comment.
+pub(crate) fn parse_meta_line(line: &str) -> Option<Meta> {
+    line.trim_start().strip_prefix(META_MARKER).map(Meta::parse)
+}
+
+/// Metadata of the variable: the comment preceding its line in the managed block.
+fn meta_from(lines: &[&str], syntax: Syntax, var: &str) -> Meta {
+    let defines = |l: &str| matches!(syntax.parse(l), Some((name, _)) if name == var);
+    let Some((start, end)) = managed_block(lines) else { return Meta::default() };
+    match (start..end).rfind(|i| defines(lines[*i])) {
+        Some(i) if i > start => parse_meta_line(lines[i - 1]).unwrap_or_default(),
+        _ => Meta::default(),
+    }
+}
+
+/// Value of the variable's definition in the managed block, the one which is rewritten.
+fn managed_value(lines: &[&str], syntax: Syntax, var: &str) -> Option<String> {
+    let (start, end) = managed_block(lines)?;
+    lines[start..end].iter().filter_map(|l| syntax.parse(l)).find(|(name, _)| *name == var).map(|(_, value)| value)
+}
+
+/// Content of the file, empty if it doesn't exist yet (e.g. the `.env` of a new project): it is created when
+/// written.
+pub(crate) fn read(envfilepath: &Path) -> Result<String, EnvError> {
+    match fs::read_to_string(envfilepath) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(String::new()),
+        result => Ok(result?),
+    }
+}
+
+/// Runs a read-only query on the content of the file.
+#[cfg(not(feature = "mmap"))]
+fn query<T, F: FnOnce(&str) -> T>(envfilepath: &Path, f: F) -> Result<T, EnvError> {
+    Ok(f(&read(envfilepath)?))
+}
+
+/// Runs a read-only query on the content of the file, mapped in memory rather than copied.
+#[cfg(feature = "mmap")]
+fn query<T, F: FnOnce(&str) -> T>(envfilepath: &Path, f: F) -> Result<T, EnvError> {
+    let file = match File::open(envfilepath) {
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(f("")),
+        file => file?,
+    };
+    // Empty files cannot be mapped
+    if file.metadata()?.len() == 0 {
+        return Ok(f(""));
+    }
+    // SAFETY: the mapping is only read during the query. A concurrent truncation of the file by
+    // another process is a risk the caller accepts by enabling the feature.
+    let map = unsafe { memmap2::Mmap::map(&file)? };
+    Ok(f(std::str::from_utf8(&map).map_err(|_| EnvError::IOError)?))
+}
+
+pub(crate) fn meta(envfilepath: &Path, syntax: Syntax, var: &str) -> Result<Meta, EnvError> {
+    query(envfilepath, |env| meta_content(env, syntax, var))
+}
+
+pub(crate) fn meta_content(env: &str, syntax: Syntax, var: &str) -> Meta {
+    meta_from(&env.lines().collect::<Vec<_>>(), syntax, var)
+}
+
+pub(crate) fn set(envfilepath: &Path, syntax: Syntax, var: &str, value: &str, meta: &Meta) -> Result<(), EnvError> {
+    // Reading the env file
+    let env = read(envfilepath)?;
+    if let Some(env) = set_content(&env, syntax, var, value, meta) {
+        config::backup(envfilepath)?;
+        retry::write(envfilepath, env)?;
+    }
+    Ok(())
+}
+
+/// Content with the variable set, `None` if it is already.
+pub(crate) fn set_content(env: &str, syntax: Syntax, var: &str, value: &str, meta: &Meta) -> Option<String> {
+    let lines: Vec<&str> = env.lines().collect();
+
+    // A definition appending to the inherited value stays one
+    let kept = managed_value(&lines, syntax, var)
+        .and_then(|old| keep_self_reference(&old, var, value, std::env::var(var).ok().as_deref()));
+    let value = kept.as_deref().unwrap_or(value);
+
+    // Already the effective value ? nothing to write
+    if get_from(env, syntax, var).as_deref() == Some(value) && meta_from(&lines, syntax, var) == *meta {
+        return None;
+    }
+
+    // Building the "export" line according to requested parameters
+    Some(with_definition(syntax, var, &lines, syntax.render(var, value), meta, Endings::of(env)))
+}
+
+/// Sets the variable to the output of a command, run by the shell at startup.
+#[cfg_attr(not(feature = "age"), allow(dead_code))]
+pub(crate) fn set_command(envfilepath: &Path, syntax: Syntax, var: &str, command: &[&str], meta: &Meta) -> Result<(), EnvError> {
+    let env = read(envfilepath)?;
+    let lines: Vec<&str> = env.lines().collect();
+    let export = syntax.render_command(var, command);
+    config::backup(envfilepath)?;
+    retry::write(envfilepath, with_definition(syntax, var, &lines, export, meta, Endings::of(&env)))?;
+    Ok(())
+}
+
+/// Appends to the variable lazily (see [`Syntax::render_append`]), after what the managed definition already
+/// appends. A managed definition with a plain value gets the suffix instead.
+pub(crate) fn append(envfilepath: &Path, syntax: Syntax, var: &str, suffix: &str, sep: &str, meta: &Meta) -> Result<(), EnvError> {
+    let env = read(envfilepath)?;
+    let lines: Vec<&str> = env.lines().collect();
+    let line = match managed_value(&lines, syntax, var) {
+        Some(value) => match appended(&value, var, sep) {
+            Some(suffixes) if suffixes.split(sep).any(|s| s == suffix) => return Ok(()),
+            Some(suffixes) => syntax.render_append(var, &format!("{}{}{}", suffixes, sep, suffix), sep),
+            None if value.split(sep).any(|s| s == suffix) => return Ok(()),
+            None => Some(syntax.render(var, &format!("{}{}{}", value, sep, suffix))),
+        },
+        None => syntax.render_append(var, suffix, sep),
+    };
+    let export = line.ok_or(EnvError::UnsupportedShell)?;
+    config::backup(envfilepath)?;
+    retry::write(envfilepath, with_definition(syntax, var, &lines, export, meta, Endings::of(&env)))?;
+    Ok(())
+}
+
+fn with_definition(syntax: Syntax, var: &str, lines: &[&str], export: String, meta: &Meta, endings: Endings) -> String {
+    let defines = |l: &str| matches!(syntax.parse(l), Some((name, _)) if name == var);
+    let block = managed_block(lines);
+    let inline = block.is_none() && GlobalEnvConfig::defaults().block_mode == BlockMode::Inline;
+    let replaced = match block {
+        Some((start, end)) => (start..end).find(|i| defines(lines[*i])),
+        // The last definition, the one the shell ends up with
+        None if inline => (0..lines.len()).rfind(|i| defines(lines[*i])),
+        None => None,
+    };
+    // A replaced line keeps its indentation and trailing comment, and its metadata comment the indentation
+    let (indent, comment) = replaced.map_or(("", ""), |i| syntax.decoration(lines[i]));
+    let export = match &meta.when {
+        Some(when) => condition::guard(syntax, when, &export).unwrap_or(export),
+        None => export,
+    };
+    let export = format!("{}{}{}", indent, export, comment);
+    let meta_line = format!("{}{}{}", indent, META_MARKER, meta);
+    let mut definition = vec![export.as_str()];
+    if !meta.is_empty() {
+        definition.insert(0, &meta_line);
+    }
+
+    // Updating the managed block, which is created at the end of the file if needed (unless inline)
+    let mut lines = lines.to_vec();
+    match (block, replaced) {
+        (_, Some(i)) => {
+            let start = block.map_or(0, |(start, _)| start);
+            let first = if i > start && is_meta_line(lines[i - 1]) { i - 1 } else { i };
+            lines.splice(first..=i, definition);
+        }
+        (Some((_, end)), None) => {
+            lines.splice(end..end, definition);
+        }
+        (None, None) if inline => lines.extend(definition),
+        (None, None) => {
+            lines.push(BLOCK_START);
+            lines.extend(definition);
+            lines.push(BLOCK_END);
+        }
+    }
+    endings.join(&lines)
+}
+
+/// Streams the file into a temporary one without the variable's lines (and their metadata), which then
+/// replaces it: memory use does not grow with the file, which may be a large generated one.
+/// With `comment_out`, the lines are commented out instead of removed. Returns the number of definitions.
+pub(crate) fn unset(envfilepath: &Path, syntax: Syntax, var: &str, comment_out: bool) -> Result<usize, EnvError> {
+    // Symbolic links (e.g. from a dotfiles manager) are kept, their target is rewritten
+    let envfilepath = match fs::canonicalize(envfilepath) {
+        // No file, nothing to remove
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(0),
+        path => path?,
+    };
+    let reader = BufReader::new(File::open(&envfilepath)?);
+    let temppath = retry::temp_path(&envfilepath);
+    let result = File::create(&temppath)
+        .and_then(|temp| unset_into(reader, BufWriter::new(temp), syntax, var, comment_out))
+        .map_err(EnvError::from);
+    match result {
+        Ok((true, removed)) => {
+            config::backup(&envfilepath)?;
+            retry::replace(&temppath, &envfilepath)?;
+            Ok(removed)
+        }
+        // Variable not present in env file ? nothing to remove
+        Ok((false, _)) => {
+            fs::remove_file(&temppath)?;
+            Ok(0)
+        }
+        Err(e) => {
+            let _ = fs::remove_file(&temppath);
+            Err(e)
+        }
+    }
+}
+
+/// Content without the variable, `None` if it is not set.
+pub(crate) fn unset_content(env: &str, syntax: Syntax, var: &str) -> Option<String> {
+    let mut content = Vec::new();
+    match unset_into(env.as_bytes(), &mut content, syntax, var, false) {
+        Ok((true, _)) => String::from_utf8(content).ok(),
+        _ => None,
+    }
+}
+
+/// Writes the lines not defining the variable (or these lines commented out), returning whether there were any,
+/// and the number of definitions among them.
+fn unset_into<R: BufRead, W: Write>(
+    mut reader: R,
+    writer: W,
+    syntax: Syntax,
+    var: &str,
+    comment_out: bool,
+) -> io::Result<(bool, usize)> {
+    let assigns = |l: &str| matches!(syntax.parse(l), Some((name, _)) if name == var);
+    // Plain assignments are removed with the `export VAR` lines exporting them
+    let defines = |l: &str| assigns(l) || syntax.exports(l, var);
+    let (mut found, mut removed) = (false, 0);
+    // Lines kept or not depending on the next ones: a metadata comment, which goes with the
+    // definition following it, and the start of the managed block, removed with it if it ends up empty
+    let mut held: Vec<String> = Vec::new();
+    let flush = |held: &mut Vec<String>, writer: &mut LineWriter<W>| -> io::Result<()> {
+        for line in held.drain(..) {
+            writer.write_line(line)?;
+        }
+        Ok(())
+    };
+    let mut writer = LineWriter { writer, newline: "\n", pending: None };
+    let (mut buf, mut first, mut final_newline) = (String::new(), true, true);
+    loop {
+        buf.clear();
+        if reader.read_line(&mut buf)? == 0 {
+            break;
+        }
+        if first && buf.ends_with("\r\n") {
+            writer.newline = "\r\n";
+        }
+        first = false;
+        final_newline = buf.ends_with('\n');
+        let line = buf.strip_suffix('\n').unwrap_or(&buf);
+        let line = line.strip_suffix('\r').unwrap_or(line).to_string();
+        if defines(&line) {
+            found = true;
+            removed += usize::from(assigns(&line));
+            if held.last().is_some_and(|l| is_meta_line(l)) {
+                held.pop();
+            }
+            if comment_out {
+                flush(&mut held, &mut writer)?;
+                let indent = &line[..line.len() - line.trim_start().len()];
+                writer.write_line(format!("{}# {}{}", indent, line.trim(), DISABLED_MARKER))?;
+            }
+        } else if is_meta_line(&line) {
+            held.push(line);
+        } else if line.trim() == BLOCK_START {
+            flush(&mut held, &mut writer)?;
+            held.push(line);
+        } else if line.trim() == BLOCK_END && held.len() == 1 && held[0].trim() == BLOCK_START {
+            held.clear();
+        } else {
+            flush(&mut held, &mut writer)?;
+            writer.write_line(line)?;
+        }
+    }
+    flush(&mut held, &mut writer)?;
+    writer.finish(final_newline)?;
+    Ok((found, removed))
+}
+
+/// Value of the last definition of the variable, the conditional ones which do not apply here being skipped.
+pub(crate) fn get_from(env: &str, syntax: Syntax, var: &str) -> Option<String> {
+    env.lines()
+        .filter(|l| condition::unguard(syntax, l).is_none_or(|(condition, _)| condition.holds()))
+        .filter_map(|l| syntax.parse(l))
+        .rfind(|(name, _)| *name == var)
+        .map(|(_, value)| value)
+}
+
+/// Value of the last definition of the variable, which is the one the shell ends up with.
+pub(crate) fn get(envfilepath: &Path, syntax: Syntax, var: &str) -> Result<Option<String>, EnvError> {
+    query(envfilepath, |env| get_from(env, syntax, var))
+}
+
+/// Variables defined by these lines, in order of first definition, with their effective value.
+fn collect<'a, I: Iterator<Item = &'a str>>(lines: I, syntax: Syntax) -> Vec<(String, String)> {
+    let mut vars: Vec<(String, String)> = Vec::new();
+    for (name, value) in lines.filter_map(|l| syntax.parse(l)) {
+        match vars.iter_mut().find(|(n, _)| n == name) {
+            Some(var) => var.1 = value,
+            None => vars.push((name.to_string(), value)),
+        }
+    }
+    vars
+}
+
+pub(crate) fn vars_from(env: &str, syntax: Syntax) -> Vec<(String, String)> {
+    collect(env.lines(), syntax)
+}
+
+/// Every variable defined in the file.
+pub(crate) fn vars(envfilepath: &Path, syntax: Syntax) -> Result<Vec<(String, String)>, EnvError> {
+    query(envfilepath, |env| vars_from(env, syntax))
+}
+
+/// Variables defined in the managed block.
+pub(crate) fn managed_vars(envfilepath: &Path, syntax: Syntax) -> Result<Vec<(String, String)>, EnvError> {
+    query(envfilepath, |env| {
+        let lines: Vec<&str> = env.lines().collect();
+        match managed_block(&lines) {
+            Some((start, end)) => collect(lines[start..end].iter().copied(), syntax),
+            None => Vec::new(),
+        }
+    })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn set_get_unset_in_file() {
+        let path = std::env::temp_dir().join("globalenv_envfile_test");
+        fs::write(&path, "# comment mentioning FOO\nexport FOOBAR=1\n").unwrap();
+        set(&path, Syntax::Posix, "FOO", "bar", &Meta::default()).unwrap();
+        set(&path, Syntax::Posix, "FOO", "baz", &Meta::default()).unwrap();
+        assert_eq!(get(&path, Syntax::Posix, "FOO").unwrap(), Some(String::from("baz")));
+        assert_eq!(vars(&path, Syntax::Posix).unwrap().len(), 2);
+        assert_eq!(managed_vars(&path, Syntax::Posix).unwrap(), vec![(String::from("FOO"), String::from("baz"))]);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("# comment mentioning FOO\nexport FOOBAR=1\n{}\nexport FOO=baz\n{}\n", BLOCK_START, BLOCK_END)
+        );
+        unset(&path, Syntax::Posix, "FOO", false).unwrap();
+        assert_eq!(get(&path, Syntax::Posix, "FOO").unwrap(), None);
+        assert_eq!(fs::read_to_string(&path).unwrap(), "# comment mentioning FOO\nexport FOOBAR=1\n");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn metadata_comment() {
+        let path = std::env::temp_dir().join("globalenv_envfile_meta");
+        fs::write(&path, "").unwrap();
+        let secret = Meta { secret: true, ..Meta::default() };
+        set(&path, Syntax::Posix, "TOKEN", "a b", &secret).unwrap();
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("{}\n# globalenv: secret\nexport TOKEN='a b'\n{}\n", BLOCK_START, BLOCK_END)
+        );
+        assert_eq!(get(&path, Syntax::Posix, "TOKEN").unwrap().as_deref(), Some("a b"));
+        assert_eq!(meta(&path, Syntax::Posix, "TOKEN").unwrap(), secret);
+        // Same value, metadata removed
+        set(&path, Syntax::Posix, "TOKEN", "a b", &Meta::default()).unwrap();
+        assert_eq!(meta(&path, Syntax::Posix, "TOKEN").unwrap(), Meta::default());
+        let expiring = Meta { secret: true, expires: Some(1700000000), ..Meta::default() };
+        set(&path, Syntax::Posix, "TOKEN", "a b", &expiring).unwrap();
+        assert!(fs::read_to_string(&path).unwrap().contains("# globalenv: secret expires=1700000000\nexport TOKEN="));
+        assert_eq!(meta(&path, Syntax::Posix, "TOKEN").unwrap(), expiring);
+        assert!(expiring.is_expired());
+        let stamped = Meta::parse("secret created=1700000000 updated=1700000500 future");
+        assert_eq!((stamped.created, stamped.updated), (Some(1700000000), Some(1700000500)));
+        assert_eq!(stamped.to_string(), "secret created=1700000000 updated=1700000500");
+        unset(&path, Syntax::Posix, "TOKEN", false).unwrap();
+        assert_eq!(fs::read_to_string(&path).unwrap(), "");
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn unset_removes_plain_assignments() {
+        let env = "EDITOR=vim\nexport EDITOR\nset -a\nPAGER=less\nset +a\nexport EDITOR_OPTS\n";
+        assert_eq!(get_from(env, Syntax::Posix, "PAGER").as_deref(), Some("less"));
+        assert_eq!(unset_content(env, Syntax::Posix, "EDITOR").as_deref(), Some("set -a\nPAGER=less\nset +a\nexport EDITOR_OPTS\n"));
+        assert_eq!(unset_content(env, Syntax::Posix, "PAGER").as_deref(), Some("EDITOR=vim\nexport EDITOR\nset -a\nset +a\nexport EDITOR_OPTS\n"));
+    }
+
+    #[test]
+    fn keeps_line_endings() {
+        let env = "export A=1\r\nexport B=2";
+        let set = set_content(env, Syntax::Posix, "C", "3", &Meta::default()).unwrap();
+        assert_eq!(set, format!("export A=1\r\nexport B=2\r\n{}\r\nexport C=3\r\n{}", BLOCK_START, BLOCK_END));
+        assert_eq!(unset_content(&set, Syntax::Posix, "C").as_deref(), Some(env));
+        assert_eq!(unset_content(env, Syntax::Posix, "B").as_deref(), Some("export A=1"));
+        assert_eq!(unset_content("export A=1\nexport B=2\n", Syntax::Posix, "A").as_deref(), Some("export B=2\n"));
+    }
+
+    #[test]
+    fn comments_out_definitions() {
+        let path = std::env::temp_dir().join("globalenv_envfile_comment_out");
+        fs::write(&path, format!("  export EDITOR=vi\n{}\n# globalenv: secret\nexport EDITOR=vim\n{}\n", BLOCK_START, BLOCK_END)).unwrap();
+        assert_eq!(unset(&path, Syntax::Posix, "EDITOR", true).unwrap(), 2);
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!(
+                "  # export EDITOR=vi  # disabled by globalenv\n{}\n# export EDITOR=vim  # disabled by globalenv\n{}\n",
+                BLOCK_START, BLOCK_END
+            )
+        );
+        assert_eq!(get(&path, Syntax::Posix, "EDITOR").unwrap(), None);
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[test]
+    fn keeps_formatting_on_update() {
+        let env = format!("{}\n\n  # editor\n  export EDITOR=vi   # for now\n\nexport PAGER=less\n{}\n", BLOCK_START, BLOCK_END);
+        assert_eq!(
+            set_content(&env, Syntax::Posix, "EDITOR", "vim", &Meta::default()).unwrap(),
+            format!("{}\n\n  # editor\n  export EDITOR=vim   # for now\n\nexport PAGER=less\n{}\n", BLOCK_START, BLOCK_END)
+        );
+        assert_eq!(
+            unset_content(&env, Syntax::Posix, "PAGER").unwrap(),
+            format!("{}\n\n  # editor\n  export EDITOR=vi   # for now\n\n{}\n", BLOCK_START, BLOCK_END)
+        );
+    }
+
+    #[test]
+    fn appends_lazily() {
+        let path = std::env::temp_dir().join("globalenv_envfile_append");
+        fs::write(&path, "").unwrap();
+        for suffix in ["/opt/a", "/opt/b", "/opt/a"] {
+            append(&path, Syntax::Posix, "PATH", suffix, ":", &Meta::default()).unwrap();
+        }
+        assert_eq!(
+            fs::read_to_string(&path).unwrap(),
+            format!("{}\nexport PATH=\"${{PATH:+$PATH:}}/opt/a:/opt/b\"\n{}\n", BLOCK_START, BLOCK_END)
+        );
+        assert!(append(&path, Syntax::Fish, "FISH_PATH", "/opt/a", ":", &Meta::default()).is_err());
+        fs::remove_file(&path).unwrap();
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn unset_rewrites_link_target() {
+        use std::os::unix::fs::PermissionsExt;
+        let dir = std::env::temp_dir().join("globalenv_envfile_link");
+        let _ = fs::remove_dir_all(&dir);
+        fs::create_dir_all(&dir).unwrap();
+        let (target, link) = (dir.join("dotfiles_bashrc"), dir.join(".bashrc"));
+        fs::write(&target, format!("export KEEP=1\n{}\nexport FOO=bar\n{}\n", BLOCK_START, BLOCK_END)).unwrap();
+        fs::set_permissions(&target, fs::Permissions::from_mode(0o600)).unwrap();
+        std::os::unix::fs::symlink(&target, &link).unwrap();
+        unset(&link, Syntax::Posix, "FOO", false).unwrap();
+        assert!(fs::symlink_metadata(&link).unwrap().file_type().is_symlink());
+        assert_eq!(fs::read_to_string(&target).unwrap(), "export KEEP=1\n");
+        assert_eq!(fs::metadata(&target).unwrap().permissions().mode() & 0o777, 0o600);
+        fs::remove_dir_all(&dir).unwrap();
+    }
+}
@@ -0,0 +1,160 @@
+//! Values with their references to other variables expanded, as a new process observes them.
+
+use crate::{get_var_in, resolve_var_in, target::Store, EnvError, Target};
+use std::env;
+
+#[cfg(all(target_os = "windows", feature = "windows-sys", not(feature = "minimal")))]
+use windows_sys::Win32::System::Environment::ExpandEnvironmentStringsW;
+
+#[cfg(all(target_os = "windows", any(feature = "minimal", not(feature = "windows-sys"))))]
+#[link(name = "kernel32")]
+extern "system" {
+    fn ExpandEnvironmentStringsW(src: *const u16, dst: *mut u16, size: u32) -> u32;
+}
+
+/// Depth of the references resolved through other persisted values, which may refer to each other.
+const MAX_DEPTH: usize = 8;
+
+/// `%VAR%` references expanded by Windows, from the environment of the current process.
+#[cfg(target_os = "windows")]
+fn expand_windows(value: &str) -> Result<String, EnvError> {
+    let src: Vec<u16> = value.encode_utf16().chain(Some(0)).collect();
+    let mut expanded = vec![0u16; src.len().max(256)];
+    loop {
+        // Length needed, with the terminating null
+        let len = unsafe { ExpandEnvironmentStringsW(src.as_ptr(), expanded.as_mut_ptr(), expanded.len() as u32) } as usize;
+        match len {
+            0 => return Err(std::io::Error::last_os_error().into()),
+            len if len <= expanded.len() => return Ok(String::from_utf16_lossy(&expanded[..len - 1])),
+            len => expanded.resize(len, 0),
+        }
+    }
+}
+
+fn name_len(s: &str) -> usize {
+    s.find(|c: char| !(c.is_ascii_alphanumeric() || c == '_')).unwrap_or(s.len())
+}
+
+/// `${expr}`: a variable, or one of the `:+`, `:-`, `+` and `-` forms (the ones the crate writes to append).
+/// Other forms are left as they are.
+fn expand_braced(expr: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let (name, op) = expr.split_at(name_len(expr));
+    let value = lookup(name);
+    let (set, op) = match op.strip_prefix(':') {
+        Some(op) => (value.as_ref().is_some_and(|v| !v.is_empty()), op),
+        None => (value.is_some(), op),
+    };
+    match (op.chars().next(), set) {
+        _ if op.is_empty() && !name.is_empty() => value.unwrap_or_default(),
+        (Some('+'), true) => expand_posix(&op[1..], lookup),
+        (Some('-'), false) => expand_posix(&op[1..], lookup),
+        (Some('+'), false) => String::new(),
+        (Some('-'), true) => value.unwrap_or_default(),
+        _ => format!("${{{}}}", expr),
+    }
+}
+
+/// `$VAR` and `${VAR}` references expanded with the lookup, unset variables expanding to nothing (as in a shell).
+fn expand_posix(value: &str, lookup: &dyn Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(i) = rest.find('$') {
+        expanded.push_str(&rest[..i]);
+        let after = &rest[i + 1..];
+        if let Some(inner) = after.strip_prefix('{') {
+            let mut depth = 0;
+            let end = inner.char_indices().find_map(|(j, c)| match c {
+                '{' => {
+                    depth += 1;
+                    None
+                }
+                '}' if depth == 0 => Some(j),
+                '}' => {
+                    depth -= 1;
+                    None
+                }
+                _ => None,
+            });
+            match end {
+                Some(end) => {
+                    expanded.push_str(&expand_braced(&inner[..end], lookup));
+                    rest = &inner[end + 1..];
+                }
+                None => {
+                    expanded.push_str(&rest[i..]);
+                    rest = "";
+                }
+            }
+            continue;
+        }
+        let len = name_len(after);
+        if len == 0 || after.starts_with(|c: char| c.is_ascii_digit()) {
+            expanded.push('$');
+            rest = after;
+        } else {
+            expanded.push_str(&lookup(&after[..len]).unwrap_or_default());
+            rest = &after[len..];
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// Value of a variable a reference of `var` resolves to: the persisted one, itself expanded, or the one the
+/// process inherited (for `var` itself, e.g. `$PATH` in `PATH`, and for the variables not persisted, e.g. `HOME`).
+fn resolve(target: &Target, var: &str, name: &str, depth: usize) -> Option<String> {
+    if name == var || depth == MAX_DEPTH {
+        return env::var(name).ok();
+    }
+    match get_var_in(target, name) {
+        Ok(Some(value)) => Some(expand_posix(&value, &|other| resolve(target, name, other, depth + 1))),
+        _ => env::var(name).ok(),
+    }
+}
+
+/// Gets the value a new process observes: the persisted one (see [`resolve_var`](crate::resolve_var)), with
+/// its references to other variables expanded. On Windows, `%OTHER%` references are expanded by
+/// `ExpandEnvironmentStrings`; on Unix, `$OTHER` and `${OTHER}` ones are resolved against the persisted
+/// variables, themselves expanded, then against the inherited environment.
+/// Example:
+/// ```rust
+/// use globalenv::{get_var_expanded_in, Target};
+/// let file = std::env::temp_dir().join("globalenv_expanded_doctest");
+/// std::fs::write(&file, "export APP_ROOT=/opt/app\nexport APP_BIN=\"${APP_ROOT}/bin\"\n").unwrap();
+/// let target = Target::new().file(&file);
+/// assert_eq!(get_var_expanded_in(&target, "APP_BIN").unwrap().as_deref(), Some("/opt/app/bin"));
+/// ```
+pub fn get_var_expanded(var: &str) -> Result<Option<String>, EnvError> {
+    get_var_expanded_in(&Target::default(), var)
+}
+
+/// Gets the expanded value of a variable persisted in the given target.
+pub fn get_var_expanded_in(target: &Target, var: &str) -> Result<Option<String>, EnvError> {
+    let store = target.store()?;
+    let Some(value) = resolve_var_in(target, var)? else { return Ok(None) };
+    match store {
+        Store::File(..) => Ok(Some(expand_posix(&value, &|name| resolve(target, var, name, 0)))),
+        #[cfg(target_os = "windows")]
+        Store::Registry(_) => expand_windows(&value).map(Some),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_shell_references() {
+        let lookup = |name: &str| match name {
+            "ROOT" => Some(String::from("/opt/app")),
+            "EMPTY" => Some(String::new()),
+            _ => None,
+        };
+        assert_eq!(expand_posix("$ROOT/bin:${ROOT}/lib", &lookup), "/opt/app/bin:/opt/app/lib");
+        assert_eq!(expand_posix("${MISSING:+$MISSING:}/usr/bin", &lookup), "/usr/bin");
+        assert_eq!(expand_posix("${ROOT:+$ROOT:}/usr/bin", &lookup), "/opt/app:/usr/bin");
+        assert_eq!(expand_posix("${EMPTY:-default} ${EMPTY-default}", &lookup), "default ");
+        assert_eq!(expand_posix("cost: 5$ $1 $MISSING.", &lookup), "cost: 5$ $1 .");
+        assert_eq!(expand_posix("${ROOT#/opt} ${ROOT", &lookup), "${ROOT#/opt} ${ROOT");
+    }
+}
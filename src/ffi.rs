@@ -0,0 +1,136 @@
+//! C interface, for applications in other languages (C, C#, Go via cgo...). The shared library is built with
+//! `cargo rustc --lib --release --features ffi --crate-type cdylib`.
+//!
+//! Strings are nul-terminated UTF-8, and functions return `GLOBALENV_OK` or one of the (negative) error codes.
+
+use crate::{get_var, set_var, unset_var, EnvError};
+use std::{
+    ffi::{c_char, c_int, CStr, CString},
+    ptr,
+};
+
+pub const GLOBALENV_OK: c_int = 0;
+pub const GLOBALENV_UNSUPPORTED_SHELL: c_int = -1;
+pub const GLOBALENV_IO_ERROR: c_int = -2;
+pub const GLOBALENV_VAR_ERROR: c_int = -3;
+pub const GLOBALENV_PARSE_ERROR: c_int = -4;
+pub const GLOBALENV_POLICY_ERROR: c_int = -5;
+pub const GLOBALENV_VALIDATION_ERROR: c_int = -6;
+pub const GLOBALENV_UNSUPPORTED_PLATFORM: c_int = -7;
+/// Null pointer, or string which isn't UTF-8
+pub const GLOBALENV_INVALID_ARGUMENT: c_int = -8;
+pub const GLOBALENV_VALUE_TOO_LONG: c_int = -9;
+pub const GLOBALENV_INVALID_NAME: c_int = -10;
+pub const GLOBALENV_CONFLICT: c_int = -11;
+/// Written, but not approved by direnv
+pub const GLOBALENV_NOT_APPROVED: c_int = -12;
+
+fn code(e: &EnvError) -> c_int {
+    match e {
+        EnvError::UnsupportedShell => GLOBALENV_UNSUPPORTED_SHELL,
+        EnvError::IOError => GLOBALENV_IO_ERROR,
+        EnvError::VarError => GLOBALENV_VAR_ERROR,
+        EnvError::ParseError => GLOBALENV_PARSE_ERROR,
+        EnvError::PolicyError => GLOBALENV_POLICY_ERROR,
+        EnvError::ValidationError(_) => GLOBALENV_VALIDATION_ERROR,
+        EnvError::UnsupportedPlatform => GLOBALENV_UNSUPPORTED_PLATFORM,
+        EnvError::ValueTooLong(_) => GLOBALENV_VALUE_TOO_LONG,
+        EnvError::InvalidName(_) => GLOBALENV_INVALID_NAME,
+        EnvError::Conflict => GLOBALENV_CONFLICT,
+        EnvError::NotApproved(_) => GLOBALENV_NOT_APPROVED,
+    }
+}
+
+unsafe fn arg<'a>(s: *const c_char) -> Result<&'a str, c_int> {
+    if s.is_null() {
+        return Err(GLOBALENV_INVALID_ARGUMENT);
+    }
+    CStr::from_ptr(s).to_str().map_err(|_| GLOBALENV_INVALID_ARGUMENT)
+}
+
+fn status(result: Result<(), EnvError>) -> c_int {
+    match result {
+        Ok(()) => GLOBALENV_OK,
+        Err(e) => code(&e),
+    }
+}
+
+/// Sets a global environment variable (see [`set_var`]).
+///
+/// # Safety
+/// `var` and `value` must be null or point to nul-terminated strings.
+#[no_mangle]
+pub unsafe extern "C" fn globalenv_set(var: *const c_char, value: *const c_char) -> c_int {
+    match (arg(var), arg(value)) {
+        (Ok(var), Ok(value)) => status(set_var(var, value)),
+        (Err(e), _) | (_, Err(e)) => e,
+    }
+}
+
+/// Unsets a global environment variable (see [`unset_var`]).
+///
+/// # Safety
+/// `var` must be null or point to a nul-terminated string.
+#[no_mangle]
+pub unsafe extern "C" fn globalenv_unset(var: *const c_char) -> c_int {
+    match arg(var) {
+        Ok(var) => status(unset_var(var)),
+        Err(e) => e,
+    }
+}
+
+/// Gets the globally persisted value of a variable (see [`get_var`]) into `*value`, which is set to null
+/// if the variable isn't set. The value must be released with [`globalenv_free`].
+///
+/// # Safety
+/// `var` must be null or point to a nul-terminated string, and `value` must be null or writable.
+#[no_mangle]
+pub unsafe extern "C" fn globalenv_get(var: *const c_char, value: *mut *mut c_char) -> c_int {
+    if value.is_null() {
+        return GLOBALENV_INVALID_ARGUMENT;
+    }
+    *value = ptr::null_mut();
+    let var = match arg(var) {
+        Ok(var) => var,
+        Err(e) => return e,
+    };
+    match get_var(var) {
+        Ok(Some(found)) => match CString::new(found) {
+            Ok(found) => {
+                *value = found.into_raw();
+                GLOBALENV_OK
+            }
+            Err(_) => GLOBALENV_VAR_ERROR,
+        },
+        Ok(None) => GLOBALENV_OK,
+        Err(e) => code(&e),
+    }
+}
+
+/// Releases a value returned by [`globalenv_get`].
+///
+/// # Safety
+/// `value` must be null or have been returned by [`globalenv_get`], and not released yet.
+#[no_mangle]
+pub unsafe extern "C" fn globalenv_free(value: *mut c_char) {
+    if !value.is_null() {
+        drop(CString::from_raw(value));
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_arguments() {
+        unsafe {
+            assert_eq!(globalenv_set(ptr::null(), c"value".as_ptr()), GLOBALENV_INVALID_ARGUMENT);
+            assert_eq!(globalenv_unset(c"\xff".as_ptr()), GLOBALENV_INVALID_ARGUMENT);
+            assert_eq!(globalenv_get(c"VAR".as_ptr(), ptr::null_mut()), GLOBALENV_INVALID_ARGUMENT);
+            globalenv_free(ptr::null_mut());
+        }
+        assert_eq!(code(&EnvError::ValidationError(String::from("empty"))), GLOBALENV_VALIDATION_ERROR);
+        assert_eq!(code(&EnvError::ValueTooLong(crate::LengthLimit::Setx)), GLOBALENV_VALUE_TOO_LONG);
+    }
+}
@@ -0,0 +1,55 @@
+//! Combinators over listed variables, e.g. to work on an application's own namespace.
+
+use crate::{unset_var_in, EnvError, EnvVar, Target};
+
+/// Combinators over the [`EnvVar`]s returned by [`vars`](crate::vars) and [`managed_vars`](crate::managed_vars).
+/// Example:
+/// ```rust
+/// use globalenv::{managed_vars_in, set_var_in, Target, VarsExt};
+/// let file = std::env::temp_dir().join("globalenv_filter_doctest");
+/// std::fs::write(&file, "").unwrap();
+/// let target = Target::new().file(&file);
+/// for var in ["MYAPP_HOME", "MYAPP_MODE", "OTHER"] {
+///     set_var_in(&target, var, "1").unwrap();
+/// }
+/// let removed = managed_vars_in(&target).unwrap().into_iter().filter_prefix("MYAPP_").unset_all_in(&target).unwrap();
+/// assert_eq!(removed, ["MYAPP_HOME", "MYAPP_MODE"]);
+/// assert_eq!(managed_vars_in(&target).unwrap().len(), 1);
+/// ```
+pub trait VarsExt: Iterator<Item = EnvVar> + Sized {
+    /// Only the variables whose name starts with this prefix (ignoring case on Windows, like the OS).
+    fn filter_prefix(self, prefix: &str) -> impl Iterator<Item = EnvVar> {
+        self.filter(move |var| {
+            var.name.get(..prefix.len()).is_some_and(|head| {
+                if cfg!(target_os = "windows") { head.eq_ignore_ascii_case(prefix) } else { head == prefix }
+            })
+        })
+    }
+
+    /// Only the variables flagged as secret (see [`SetOptions::secret`](crate::SetOptions::secret)).
+    fn filter_secret(self) -> impl Iterator<Item = EnvVar> {
+        self.filter(|var| var.secret)
+    }
+
+    /// Names of the variables.
+    fn names(self) -> Vec<String> {
+        self.map(|var| var.name).collect()
+    }
+
+    /// Unsets the variables globally, returning their names.
+    fn unset_all(self) -> Result<Vec<String>, EnvError> {
+        self.unset_all_in(&Target::default())
+    }
+
+    /// Unsets the variables from the given target, returning their names.
+    fn unset_all_in(self, target: &Target) -> Result<Vec<String>, EnvError> {
+        let mut names = Vec::new();
+        for var in self {
+            unset_var_in(target, &var.name)?;
+            names.push(var.name);
+        }
+        Ok(names)
+    }
+}
+
+impl<I: Iterator<Item = EnvVar>> VarsExt for I {}
@@ -0,0 +1,145 @@
+//! Flatpak overrides, the environment of sandboxed applications (which do not inherit the session's).
+
+use crate::{EnvError, Scope, Target};
+use std::{env, fs, path::PathBuf};
+
+const SECTION: &str = "[Environment]";
+
+/// The override file of the application, as written by `flatpak override`: in the user's installation
+/// (`$XDG_DATA_HOME/flatpak/overrides`) for [`Scope::User`] (and [`Scope::Project`]), in the system one otherwise.
+fn override_file(target: &Target, app_id: &str) -> Result<PathBuf, EnvError> {
+    if app_id.is_empty() || app_id.starts_with('.') || app_id.contains(['/', '\\']) {
+        return Err(EnvError::VarError);
+    }
+    let overrides = match target.get_scope() {
+        Scope::System | Scope::Service => PathBuf::from("/var/lib/flatpak/overrides"),
+        Scope::User | Scope::Project(_) => {
+            let data_home = env::var_os("XDG_DATA_HOME").map(PathBuf::from).filter(|dir| dir.is_absolute() && !target.has_home());
+            data_home.unwrap_or(target.get_home()?.join(".local/share")).join("flatpak/overrides")
+        }
+    };
+    Ok(overrides.join(app_id))
+}
+
+/// Value escaped as in a GLib key file.
+fn escape(value: &str) -> String {
+    let mut escaped = String::new();
+    for (i, c) in value.chars().enumerate() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '\t' => escaped.push_str("\\t"),
+            '\r' => escaped.push_str("\\r"),
+            // Leading blanks would be trimmed
+            ' ' if i == 0 => escaped.push_str("\\s"),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// The key file with the variable set (or removed) in its `[Environment]` group, other groups and entries
+/// being left as they are. `None` if nothing changes.
+fn with_entry(content: &str, var: &str, value: Option<&str>) -> Option<String> {
+    let mut lines: Vec<String> = content.lines().map(String::from).collect();
+    let entry = value.map(|value| format!("{}={}", var, escape(value)));
+    let start = lines.iter().position(|l| l.trim() == SECTION);
+    let found = start.map(|start| {
+        let end = lines[start + 1..].iter().position(|l| l.starts_with('[')).map_or(lines.len(), |i| start + 1 + i);
+        let defined = (start + 1..end).find(|i| lines[*i].split_once('=').is_some_and(|(key, _)| key.trim() == var));
+        defined.ok_or(end)
+    });
+    match (found, entry) {
+        (Some(Ok(i)), Some(entry)) if lines[i] == entry => return None,
+        (Some(Ok(i)), Some(entry)) => lines[i] = entry,
+        (Some(Ok(i)), None) => {
+            lines.remove(i);
+        }
+        (Some(Err(end)), Some(entry)) => {
+            // After the group's last entry, before the blank lines separating it from the next
+            let mut at = end;
+            while at > 0 && lines[at - 1].trim().is_empty() {
+                at -= 1;
+            }
+            lines.insert(at, entry);
+        }
+        (None, Some(entry)) => {
+            if lines.last().is_some_and(|l| !l.trim().is_empty()) {
+                lines.push(String::new());
+            }
+            lines.push(String::from(SECTION));
+            lines.push(entry);
+        }
+        (_, None) => return None,
+    }
+    Some(lines.iter().map(|l| format!("{}\n", l)).collect())
+}
+
+/// Sets a variable in the environment of a Flatpak application, for the current user, as
+/// `flatpak override --user --env=VAR=value <app_id>` does. It applies the next time the application starts.
+/// Example:
+/// ```rust,no_run
+/// globalenv::set_var_flatpak("org.mozilla.firefox", "MOZ_ENABLE_WAYLAND", "1").unwrap();
+/// ```
+pub fn set_var_flatpak(app_id: &str, var: &str, value: &str) -> Result<(), EnvError> {
+    set_var_flatpak_in(&Target::default(), app_id, var, value)
+}
+
+/// Sets a variable in the environment of a Flatpak application, for every user if the target has the
+/// system scope (`/var/lib/flatpak/overrides`), for the target's user otherwise.
+pub fn set_var_flatpak_in(target: &Target, app_id: &str, var: &str, value: &str) -> Result<(), EnvError> {
+    target.check(var)?;
+    let file = override_file(target, app_id)?;
+    let content = match fs::read_to_string(&file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => String::new(),
+        Err(e) => return Err(e.into()),
+    };
+    if let Some(updated) = with_entry(&content, var, Some(value)) {
+        if let Some(dir) = file.parent() {
+            target.create_dir(dir)?;
+        }
+        fs::write(&file, updated)?;
+        target.own(&file)?;
+    }
+    Ok(())
+}
+
+/// Unsets a variable from the environment of a Flatpak application, returning whether it was set.
+pub fn unset_var_flatpak(app_id: &str, var: &str) -> Result<bool, EnvError> {
+    unset_var_flatpak_in(&Target::default(), app_id, var)
+}
+
+/// Unsets a variable from the environment of a Flatpak application, for the given target.
+pub fn unset_var_flatpak_in(target: &Target, app_id: &str, var: &str) -> Result<bool, EnvError> {
+    target.check(var)?;
+    let file = override_file(target, app_id)?;
+    let content = match fs::read_to_string(&file) {
+        Ok(content) => content,
+        Err(e) if e.kind() == std::io::ErrorKind::NotFound => return Ok(false),
+        Err(e) => return Err(e.into()),
+    };
+    match with_entry(&content, var, None) {
+        Some(updated) => {
+            fs::write(&file, updated)?;
+            Ok(true)
+        }
+        None => Ok(false),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_environment_group() {
+        let content = "[Context]\nshared=network;\n\n[Environment]\nA=1\n\n[Session Bus Policy]\norg.a=talk\n";
+        let updated = with_entry(content, "B", Some(" x\ty")).unwrap();
+        assert_eq!(updated, "[Context]\nshared=network;\n\n[Environment]\nA=1\nB=\\sx\\ty\n\n[Session Bus Policy]\norg.a=talk\n");
+        assert_eq!(with_entry(&updated, "B", None).as_deref(), Some(content));
+        assert_eq!(with_entry(content, "A", Some("1")), None);
+        assert_eq!(with_entry("[Context]\nshared=ipc;\n", "A", Some("1")).as_deref(), Some("[Context]\nshared=ipc;\n\n[Environment]\nA=1\n"));
+        assert_eq!(with_entry("", "A", None), None);
+    }
+}
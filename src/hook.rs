@@ -0,0 +1,156 @@
+//! Hook mode: the managed variables are kept in a private file, which the startup files source with a single line.
+
+use crate::{envfile::Endings, migrate_in, paths, syntax::quote_posix, target::{home_dir, Store}, EnvError, Shell, Target};
+use std::{fs, path::{Path, PathBuf}};
+
+/// Comment ending the line sourcing the hook file.
+const HOOK_MARK: &str = "# globalenv hook";
+/// Startup files of the POSIX shells which may source it.
+const HOOKED_FILES: [&str; 3] = [".bashrc", ".zshenv", ".profile"];
+/// Startup files which may have got it, see [`install_hook`].
+const UNHOOKED_FILES: [&str; 6] = [".bashrc", ".zshenv", ".profile", ".bash_profile", ".bash_login", crate::target::HAIKU_PROFILE];
+
+/// Line sourcing the hook file, understood by every POSIX shell. Relative to `$HOME` when possible, so the startup
+/// files can be shared between machines.
+fn hook_line(home: &Path, hook_file: &Path) -> String {
+    let path = match hook_file.strip_prefix(home).ok().and_then(Path::to_str) {
+        Some(relative) if !relative.contains(['"', '$', '`', '\\']) => format!("\"$HOME/{}\"", relative),
+        _ => quote_posix(&hook_file.to_string_lossy()),
+    };
+    format!("[ -f {0} ] && . {0} {1}", path, HOOK_MARK)
+}
+
+fn has_line(path: &Path, line: &str) -> bool {
+    fs::read_to_string(path).is_ok_and(|content| content.lines().any(|l| l.trim() == line))
+}
+
+/// The hook file, if this startup file sources it: the variables of the shell are then persisted in it.
+pub(crate) fn hooked(home: &Path, startup_file: &Path) -> Option<PathBuf> {
+    hooked_to(home, &paths().ok()?.env_file, startup_file)
+}
+
+fn hooked_to(home: &Path, hook_file: &Path, startup_file: &Path) -> Option<PathBuf> {
+    (hook_file.exists() && has_line(startup_file, &hook_line(home, hook_file))).then(|| hook_file.to_path_buf())
+}
+
+/// Switches to hook mode: the managed variables of the detected shell are moved to the `env_file` of
+/// [`paths`](fn@crate::paths) (`~/.config/globalenv/env.sh`),
+/// and a line sourcing it is added to `~/.bashrc`, `~/.zshenv` and `~/.profile` (those which exist) and to the
+/// startup file of the detected shell. Every later change is then a rewrite of this private file, the startup files being left
+/// alone. Returns the startup files which got the line. Only POSIX shells (sh, ksh, bash, zsh) are supported.
+/// Example:
+/// ```rust,no_run
+/// globalenv::install_hook().unwrap();
+/// assert!(globalenv::hook_installed().unwrap());
+/// ```
+pub fn install_hook() -> Result<Vec<PathBuf>, EnvError> {
+    let home = home_dir()?;
+    let shell = Shell::detect()?;
+    if !matches!(shell, Shell::Sh | Shell::Ksh | Shell::Bash | Shell::Zsh) {
+        return Err(EnvError::UnsupportedShell);
+    }
+    let hook_file = paths()?.env_file;
+    if let Some(parent) = hook_file.parent() {
+        fs::create_dir_all(parent)?;
+    }
+    let current = match Target::new().shell(shell).store()? {
+        Store::File(path, _) => path,
+        #[cfg(target_os = "windows")]
+        Store::Registry(_) => return Err(EnvError::UnsupportedShell),
+    };
+    if !hook_file.exists() {
+        fs::write(&hook_file, "")?;
+    }
+    let mut startup_files: Vec<PathBuf> = HOOKED_FILES.iter().map(|f| home.join(f)).filter(|p| p.exists()).collect();
+    if !startup_files.contains(&current) {
+        startup_files.push(current.clone());
+    }
+    let line = hook_line(&home, &hook_file);
+    let mut hooked = Vec::new();
+    for path in startup_files {
+        if has_line(&path, &line) {
+            continue;
+        }
+        let mut content = fs::read_to_string(&path).unwrap_or_default();
+        let newline = Endings::of(&content).newline();
+        if !content.is_empty() && !content.ends_with('\n') {
+            content.push_str(newline);
+        }
+        content.push_str(&line);
+        content.push_str(newline);
+        fs::write(&path, content)?;
+        hooked.push(path);
+    }
+    // Variables previously set in the startup file
+    if current != hook_file {
+        migrate_in(&Target::new().file(&current), &Target::new().file(&hook_file))?;
+    }
+    crate::invalidate();
+    Ok(hooked)
+}
+
+/// Leaves hook mode: the line sourcing `~/.config/globalenv/env.sh` is removed from every startup file, and the
+/// file deleted with the variables it holds. A startup file left empty, i.e. created by [`install_hook`], is
+/// deleted too. Returns the startup files which had the line.
+pub fn uninstall_hook() -> Result<Vec<PathBuf>, EnvError> {
+    let unhooked = unhook(&home_dir()?, &paths()?.env_file)?;
+    crate::invalidate();
+    Ok(unhooked)
+}
+
+fn unhook(home: &Path, hook_file: &Path) -> Result<Vec<PathBuf>, EnvError> {
+    let startup_files = UNHOOKED_FILES.iter().map(|f| home.join(f));
+    let mut unhooked = Vec::new();
+    for path in startup_files {
+        let Ok(content) = fs::read_to_string(&path) else { continue };
+        if !content.lines().any(|l| l.trim_end().ends_with(HOOK_MARK)) {
+            continue;
+        }
+        let content: String = content.split_inclusive('\n').filter(|l| !l.trim_end().ends_with(HOOK_MARK)).collect();
+        if content.is_empty() {
+            fs::remove_file(&path)?;
+        } else {
+            fs::write(&path, content)?;
+        }
+        unhooked.push(path);
+    }
+    if hook_file.exists() {
+        fs::remove_file(hook_file)?;
+    }
+    // The configuration directory, if nothing else is in it
+    if let Some(dir) = hook_file.parent() {
+        let _ = fs::remove_dir(dir);
+    }
+    Ok(unhooked)
+}
+
+/// Whether the startup file of the detected shell sources the hook file (see [`install_hook`]).
+pub fn hook_installed() -> Result<bool, EnvError> {
+    let hook_file = paths()?.env_file;
+    Ok(matches!(Target::default().store()?, Store::File(path, _) if path == hook_file))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_and_removes_hook() {
+        let home = std::env::temp_dir().join("globalenv_hook_home");
+        let hook_file = home.join(".config/globalenv/env.sh");
+        fs::create_dir_all(home.join(".config/globalenv")).unwrap();
+        let line = hook_line(&home, &hook_file);
+        assert_eq!(line, r#"[ -f "$HOME/.config/globalenv/env.sh" ] && . "$HOME/.config/globalenv/env.sh" # globalenv hook"#);
+        fs::write(home.join(".bashrc"), format!("alias ll='ls -l'\n{}\n", line)).unwrap();
+        let _ = fs::remove_file(&hook_file);
+        assert_eq!(hooked_to(&home, &hook_file, &home.join(".bashrc")), None);
+        fs::write(&hook_file, "").unwrap();
+        assert_eq!(hooked_to(&home, &hook_file, &home.join(".bashrc")), Some(hook_file.clone()));
+        assert_eq!(hooked_to(&home, &hook_file, &home.join(".zshenv")), None);
+        fs::write(home.join(".zshenv"), format!("{}\n", line)).unwrap();
+        assert_eq!(unhook(&home, &hook_file).unwrap(), [home.join(".bashrc"), home.join(".zshenv")]);
+        assert_eq!(fs::read_to_string(home.join(".bashrc")).unwrap(), "alias ll='ls -l'\n");
+        assert!(!home.join(".zshenv").exists() && !home.join(".config/globalenv").exists());
+        fs::remove_dir_all(&home).unwrap();
+    }
+}
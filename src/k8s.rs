@@ -0,0 +1,60 @@
+//! Kubernetes manifest snippets.
+
+use crate::{filtered_vars_in, EnvError, Filter, Target};
+
+/// YAML double-quoted scalar.
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '\r' => quoted.push_str("\\r"),
+            c if c.is_control() => quoted.push_str(&format!("\\u{:04x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+pub(crate) fn render(vars: &[(String, String)]) -> String {
+    let mut yaml = String::from("env:\n");
+    for (name, value) in vars {
+        let plain = name.chars().all(|c| c.is_ascii_alphanumeric() || matches!(c, '_' | '.' | '-'))
+            && name.starts_with(|c: char| c.is_ascii_alphabetic() || c == '_');
+        let name = if plain { name.clone() } else { quote(name) };
+        yaml.push_str(&format!("  - name: {}\n    value: {}\n", name, quote(value)));
+    }
+    yaml
+}
+
+/// Renders the globally managed variables as the `env:` list of a container in a pod spec.
+/// Example:
+/// ```rust,no_run
+/// print!("{}", globalenv::export_k8s_env().unwrap());
+/// ```
+pub fn export_k8s_env() -> Result<String, EnvError> {
+    export_k8s_env_in(&Target::default())
+}
+
+/// Renders the variables managed in the given target as the `env:` list of a container.
+pub fn export_k8s_env_in(target: &Target) -> Result<String, EnvError> {
+    Ok(render(&filtered_vars_in(target, Filter::Managed)?))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_env_list() {
+        let vars = vec![(String::from("PORT"), String::from("8080")), (String::from("MOTD"), String::from("a: \"b\"\n"))];
+        assert_eq!(
+            render(&vars),
+            "env:\n  - name: PORT\n    value: \"8080\"\n  - name: MOTD\n    value: \"a: \\\"b\\\"\\n\"\n"
+        );
+    }
+}
@@ -0,0 +1,262 @@
+//! Mirroring of the variables to distributed key-value stores (etcd, Consul), for fleet-wide defaults.
+
+use crate::{set_var_mirrored, EnvError, SetOptions, Target};
+use std::{
+    io::Write,
+    process::{Command, Stdio},
+    sync::{Arc, RwLock},
+};
+
+/// A key-value store to which changes are mirrored once [registered](register_kv_backend), and from which
+/// variables can be [materialized](materialize) locally.
+pub trait KvBackend: Send + Sync {
+    fn put(&self, var: &str, value: &str) -> Result<(), EnvError>;
+    fn delete(&self, var: &str) -> Result<(), EnvError>;
+    /// Variables of the store, with their values.
+    fn list(&self) -> Result<Vec<(String, String)>, EnvError>;
+}
+
+static BACKENDS: RwLock<Vec<Arc<dyn KvBackend>>> = RwLock::new(Vec::new());
+
+/// Mirrors the variables set and unset from now on (by this process, in any target) to this store, in addition
+/// to the local one.
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{register_kv_backend, Etcd};
+/// register_kv_backend(Etcd::new("/env/web"));
+/// // Also puts /env/web/APP_ENV
+/// globalenv::set_var("APP_ENV", "production").unwrap();
+/// ```
+pub fn register_kv_backend<B: KvBackend + 'static>(backend: B) {
+    BACKENDS.write().unwrap_or_else(|e| e.into_inner()).push(Arc::new(backend));
+}
+
+/// Applies a change (`None` to delete) to the registered stores, once it is persisted locally.
+pub(crate) fn mirror(var: &str, value: Option<&str>) -> Result<(), EnvError> {
+    let backends = BACKENDS.read().unwrap_or_else(|e| e.into_inner()).clone();
+    mirror_to(&backends, var, value)
+}
+
+fn mirror_to(backends: &[Arc<dyn KvBackend>], var: &str, value: Option<&str>) -> Result<(), EnvError> {
+    for backend in backends {
+        match value {
+            Some(value) => backend.put(var, value)?,
+            None => backend.delete(var)?,
+        }
+    }
+    Ok(())
+}
+
+/// Sets globally the variables of the store, returning their names.
+pub fn materialize(backend: &dyn KvBackend) -> Result<Vec<String>, EnvError> {
+    materialize_in(&Target::default(), backend)
+}
+
+/// Sets in the given target the variables of the store, returning their names. They aren't mirrored to the
+/// registered stores, the one they come from included.
+pub fn materialize_in(target: &Target, backend: &dyn KvBackend) -> Result<Vec<String>, EnvError> {
+    let mut names = Vec::new();
+    for (var, value) in backend.list()? {
+        set_var_mirrored(target, &var, &value, &SetOptions::default(), false)?;
+        names.push(var);
+    }
+    Ok(names)
+}
+
+/// Runs a store's command line tool, with the input on stdin.
+fn run(command: &mut Command, input: Option<&str>) -> Result<String, EnvError> {
+    let mut child = command
+        .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()?;
+    if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+        stdin.write_all(input.as_bytes())?;
+    }
+    let output = child.wait_with_output()?;
+    if !output.status.success() {
+        return Err(EnvError::IOError);
+    }
+    String::from_utf8(output.stdout).map_err(|_| EnvError::ParseError)
+}
+
+fn key(prefix: &str, var: &str) -> String {
+    format!("{}/{}", prefix.trim_end_matches('/'), var)
+}
+
+/// The variable a key of the listing names: directly under the prefix.
+fn var_of<'a>(prefix: &str, key: &'a str) -> Option<&'a str> {
+    let var = key.strip_prefix(prefix.trim_end_matches('/'))?.strip_prefix('/')?;
+    (!var.is_empty() && !var.contains('/')).then_some(var)
+}
+
+/// A value printed by the store's tool, which ends it with a newline.
+fn value_of(output: String) -> String {
+    output.strip_suffix('\n').map(String::from).unwrap_or(output)
+}
+
+/// etcd (v3 API), through `etcdctl`: variables are the keys `<prefix>/<VAR>`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Etcd {
+    prefix: String,
+    endpoints: Option<String>,
+}
+
+impl Etcd {
+    pub fn new(prefix: &str) -> Etcd {
+        Etcd { prefix: prefix.to_string(), endpoints: None }
+    }
+
+    /// Comma-separated endpoints, instead of those of `etcdctl`'s environment.
+    pub fn endpoints(mut self, endpoints: &str) -> Etcd {
+        self.endpoints = Some(endpoints.to_string());
+        self
+    }
+
+    fn etcdctl(&self) -> Command {
+        let mut etcdctl = Command::new("etcdctl");
+        if let Some(endpoints) = &self.endpoints {
+            etcdctl.arg(format!("--endpoints={}", endpoints));
+        }
+        etcdctl
+    }
+}
+
+impl KvBackend for Etcd {
+    fn put(&self, var: &str, value: &str) -> Result<(), EnvError> {
+        // The value is read from stdin, so that values starting with '-' aren't options
+        run(self.etcdctl().args(["put", &key(&self.prefix, var)]), Some(value)).map(drop)
+    }
+
+    fn delete(&self, var: &str) -> Result<(), EnvError> {
+        run(self.etcdctl().args(["del", &key(&self.prefix, var)]), None).map(drop)
+    }
+
+    /// Values are read one key at a time, as a listing of keys and values can't be split when one spans lines.
+    fn list(&self) -> Result<Vec<(String, String)>, EnvError> {
+        let keys = run(self.etcdctl().args(["get", "--prefix", "--keys-only", &key(&self.prefix, "")]), None)?;
+        let mut vars = Vec::new();
+        for key in keys.lines() {
+            if let Some(var) = var_of(&self.prefix, key) {
+                let value = run(self.etcdctl().args(["get", "--print-value-only", key]), None)?;
+                vars.push((var.to_string(), value_of(value)));
+            }
+        }
+        Ok(vars)
+    }
+}
+
+/// Consul's KV store, through the `consul` command: variables are the keys `<prefix>/<VAR>`.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct Consul {
+    prefix: String,
+    http_addr: Option<String>,
+}
+
+impl Consul {
+    pub fn new(prefix: &str) -> Consul {
+        Consul { prefix: prefix.trim_start_matches('/').to_string(), http_addr: None }
+    }
+
+    /// Address of the agent, instead of `CONSUL_HTTP_ADDR`.
+    pub fn http_addr(mut self, http_addr: &str) -> Consul {
+        self.http_addr = Some(http_addr.to_string());
+        self
+    }
+
+    fn consul(&self, subcommand: &str) -> Command {
+        let mut consul = Command::new("consul");
+        consul.args(["kv", subcommand]);
+        if let Some(http_addr) = &self.http_addr {
+            consul.arg(format!("-http-addr={}", http_addr));
+        }
+        consul
+    }
+}
+
+impl KvBackend for Consul {
+    fn put(&self, var: &str, value: &str) -> Result<(), EnvError> {
+        // "-" reads the value from stdin
+        run(self.consul("put").args([&key(&self.prefix, var), "-"]), Some(value)).map(drop)
+    }
+
+    fn delete(&self, var: &str) -> Result<(), EnvError> {
+        run(self.consul("delete").arg(key(&self.prefix, var)), None).map(drop)
+    }
+
+    fn list(&self) -> Result<Vec<(String, String)>, EnvError> {
+        let keys = run(self.consul("get").args(["-keys", &key(&self.prefix, "")]), None)?;
+        let mut vars = Vec::new();
+        for key in keys.lines() {
+            if let Some(var) = var_of(&self.prefix, key) {
+                let value = run(self.consul("get").arg(key), None)?;
+                vars.push((var.to_string(), value_of(value)));
+            }
+        }
+        Ok(vars)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{fs, sync::Mutex};
+
+    type Changes = Arc<Mutex<Vec<(String, Option<String>)>>>;
+
+    #[derive(Default)]
+    struct Recorder(Changes);
+
+    impl KvBackend for Recorder {
+        fn put(&self, var: &str, value: &str) -> Result<(), EnvError> {
+            self.0.lock().unwrap().push((var.to_string(), Some(value.to_string())));
+            Ok(())
+        }
+
+        fn delete(&self, var: &str) -> Result<(), EnvError> {
+            self.0.lock().unwrap().push((var.to_string(), None));
+            Ok(())
+        }
+
+        fn list(&self) -> Result<Vec<(String, String)>, EnvError> {
+            Ok(vec![(String::from("KV_MATERIALIZED"), String::from("1"))])
+        }
+    }
+
+    #[test]
+    fn mirrors_changes() {
+        let changes = Changes::default();
+        let backends: Vec<Arc<dyn KvBackend>> = vec![Arc::new(Recorder(changes.clone()))];
+        mirror_to(&backends, "KV_MIRRORED", Some("1")).unwrap();
+        mirror_to(&backends, "KV_MIRRORED", None).unwrap();
+        assert_eq!(*changes.lock().unwrap(), [(String::from("KV_MIRRORED"), Some(String::from("1"))), (String::from("KV_MIRRORED"), None)]);
+    }
+
+    #[test]
+    fn materializes_without_mirroring() {
+        let file = std::env::temp_dir().join("globalenv_kv");
+        fs::write(&file, "").unwrap();
+        let target = Target::new().file(&file);
+        // Registered only for the test: it records what the other tests set meanwhile, which is harmless
+        let changes = Changes::default();
+        let registered: Arc<dyn KvBackend> = Arc::new(Recorder(changes.clone()));
+        BACKENDS.write().unwrap().push(registered.clone());
+        let materialized = materialize_in(&target, &Recorder::default());
+        let set = crate::set_var_in(&target, "KV_MIRRORED", "1");
+        BACKENDS.write().unwrap().retain(|backend| !Arc::ptr_eq(backend, &registered));
+        assert_eq!(materialized.unwrap(), ["KV_MATERIALIZED"]);
+        set.unwrap();
+        assert_eq!(crate::get_var_in(&target, "KV_MATERIALIZED").unwrap().as_deref(), Some("1"));
+        let changes = changes.lock().unwrap();
+        assert!(changes.iter().any(|(var, _)| var == "KV_MIRRORED") && !changes.iter().any(|(var, _)| var == "KV_MATERIALIZED"));
+        fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn reads_listings() {
+        let keys = "/env/web/APP_ENV\n\n/env/web/nested/KEY\n\n/env/web/PORT\n\n/env/webapp/X\n";
+        let vars: Vec<_> = keys.lines().filter_map(|key| var_of("/env/web/", key)).collect();
+        assert_eq!(vars, ["APP_ENV", "PORT"]);
+        assert_eq!(value_of(String::from("line 1\nline 2\n")), "line 1\nline 2");
+    }
+}
@@ -1,27 +1,330 @@
 //! Globally set or unset environment variables (and not just for the current process).
-//! Support for Windows, zsh and bash (MacOS and most Linux distros).
+//! Support for Windows, zsh and bash (MacOS and most Linux distros), as well as sh, ksh and (t)csh (BSD and illumos defaults), fish, PowerShell, ion (Redox) and bash on Haiku.
+//! On other targets (e.g. wasm32), the crate builds but every operation fails with [`EnvError::UnsupportedPlatform`].
 //! Example:
 //! ```rust
 //! use globalenv::{set_var, unset_var};
 //! set_var("ENVTEST", "TESTVALUE").unwrap();
 //! unset_var("ENVTEST").unwrap();
 //! ```
+//!
+//! The `*_in` functions take a [`Target`] to choose the scope, the shell or an explicit file:
+//! ```rust,no_run
+//! use globalenv::{set_var_in, Scope, Target};
+//! set_var_in(&Target::new().scope(Scope::System), "ENVTEST", "TESTVALUE").unwrap();
+//! ```
+//!
+//! On Unix, [`Target::for_user`] targets another account, whose home directory and login shell are read from
+//! the passwd database, e.g. for provisioning scripts run as root, and [`Target::invoking_user`] the user who
+//! ran `sudo`. [`Scope::Project`] persists variables in the `.envrc` or `.env` file of a project directory instead,
+//! [`allow_direnv`] (or [`Target::direnv`], after every write) approving the `.envrc` for direnv.
+//!
+//! Names written in the code can be checked at compile time with [`env_name!`], which yields a [`VarName`] the
+//! functions taking a name accept.
+//!
+//! A [`GlobalEnv`](struct@GlobalEnv) session (opened on a target or built with [`GlobalEnv::builder`]) batches many
+//! operations on a target, which is only read and written once:
+//! it fails with [`EnvError::Conflict`] rather than overwrite a change another process made in the meantime.
+//! [`GlobalEnv::plan`] lists the changes it would write, for automation to inspect (as JSON, with the `serde`
+//! feature) and approve before [`Plan::apply`] writes them, with the same check.
+//!
+//! [`doctor`] reports the detected shell, its startup files and whether the file which would be written
+//! is actually read by new shells. [`diff_process_vs_global`] lists the persisted variables whose value differs in
+//! the current process, e.g. to tell the user to restart their shell. [`snapshot`] records the persisted variables
+//! as an [`EnvSnapshot`], and [`EnvSnapshot::diff`] lists the changes between two of them.
+//! [`find_conflicts`] reports the definitions of a variable in the other startup files, which may override it, and
+//! [`locate_var`] the one a new shell ends up with. [`resolve_var`] returns the value a new process gets, which on
+//! Windows merges the machine and user values (`Path` being the concatenation of both), and
+//! [`get_var_expanded`] that value with its references to other variables expanded.
+//! [`lint_env_file`] finds redundant definitions, values the shell would split and stale managed entries in a
+//! startup file, which [`fix_env_file`] removes or quotes.
+//!
+//! On Unix, variables are written in a block delimited by `# >>> globalenv >>>` and `# <<< globalenv <<<`
+//! comments, which [`managed_vars`] lists. On Windows, their names are recorded under `Software\globalenv\Managed`.
+//! [`vars`] and [`managed_vars`] return [`EnvVar`]s, telling where each variable is persisted, whether it is secret
+//! and when it was last modified; [`vars_with_metadata`] also tells when each of them was first set.
+//! [`VarsExt`] filters them, e.g. to list or remove only the variables of an application's namespace.
+//!
+//! [`migrate`] moves them to the startup file of another shell, e.g. when the user switches from bash to fish.
+//! With [`install_hook`], they are instead kept in `~/.config/globalenv/env.sh`, which the startup files of the
+//! POSIX shells source with a single line, so they are never edited again; [`uninstall_hook`] removes these lines
+//! and the file.
+//! The crate's own files (this one, the age identity...) are kept in the XDG directories returned by [`paths`](fn@paths).
+//!
+//! Writes failing transiently (a startup file locked by an editor or a sync client, a registry sharing
+//! violation) are retried with a backoff, which [`set_retry`] configures. With [`set_durable`], they are synced
+//! to the disk before returning.
+//!
+//! [`GlobalEnvConfig::set_defaults`] configures once for the process how values are quoted, whether startup files
+//! are backed up before being rewritten, whether definitions go in the managed block, and the default scope.
+//!
+//! A [`Policy`] (allowlist, denylist and protected names, possibly read from a file) restricts which variables
+//! a target lets modify.
+//!
+//! [`append_var`] appends to a variable (e.g. a directory to `PATH`) without flattening what it inherits.
+//! On Windows, it warns when the merged value exceeds a length limit of `setx` or cmd.exe ([`length_warning`]),
+//! which [`SetOptions::strict_length`] turns into an error when setting a value.
+//!
+//! [`set_var_with`] takes [`SetOptions`], e.g. to flag a variable as secret so reports redact its value, or to
+//! give it a time-to-live after which [`gc`] removes it, or to resolve placeholders such as `{home}` or `{config_dir}`
+//! for the platform ([`render_template`]), so installers write one value on every OS. [`unset_var_with`] takes [`UnsetOptions`], e.g. to
+//! comment the definitions out rather than remove them, or to remove them from every startup file;
+//! [`unset_var_report`] tells whether there was anything to remove. A [`Condition`] (an operating system, a host
+//! name) makes a definition conditional, for startup files shared across machines, and [`apply_for_host`] applies
+//! the [`Profile`] (a set of variables) whose host name patterns match this machine.
+//!
+//! [`render_script`] generates a script of the managed variables, which can be sourced (or, for `setx` batch
+//! scripts, run) on another machine,
+//! and [`export_reg`] a `.reg` file which `regedit` applies ([`import_reg`] replays such a file).
+//!
+//! [`import_dotenv`] and [`export_dotenv`] bulk-apply a `.env` file or dump the persisted variables into one,
+//! [`export_env_file`] writes the managed variables for `docker run --env-file`, [`export_k8s_env`] renders them
+//! as the `env:` list of a Kubernetes pod spec. [`import_env_output`] bulk-applies the output of `env` or `set`
+//! captured on another machine ([`parse_env_output`]).
+//!
+//! On Linux, [`write_systemd_dropin`] gives a systemd service the same variables as the shell, and
+//! [`set_var_flatpak`] sets a variable in the override file of a Flatpak application.
+//! On Unix, [`set_var_x11`] sets a variable for the applications launched from an X11 desktop, in `~/.xprofile`,
+//! and [`write_plasma_env`] gives a KDE Plasma session variables through a script it sources at startup.
+//! On Windows, [`Scope::Service`] targets `HKU\.DEFAULT\Environment`, the variables of the services running as
+//! SYSTEM, and `set_service_var` the environment of a single service. `add_pathext` and `remove_pathext` edit
+//! the `PATHEXT` list, e.g. for an installer of a script interpreter. `get_var_list` and `set_var_list` read and
+//! write list values (`REG_MULTI_SZ`), which `join_list` turns into the `;`-separated form processes expect, and
+//! `set_var_typed` stores numbers as `REG_DWORD`, possibly in an application's own key.
+//!
+//! [`set_var_ci`] passes variables to the next steps of a CI job (GitHub Actions, Azure Pipelines) or to the
+//! next jobs (GitLab CI dotenv report) when running in one.
+//!
+//! With the `serde` feature, the public data types (targets, reports, snapshots, plans, profiles...) implement `Serialize`
+//! and `Deserialize`, and `export_as` renders the persisted variables (or [`EnvSnapshot::export_as`] those of a
+//! snapshot) and their metadata as a JSON, TOML or YAML document, for inventory and backup tools, which
+//! `import_from` applies.
+//!
+//! With the `figment` feature, `GlobalEnvProvider` layers application configuration on the persisted variables.
+//!
+//! With the `keyring` feature, `set_secret` stores a value in the OS keychain and only persists a reference to it.
+//!
+//! With the `age` feature, `set_var_encrypted` stores a value encrypted in a sidecar file, decrypted by the
+//! shell at startup, for dotfiles kept in public repositories.
+//!
+//! With the `mmap` feature, read-only queries ([`get_var`], [`vars`]...) scan large startup files mapped in
+//! memory instead of copying them.
+//!
+//! With `default-features = false` (or the `minimal` feature), the only dependency is `libc` on Unix, and
+//! there is none on Windows: the registry API is declared by the crate itself instead of using `windows-sys`.
+//! The optional integrations (`serde`, `figment`, `keyring`, `age`, `derive`, `mmap`, `ffi`, `python`, `ssh`, `kv`, `elevate`) are off unless enabled.
+//!
+//! With the `elevate` feature, `set_var_system_elevated` sets a system variable from an unprivileged process,
+//! running the `globalenv` command through a UAC prompt on Windows, or `sudo` (`pkexec` without a terminal) on Unix.
+//!
+//! With the `derive` feature, `#[derive(GlobalEnv)]` maps the fields of a struct to (prefixed) variables.
+//!
+//! With the `kv` feature, changes can be mirrored to etcd or Consul (`register_kv_backend`), and the variables
+//! of such a store materialized locally.
+//!
+//! With the `ssh` feature, a `RemoteBackend` edits the startup files of remote hosts over SSH, for fleet
+//! provisioning.
+//!
+//! With the `python` feature, the crate is a Python module (`globalenv.set_var`, `unset_var`, `get_var` and
+//! `vars`, errors being raised as exceptions) built with [maturin](https://www.maturin.rs).
+//!
+//! The `globalenv_node` crate of the workspace is a Node.js binding (napi-rs, `setVar`, `unsetVar`, `getVar` and
+//! `vars`), for Electron apps and Node CLIs.
+//!
+//! With the `ffi` feature, the `ffi` module exposes `globalenv_set`, `globalenv_unset` and `globalenv_get`
+//! with the C ABI, and the crate can be built as a shared library for other languages.
+//!
+//! A `globalenv` command line tool (`set`, `unset`, `get`, `list`, `import`, `export`, `gc`, `doctor` and `completions` subcommands, with a `--json`
+//! output mode for automation) is also provided.
+
+use std::{env, fmt, error, sync::Arc, time::{Duration, SystemTime, UNIX_EPOCH}};
 
-use std::{env, fmt, error};
+mod capture;
+mod ci;
+mod condition;
+mod config;
+mod conflicts;
+mod direnv;
+mod doctor;
+mod dotenv;
+mod drift;
+mod durability;
+#[cfg(feature = "elevate")]
+mod elevate;
+#[cfg(feature = "age")]
+mod encrypted;
+mod expand;
+mod envfile;
+mod filter;
+#[cfg(target_os = "linux")]
+mod flatpak;
+#[cfg(feature = "ffi")]
+pub mod ffi;
+mod hook;
+mod k8s;
+#[cfg(feature = "kv")]
+mod kv;
+mod limits;
+#[cfg(target_os = "windows")]
+mod list;
+mod lint;
+mod meta;
+mod migrate;
+mod name;
+#[cfg(target_os = "windows")]
+mod pathext;
+#[cfg(target_family = "unix")]
+mod passwd;
+mod paths;
+mod plan;
+#[cfg(target_family = "unix")]
+mod plasma;
+mod policy;
+mod profile;
+#[cfg(feature = "figment")]
+mod provider;
+#[cfg(feature = "python")]
+mod python;
+mod reg;
+#[cfg(feature = "ssh")]
+mod remote;
+#[cfg(target_os = "windows")]
+mod registry;
+mod retry;
+mod script;
+#[cfg(feature = "keyring")]
+mod secret;
+#[cfg(target_os = "windows")]
+mod service;
+mod session;
+mod snapshot;
+#[cfg(feature = "serde")]
+mod structured;
+mod syntax;
+#[cfg(target_os = "linux")]
+mod systemd;
+mod target;
+mod template;
+mod tmux;
 #[cfg(target_os = "windows")]
-use winreg::{ enums::*, RegKey };
+mod typed;
+#[cfg(target_family = "unix")]
+mod x11;
 
+pub use capture::{import_env_output, import_env_output_in, parse_env_output};
+pub use ci::{set_var_ci, set_var_ci_in, CiBackend, GITLAB_DOTENV_FILE};
+pub use condition::Condition;
+pub use config::{Backup, BlockMode, GlobalEnvConfig, QuoteStyle};
+pub use conflicts::{find_conflicts, find_conflicts_in, locate_var, locate_var_in, resolve_var, resolve_var_in, Definition};
+/// Maps the fields of a struct to global variables. Requires the `derive` feature.
+/// Example:
+/// ```rust
+/// # let file = std::env::temp_dir().join("globalenv_derive_doc");
+/// # std::fs::write(&file, "").unwrap();
+/// use globalenv::{GlobalEnv, Target};
+///
+/// #[derive(GlobalEnv, Debug, PartialEq)]
+/// #[globalenv(prefix = "MYAPP_")]
+/// struct Settings {
+///     api_url: String,
+///     retries: u32,
+/// }
+///
+/// let target = Target::new().file(&file);
+/// let settings = Settings { api_url: String::from("https://api"), retries: 3 };
+/// // Sets MYAPP_API_URL and MYAPP_RETRIES
+/// settings.persist_in(&target).unwrap();
+/// assert_eq!(Settings::load_global_in(&target).unwrap(), settings);
+/// # std::fs::remove_file(&file).unwrap();
+/// ```
+#[cfg(feature = "derive")]
+pub use globalenv_derive::GlobalEnv;
+pub use direnv::{allow_direnv, allow_direnv_in, DirenvWarning};
+pub use doctor::{doctor, doctor_in, Diagnostics, StartupFile};
+pub use drift::{diff_process_vs_global, diff_process_vs_global_in, Drift};
+pub use durability::set_durable;
+pub use dotenv::{
+    export_dotenv, export_dotenv_in, export_env_file, export_env_file_in, import_dotenv, import_dotenv_in, render_dotenv,
+    render_dotenv_in, ImportOptions, ImportOutcome, ImportResult,
+};
+#[cfg(feature = "elevate")]
+pub use elevate::set_var_system_elevated;
+#[cfg(feature = "age")]
+pub use encrypted::{get_var_encrypted, set_var_encrypted, set_var_encrypted_in, unset_var_encrypted, unset_var_encrypted_in};
+pub use expand::{get_var_expanded, get_var_expanded_in};
+pub use filter::VarsExt;
+#[cfg(target_os = "linux")]
+pub use flatpak::{set_var_flatpak, set_var_flatpak_in, unset_var_flatpak, unset_var_flatpak_in};
+pub use hook::{hook_installed, install_hook, uninstall_hook};
+pub use k8s::{export_k8s_env, export_k8s_env_in};
+#[cfg(feature = "kv")]
+pub use kv::{materialize, materialize_in, register_kv_backend, Consul, Etcd, KvBackend};
+pub use limits::{length_warning, LengthLimit};
+#[cfg(target_os = "windows")]
+pub use list::{get_var_list, get_var_list_in, join_list, set_var_list, set_var_list_in, split_list};
+pub use lint::{fix_env_file, lint_env_file, Issue, IssueKind};
+#[cfg(feature = "figment")]
+pub use provider::GlobalEnvProvider;
+pub use migrate::{migrate, migrate_in};
+pub use name::VarName;
+#[cfg(target_os = "windows")]
+pub use pathext::{add_pathext, add_pathext_in, remove_pathext, remove_pathext_in};
+pub use paths::{paths, Paths};
+pub use plan::{Action, Change, Plan, PLAN_VERSION};
 #[cfg(target_family = "unix")]
-use std::{ fs, io::prelude::*, path::PathBuf, fs::OpenOptions };
+pub use plasma::{remove_plasma_env, remove_plasma_env_in, write_plasma_env, write_plasma_env_in};
+pub use policy::Policy;
+pub use profile::{apply_for_host, apply_for_host_in, Profile};
+pub use reg::{export_reg, export_reg_in, import_reg, import_reg_in};
+#[cfg(feature = "ssh")]
+pub use remote::RemoteBackend;
+pub use retry::{set_retry, Retry};
+pub use script::{render_script, render_script_in, Format};
+#[cfg(feature = "keyring")]
+pub use secret::{get_secret, get_secret_in, set_secret, set_secret_in, unset_secret, unset_secret_in};
+#[cfg(target_os = "linux")]
+pub use systemd::{remove_systemd_dropin, remove_systemd_dropin_in, write_systemd_dropin, write_systemd_dropin_in};
+#[cfg(target_os = "windows")]
+pub use service::{service_vars, set_service_var, unset_service_var};
+pub use session::{GlobalEnv, GlobalEnvBuilder};
+pub use snapshot::{snapshot, snapshot_in, EnvSnapshot};
+#[cfg(feature = "serde")]
+pub use structured::{export_as, export_as_in, import_from, import_from_in};
+pub use target::{invalidate, Scope, Shell, Target};
+pub use template::{render_template, render_template_in};
+#[cfg(target_os = "windows")]
+pub use typed::{get_var_typed, get_var_typed_in, set_var_typed, set_var_typed_in, Namespace, TypedValue};
+#[cfg(target_family = "unix")]
+pub use x11::{set_var_x11, set_var_x11_in, unset_var_x11, unset_var_x11_in};
+use target::Store;
 
 #[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
 pub enum EnvError {
     /// Unsupported shell
     UnsupportedShell,
     /// IO Error (file or registry operation)
     IOError,
     /// ENV error (can't get or set variable)
-    VarError
+    VarError,
+    /// Malformed file (e.g. unterminated quote in a .env file)
+    ParseError,
+    /// Modification of the variable denied by the policy
+    PolicyError,
+    /// Value rejected by a validator (see [`SetOptions::validator`]), with the reason
+    ValidationError(String),
+    /// Neither Windows nor Unix (e.g. wasm32): there is no environment to persist. Also a document format
+    /// requested from a build without the `serde` feature
+    UnsupportedPlatform,
+    /// Name which can't be the one of a variable (empty, with `=` or a NUL, too long for the registry, not a POSIX
+    /// name in a file), with the reason
+    InvalidName(String),
+    /// Value over a Windows length limit, rejected in strict mode (see [`SetOptions::strict_length`])
+    ValueTooLong(LengthLimit),
+    /// Storage changed by another process since it was read (see [`GlobalEnv::flush`]), left as it is
+    Conflict,
+    /// Change written, but the `.envrc` not approved by direnv as the target asked (see [`Target::direnv`]), with why
+    NotApproved(DirenvWarning),
 }
 
 impl error::Error for EnvError {}
@@ -29,10 +332,27 @@ impl error::Error for EnvError {}
 impl fmt::Display for EnvError {
     fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
         f.write_str("ENV operation error : ")?;
+        if let EnvError::ValidationError(reason) = self {
+            return write!(f, "invalid value ({})", reason);
+        }
+        if let EnvError::InvalidName(reason) = self {
+            return write!(f, "invalid variable name ({})", reason);
+        }
+        if let EnvError::ValueTooLong(limit) = self {
+            return write!(f, "value longer than {}", limit);
+        }
+        if let EnvError::NotApproved(warning) = self {
+            return write!(f, "written, but not approved ({})", warning);
+        }
         f.write_str(match self {
             EnvError::UnsupportedShell => "Unsupported shell",
+            EnvError::UnsupportedPlatform => "Unsupported platform",
             EnvError::IOError => "I/O error",
             EnvError::VarError => "error while getting or setting env",
+            EnvError::ParseError => "malformed file",
+            EnvError::PolicyError => "denied by policy",
+            EnvError::Conflict => "modified concurrently",
+            EnvError::ValidationError(_) | EnvError::InvalidName(_) | EnvError::ValueTooLong(_) | EnvError::NotApproved(_) => unreachable!(),
         })
     }
 }
@@ -49,106 +369,676 @@ impl From<std::env::VarError> for EnvError {
     }
 }
 
-#[cfg(target_os = "windows")]
 /// Sets a global environment variable, usable also in current process without reload.
 pub fn set_var(var: &str, value: &str) -> Result<(), EnvError> {
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    let key = hkcu.open_subkey_with_flags("Environment", KEY_SET_VALUE)?;
-    // Setting the variable globally
-    key.set_value(var, &value)?;
-    // Additionnaly, we set the env for current shell
-    env::set_var(var, value);
-    Ok(())
+    set_var_in(&Target::default(), var, value)
 }
 
-#[cfg(target_family = "unix")]
-/// Sets a global environment variable, usable also in current process without reload.
-pub fn set_var(var: &str, value: &str) -> Result<(), EnvError> {
-    // Getting env and building env file path
-    let homedir = env::var("HOME")?;
-    let shell = env::var("SHELL")?;
-    let envfile = match shell.as_str() {
-        "/usr/bin/zsh" => ".zshenv",
-        "/bin/zsh" => ".zshenv",
-        "/bin/bash" => ".bashrc",
-        _ => return Err(EnvError::UnsupportedShell)
-    };
+/// Sets an environment variable in the given target, and in the current process.
+pub fn set_var_in(target: &Target, var: &str, value: &str) -> Result<(), EnvError> {
+    set_var_with_in(target, var, value, &SetOptions::default())
+}
 
-    let mut envfilepath = PathBuf::from(homedir);
-    envfilepath.push(envfile);
+type Validator = Arc<dyn Fn(&str, &str) -> Result<(), String> + Send + Sync>;
+
+/// Options of [`set_var_with`].
+#[derive(Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct SetOptions {
+    secret: Option<bool>,
+    ttl: Option<Duration>,
+    #[cfg_attr(feature = "serde", serde(skip))]
+    validators: Vec<Validator>,
+    #[cfg_attr(feature = "serde", serde(default))]
+    strict_length: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    template: bool,
+    #[cfg_attr(feature = "serde", serde(default))]
+    condition: Option<Option<Condition>>,
+}
 
-    // Reading the env file
-    let env = fs::read_to_string(&envfilepath)?;
+impl fmt::Debug for SetOptions {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("SetOptions")
+            .field("secret", &self.secret)
+            .field("ttl", &self.ttl)
+            .field("validators", &self.validators.len())
+            .field("strict_length", &self.strict_length)
+            .field("template", &self.template)
+            .field("condition", &self.condition)
+            .finish()
+    }
+}
 
-    // Building the "export" line according to requested parameters
-    let mut export = String::from("export ");
-    export.push_str(var);
-    export.push_str("=");
-    export.push_str(value);
-    export.push_str("\n");
+/// Validators are compared by identity.
+impl PartialEq for SetOptions {
+    fn eq(&self, other: &SetOptions) -> bool {
+        self.secret == other.secret
+            && self.ttl == other.ttl
+            && self.validators.len() == other.validators.len()
+            && self.validators.iter().zip(&other.validators).all(|(a, b)| Arc::ptr_eq(a, b))
+            && self.strict_length == other.strict_length
+            && self.template == other.template
+            && self.condition == other.condition
+    }
+}
 
-    // Already present ? we just set the variable for current process
-    if env.contains(&export) { env::set_var(var, value); return Ok(()); }
+impl Eq for SetOptions {}
 
-    // Not present ? we append the env file to set it globally
-    let mut env_file = OpenOptions::new()
-        .append(true)
-        .create(true)
-        .open(envfilepath)?;
-    env_file.write(export.as_bytes())?;
+impl SetOptions {
+    pub fn new() -> SetOptions {
+        SetOptions::default()
+    }
+
+    /// Flags the variable as secret (or not): its value is still persisted as is, but redacted from
+    /// reports such as the `globalenv` command's output. By default the flag is left unchanged.
+    pub fn secret(mut self, secret: bool) -> SetOptions {
+        self.secret = Some(secret);
+        self
+    }
+
+    /// Makes the variable expire after this time: [`gc`] then removes it. Temporary credentials or
+    /// trial settings thus do not linger. By default the expiry is left unchanged.
+    /// Example:
+    /// ```rust,no_run
+    /// use globalenv::{set_var_with, SetOptions};
+    /// use std::time::Duration;
+    /// set_var_with("TRIAL_KEY", "abc", &SetOptions::new().ttl(Duration::from_secs(7 * 24 * 3600))).unwrap();
+    /// ```
+    pub fn ttl(mut self, ttl: Duration) -> SetOptions {
+        self.ttl = Some(ttl);
+        self
+    }
+
+    /// Adds a check of the variable name and value, run before anything is written: a rejection
+    /// fails the operation with [`EnvError::ValidationError`] and its reason.
+    /// Example:
+    /// ```rust
+    /// use globalenv::{set_var_with, EnvError, SetOptions};
+    /// let options = SetOptions::new().validator(|_, value| match value.starts_with("https://") {
+    ///     true => Ok(()),
+    ///     false => Err(String::from("not an https URL")),
+    /// });
+    /// assert_eq!(
+    ///     set_var_with("API_URL", "ftp://host", &options),
+    ///     Err(EnvError::ValidationError(String::from("not an https URL")))
+    /// );
+    /// ```
+    pub fn validator<F: Fn(&str, &str) -> Result<(), String> + Send + Sync + 'static>(mut self, validator: F) -> SetOptions {
+        self.validators.push(Arc::new(validator));
+        self
+    }
+
+    /// Rejects values over a Windows length limit (see [`length_warning`]) with [`EnvError::ValueTooLong`],
+    /// instead of letting `setx` or cmd.exe truncate them later. Only registry targets are checked.
+    /// Example:
+    /// ```rust,no_run
+    /// use globalenv::{set_var_with, SetOptions};
+    /// set_var_with("PATH", &std::env::var("PATH").unwrap(), &SetOptions::new().strict_length(true)).unwrap();
+    /// ```
+    pub fn strict_length(mut self, strict: bool) -> SetOptions {
+        self.strict_length = strict;
+        self
+    }
 
+    /// Resolves the placeholders of the value (`{home}`, `{config_dir}`...) before it is validated and written,
+    /// see [`render_template`].
+    /// Example:
+    /// ```rust,no_run
+    /// use globalenv::{set_var_with, SetOptions};
+    /// set_var_with("MYAPP_CONFIG", "{config_dir}/myapp", &SetOptions::new().template(true)).unwrap();
+    /// ```
+    pub fn template(mut self, template: bool) -> SetOptions {
+        self.template = template;
+        self
+    }
+
+    /// Makes the definition conditional (or not, with `None`): in a startup file, its line gets an `if` guard
+    /// testing the operating system or the host name, so one file can be shared across machines (e.g. in
+    /// dotfiles). The variable is only set in the current process, or in the registry on Windows, where the
+    /// condition holds. Ion, which can't test it, is an [`EnvError::UnsupportedShell`]. By default the
+    /// condition is left unchanged.
+    /// Example:
+    /// ```rust
+    /// use globalenv::{set_var_with_in, Condition, SetOptions, Target};
+    /// let file = std::env::temp_dir().join("globalenv_condition_doctest");
+    /// std::fs::write(&file, "").unwrap();
+    /// let options = SetOptions::new().condition(Some(Condition::Os(String::from("macos"))));
+    /// set_var_with_in(&Target::new().file(&file), "BROWSER", "open", &options).unwrap();
+    /// assert!(std::fs::read_to_string(&file).unwrap().contains(r#"if [ "$(uname -s)" = Darwin ]; then export BROWSER=open; fi"#));
+    /// ```
+    pub fn condition(mut self, condition: Option<Condition>) -> SetOptions {
+        self.condition = Some(condition);
+        self
+    }
+
+    /// The value to write, with its placeholders resolved if the options say so.
+    pub(crate) fn value<'a>(&self, target: &Target, value: &'a str) -> Result<std::borrow::Cow<'a, str>, EnvError> {
+        match self.template {
+            true => Ok(render_template_in(target, value)?.into()),
+            false => Ok(value.into()),
+        }
+    }
+
+    pub(crate) fn validate(&self, var: &str, value: &str) -> Result<(), EnvError> {
+        for validator in &self.validators {
+            validator(var, value).map_err(EnvError::ValidationError)?;
+        }
+        Ok(())
+    }
+
+    /// Metadata to write with the value, from the current ones.
+    pub(crate) fn meta(&self, previous: &meta::Meta, value_changed: bool) -> meta::Meta {
+        let mut meta = previous.clone();
+        if let Some(secret) = self.secret {
+            meta.secret = secret;
+        }
+        if let Some(ttl) = self.ttl {
+            meta.expires = Some(meta::now().saturating_add(ttl.as_secs()));
+        }
+        if let Some(condition) = &self.condition {
+            meta.when = condition.clone();
+        }
+        // Timestamps only change with the value or the flags
+        if meta != *previous || value_changed {
+            meta.touch();
+        }
+        meta
+    }
+}
+
+/// Sets a global environment variable with options.
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{set_var_with, SetOptions};
+/// set_var_with("API_TOKEN", "s3cr3t", &SetOptions::new().secret(true)).unwrap();
+/// ```
+pub fn set_var_with(var: &str, value: &str, options: &SetOptions) -> Result<(), EnvError> {
+    set_var_with_in(&Target::default(), var, value, options)
+}
+
+/// Sets an environment variable with options in the given target, and in the current process.
+pub fn set_var_with_in(target: &Target, var: &str, value: &str, options: &SetOptions) -> Result<(), EnvError> {
+    set_var_mirrored(target, var, value, options, true)
+}
+
+/// Sets the variable, mirroring it to the registered key-value stores if `mirror` (not when it comes from one).
+#[cfg_attr(not(feature = "kv"), allow(unused_variables))]
+pub(crate) fn set_var_mirrored(target: &Target, var: &str, value: &str, options: &SetOptions, mirror: bool) -> Result<(), EnvError> {
+    target.check(var)?;
+    let value = &*options.value(target, value)?;
+    options.validate(var, value)?;
+    let store = target.store()?;
+    let changed = stored_var_in(target, var)?.as_deref() != Some(value);
+    let meta = options.meta(&meta_of(&store, var)?, changed);
+    let applies = condition::applies(&meta, store.syntax())?;
+    // Setting the variable globally
+    let approval = match store {
+        Store::File(path, syntax) => {
+            envfile::set(&path, syntax, var, value, &meta)?;
+            direnv::approve(target, &path)
+        }
+        // The registry has no guards: a conditional variable is only set where the condition holds
+        #[cfg(target_os = "windows")]
+        Store::Registry(_) if !applies => return Ok(()),
+        #[cfg(target_os = "windows")]
+        Store::Registry(scope) => {
+            if let Some(limit) = length_warning(value).filter(|_| options.strict_length) {
+                return Err(EnvError::ValueTooLong(limit));
+            }
+            registry::set(&scope, var, value, &meta)?;
+            None
+        }
+    };
+    if !applies {
+        return direnv::approved(approval);
+    }
+    #[cfg(feature = "kv")]
+    if mirror {
+        kv::mirror(var, Some(value))?;
+    }
     // Additionnaly, we set the env for current process
     env::set_var(var, value);
-            
-    Ok(())
+    if target.get_tmux() {
+        tmux::set_environment(var, Some(value));
+    }
+    direnv::approved(approval)
 }
 
-#[cfg(target_os = "windows")]
-/// Unsets both global and local (process) environment variable.
-pub fn unset_var(var: &str) -> Result<(), EnvError> {
-    let hkcu = RegKey::predef(HKEY_CURRENT_USER);
-    let key = hkcu.open_subkey_with_flags("Environment", KEY_SET_VALUE)?;
-    key.delete_value(var)?;
-    env::remove_var(var);
-    Ok(())
+/// Appends a value to a variable globally, e.g. a directory to `PATH`, composing with whatever else defines it:
+/// on Unix, the line written is `export VAR="${VAR:+$VAR<sep>}suffix"`, which appends to the value the
+/// variable inherits when the shell starts (only POSIX shells support it); on Windows, the suffix is appended
+/// to the registry value, which becomes expandable (`REG_EXPAND_SZ`). Appending a suffix already there
+/// changes nothing. Returns the Windows length limit the merged registry value exceeds, if any (see
+/// [`length_warning`]): it is written anyway, but `setx` or cmd.exe may truncate it.
+/// Example:
+/// ```rust
+/// use globalenv::{append_var_in, Target};
+/// let file = std::env::temp_dir().join("globalenv_append_doctest");
+/// std::fs::write(&file, "").unwrap();
+/// assert_eq!(append_var_in(&Target::new().file(&file), "PATH", "/opt/myapp/bin", ":").unwrap(), None);
+/// assert!(std::fs::read_to_string(&file).unwrap().contains(r#"export PATH="${PATH:+$PATH:}/opt/myapp/bin""#));
+/// ```
+pub fn append_var(var: &str, suffix: &str, sep: &str) -> Result<Option<LengthLimit>, EnvError> {
+    append_var_in(&Target::default(), var, suffix, sep)
+}
+
+/// Appends a value to a variable in the given target, and in the current process.
+pub fn append_var_in(target: &Target, var: &str, suffix: &str, sep: &str) -> Result<Option<LengthLimit>, EnvError> {
+    target.check(var)?;
+    let store = target.store()?;
+    let meta = SetOptions::new().meta(&meta_of(&store, var)?, true);
+    let (warning, approval) = match store {
+        Store::File(path, syntax) => {
+            envfile::append(&path, syntax, var, suffix, sep, &meta)?;
+            (None, direnv::approve(target, &path))
+        }
+        #[cfg(target_os = "windows")]
+        Store::Registry(scope) => (length_warning(&registry::append(&scope, var, suffix, sep, &meta)?), None),
+    };
+    let value = match env::var(var) {
+        Ok(current) if current.split(sep).any(|s| s == suffix) => current,
+        Ok(current) if !current.is_empty() => format!("{}{}{}", current, sep, suffix),
+        _ => suffix.to_string(),
+    };
+    #[cfg(feature = "kv")]
+    kv::mirror(var, Some(&value))?;
+    env::set_var(var, &value);
+    if target.get_tmux() {
+        tmux::set_environment(var, Some(&value));
+    }
+    direnv::approved(approval)?;
+    Ok(warning)
 }
 
-#[cfg(target_family = "unix")]
 /// Unsets both global and local (process) environment variable.
 pub fn unset_var(var: &str) -> Result<(), EnvError> {
-    // Getting env and building env file path
-    let homedir = env::var("HOME")?;
-    let shell = env::var("SHELL")?;
-    let envfile = match shell.as_str() {
-        "/usr/bin/zsh" => ".zshenv",
-        "/bin/zsh" => ".zshenv",
-        "/bin/bash" => ".bashrc",
-        _ => return Err(EnvError::UnsupportedShell)
-    };
+    unset_var_in(&Target::default(), var)
+}
+
+/// Unsets an environment variable from the given target, and from the current process.
+pub fn unset_var_in(target: &Target, var: &str) -> Result<(), EnvError> {
+    unset_var_with_in(target, var, &UnsetOptions::default()).map(|_| ())
+}
+
+/// Unsets a variable globally and in the current process, returning whether it was persisted: `false` means it
+/// was only set in the process (or not at all), and nothing was removed from the startup file or the registry.
+/// Example:
+/// ```rust
+/// use globalenv::{set_var_in, unset_var_report_in, Target};
+/// let file = std::env::temp_dir().join("globalenv_unset_report_doctest");
+/// std::fs::write(&file, "").unwrap();
+/// let target = Target::new().file(&file);
+/// set_var_in(&target, "REPORTED", "1").unwrap();
+/// assert!(unset_var_report_in(&target, "REPORTED").unwrap());
+/// assert!(!unset_var_report_in(&target, "REPORTED").unwrap());
+/// ```
+pub fn unset_var_report(var: &str) -> Result<bool, EnvError> {
+    unset_var_report_in(&Target::default(), var)
+}
+
+/// Unsets a variable from the given target and from the current process, returning whether it was persisted.
+pub fn unset_var_report_in(target: &Target, var: &str) -> Result<bool, EnvError> {
+    Ok(unset_var_with_in(target, var, &UnsetOptions::default())? > 0)
+}
 
-    let mut envfilepath = PathBuf::from(homedir);
-    envfilepath.push(envfile);
+/// Options of [`unset_var_with`].
+#[derive(Debug, Clone, Default, PartialEq, Eq)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct UnsetOptions {
+    comment_out: bool,
+    remove_all: bool,
+}
 
-    // Reading the env file
-    let env = fs::read_to_string(&envfilepath)?;
+impl UnsetOptions {
+    pub fn new() -> UnsetOptions {
+        UnsetOptions::default()
+    }
 
-    // Building the "export" line according to requested parameters
-    let mut export = String::from("export ");
-    export.push_str(var);
-    export.push_str("=");
+    /// Comments out the lines defining the variable, as `# export VAR=value  # disabled by globalenv`, instead
+    /// of removing them, so the user can see and restore what was there. Only for startup files: registry
+    /// values are still deleted.
+    pub fn comment_out(mut self, comment_out: bool) -> UnsetOptions {
+        self.comment_out = comment_out;
+        self
+    }
 
-    // Variable not present in env file ? we just unset the variable for current process
-    if !env.contains(&export) { env::remove_var(var); return Ok(()); }
+    /// Also removes the definitions from the other startup files of the user's shell (in the home directory)
+    /// which a new shell reads, so none of them survives. By default only the target's file is changed.
+    pub fn remove_all(mut self, remove_all: bool) -> UnsetOptions {
+        self.remove_all = remove_all;
+        self
+    }
+}
 
-    // Present ? we remove it from the env file to unset it globally
-    let mut updated_env = String::new();
-    for l in env.lines() { if !l.contains(var) { updated_env.push_str(l); updated_env.push_str("\n") } }
-    fs::write(envfilepath, updated_env)?;
+/// Unsets a variable globally and in the current process, with options. Returns the number of definitions
+/// removed (a registry value counting as one).
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{unset_var_with, UnsetOptions};
+/// let removed = unset_var_with("JAVA_HOME", &UnsetOptions::new().remove_all(true)).unwrap();
+/// println!("{} definitions of JAVA_HOME removed", removed);
+/// ```
+pub fn unset_var_with(var: &str, options: &UnsetOptions) -> Result<usize, EnvError> {
+    unset_var_with_in(&Target::default(), var, options)
+}
 
+/// Unsets a variable from the given target and from the current process, with options.
+pub fn unset_var_with_in(target: &Target, var: &str, options: &UnsetOptions) -> Result<usize, EnvError> {
+    target.check(var)?;
+    let (removed, approval) = match target.store()? {
+        Store::File(path, syntax) => {
+            let others = if options.remove_all {
+                let home = target.get_home()?;
+                let files = conflicts::read_files(target, path.clone(), syntax);
+                files.into_iter().filter(|(p, _)| *p != path && p.starts_with(&home)).collect()
+            } else {
+                Vec::new()
+            };
+            let mut removed = envfile::unset(&path, syntax, var, options.comment_out)?;
+            let approval = direnv::approve(target, &path);
+            for (other, syntax) in others {
+                if other.exists() {
+                    removed += envfile::unset(&other, syntax, var, options.comment_out)?;
+                }
+            }
+            (removed, approval)
+        }
+        #[cfg(target_os = "windows")]
+        Store::Registry(scope) => (usize::from(registry::unset(&scope, var)?), None),
+    };
+    #[cfg(feature = "kv")]
+    kv::mirror(var, None)?;
     // Additionnaly, we unset the env for current process
     env::remove_var(var);
-            
-    Ok(())
+    if target.get_tmux() {
+        tmux::set_environment(var, None);
+    }
+    direnv::approved(approval)?;
+    Ok(removed)
+}
+
+/// Persists globally the current process values of these variables, e.g. after an interactive configuration
+/// step, in one batch (see [`GlobalEnv`](struct@GlobalEnv)). Fails with [`EnvError::VarError`], without writing
+/// anything, if one of them isn't set (or isn't Unicode).
+/// Example:
+/// ```rust,no_run
+/// std::env::set_var("EDITOR", "vim");
+/// globalenv::persist_from_process(&["EDITOR", "VISUAL"]).unwrap();
+/// ```
+pub fn persist_from_process(vars: &[&str]) -> Result<(), EnvError> {
+    persist_from_process_in(&Target::default(), vars)
+}
+
+/// Persists in the given target the current process values of these variables.
+/// Example:
+/// ```rust
+/// let file = std::env::temp_dir().join("globalenv_persist_doctest");
+/// std::fs::write(&file, "").unwrap();
+/// let target = globalenv::Target::new().file(&file);
+/// std::env::set_var("APP_REGION", "eu-west-1");
+/// globalenv::persist_from_process_in(&target, &["APP_REGION"]).unwrap();
+/// assert_eq!(globalenv::get_var_in(&target, "APP_REGION").unwrap().as_deref(), Some("eu-west-1"));
+/// ```
+pub fn persist_from_process_in(target: &Target, vars: &[&str]) -> Result<(), EnvError> {
+    let values = vars.iter().map(env::var).collect::<Result<Vec<_>, _>>()?;
+    let mut session = GlobalEnv::open_in(target)?;
+    for (var, value) in vars.iter().zip(&values) {
+        session.set(var, value)?;
+    }
+    session.flush()
+}
+
+/// Unsets the global variables whose time-to-live (see [`SetOptions::ttl`]) has elapsed, returning their names.
+/// Example:
+/// ```rust,no_run
+/// for name in globalenv::gc().unwrap() {
+///     println!("{} expired", name);
+/// }
+/// ```
+pub fn gc() -> Result<Vec<String>, EnvError> {
+    gc_in(&Target::default())
+}
+
+/// Unsets the expired variables of the given target, returning their names.
+/// Example:
+/// ```rust
+/// use globalenv::{gc_in, get_var_in, set_var_with_in, SetOptions, Target};
+/// use std::time::Duration;
+/// let file = std::env::temp_dir().join("globalenv_gc_doctest");
+/// std::fs::write(&file, "").unwrap();
+/// let target = Target::new().file(&file);
+/// set_var_with_in(&target, "TRIAL_KEY", "abc", &SetOptions::new().ttl(Duration::ZERO)).unwrap();
+/// assert_eq!(gc_in(&target).unwrap(), vec![String::from("TRIAL_KEY")]);
+/// assert_eq!(get_var_in(&target, "TRIAL_KEY").unwrap(), None);
+/// ```
+pub fn gc_in(target: &Target) -> Result<Vec<String>, EnvError> {
+    let store = target.store()?;
+    let mut expired = Vec::new();
+    for (name, _) in filtered_vars_in(target, Filter::Managed)? {
+        if meta_of(&store, &name)?.is_expired() {
+            unset_var_in(target, &name)?;
+            expired.push(name);
+        }
+    }
+    Ok(expired)
+}
+
+/// Gets the globally persisted value of a variable (which may differ from the current process value). On Unix,
+/// it is the value a new shell ends up with: the startup files the detected shell reads are searched in that
+/// order (see [`locate_var`]), as one of them may override the file the crate writes.
+pub fn get_var(var: &str) -> Result<Option<String>, EnvError> {
+    get_var_in(&Target::default(), var)
+}
+
+/// Gets the value of a variable persisted in the given target (the one a new shell ends up with, unless the
+/// target is an explicit file or the system scope).
+pub fn get_var_in(target: &Target, var: &str) -> Result<Option<String>, EnvError> {
+    match target.store()? {
+        Store::File(path, syntax) => match conflicts::read_files(target, path, syntax).as_slice() {
+            [(path, syntax)] => envfile::get(path, *syntax, var),
+            files => Ok(conflicts::last_definition(files, var).map(|definition| definition.value)),
+        },
+        #[cfg(target_os = "windows")]
+        Store::Registry(scope) => registry::get(&scope, var),
+    }
+}
+
+/// Value of the variable in the target's own file or registry key.
+pub(crate) fn stored_var_in(target: &Target, var: &str) -> Result<Option<String>, EnvError> {
+    match target.store()? {
+        Store::File(path, syntax) => envfile::get(&path, syntax, var),
+        #[cfg(target_os = "windows")]
+        Store::Registry(scope) => registry::get(&scope, var),
+    }
+}
+
+pub(crate) fn meta_of(store: &Store, var: &str) -> Result<meta::Meta, EnvError> {
+    match store {
+        Store::File(path, syntax) => envfile::meta(path, *syntax, var),
+        #[cfg(target_os = "windows")]
+        Store::Registry(scope) => registry::meta(scope, var),
+    }
+}
+
+/// Whether the globally persisted variable is flagged as secret (see [`SetOptions::secret`]).
+pub fn is_secret(var: &str) -> Result<bool, EnvError> {
+    is_secret_in(&Target::default(), var)
+}
+
+/// Whether the variable persisted in the given target is flagged as secret.
+pub fn is_secret_in(target: &Target, var: &str) -> Result<bool, EnvError> {
+    Ok(meta_of(&target.store()?, var)?.secret)
+}
+
+/// Metadata of a variable set through this crate.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct VarMetadata {
+    /// Flagged as secret (see [`SetOptions::secret`])
+    pub secret: bool,
+    /// Stored encrypted in a sidecar file
+    pub encrypted: bool,
+    /// Time after which [`gc`] removes the variable
+    pub expires: Option<SystemTime>,
+    /// Time it was first set (`None` if set by a version not recording it)
+    pub created: Option<SystemTime>,
+    /// Time its value or flags were last changed
+    pub updated: Option<SystemTime>,
+}
+
+impl From<meta::Meta> for VarMetadata {
+    fn from(meta: meta::Meta) -> VarMetadata {
+        let time = |secs: Option<u64>| secs.map(|secs| UNIX_EPOCH + Duration::from_secs(secs));
+        VarMetadata {
+            secret: meta.secret,
+            encrypted: meta.encrypted,
+            expires: time(meta.expires),
+            created: time(meta.created),
+            updated: time(meta.updated),
+        }
+    }
+}
+
+/// Lists the variables set globally through this crate with their value and metadata, e.g. to find stale ones.
+/// Example:
+/// ```rust,no_run
+/// use std::time::{Duration, SystemTime};
+/// let year_ago = SystemTime::now() - Duration::from_secs(365 * 24 * 3600);
+/// for (name, _, metadata) in globalenv::vars_with_metadata().unwrap() {
+///     if metadata.updated.is_some_and(|updated| updated < year_ago) {
+///         println!("{} has not changed for a year", name);
+///     }
+/// }
+/// ```
+pub fn vars_with_metadata() -> Result<Vec<(String, String, VarMetadata)>, EnvError> {
+    vars_with_metadata_in(&Target::default())
+}
+
+/// Lists the variables set in the given target through this crate, with their value and metadata.
+/// Example:
+/// ```rust
+/// use globalenv::{set_var_in, vars_with_metadata_in, Target};
+/// let file = std::env::temp_dir().join("globalenv_metadata_doctest");
+/// std::fs::write(&file, "").unwrap();
+/// let target = Target::new().file(&file);
+/// set_var_in(&target, "APP_MODE", "trial").unwrap();
+/// let (name, value, metadata) = vars_with_metadata_in(&target).unwrap().remove(0);
+/// assert_eq!((name.as_str(), value.as_str()), ("APP_MODE", "trial"));
+/// assert!(metadata.updated.is_some() && metadata.created == metadata.updated);
+/// ```
+pub fn vars_with_metadata_in(target: &Target) -> Result<Vec<(String, String, VarMetadata)>, EnvError> {
+    let store = target.store()?;
+    filtered_vars_in(target, Filter::Managed)?
+        .into_iter()
+        .map(|(name, value)| {
+            let metadata = meta_of(&store, &name)?.into();
+            Ok((name, value, metadata))
+        })
+        .collect()
+}
+
+/// Which persisted variables are listed or exported.
+#[derive(Debug, PartialEq, Eq, Clone, Copy, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Filter {
+    /// Every variable of the target
+    #[default]
+    All,
+    /// Only the variables set through this crate
+    Managed,
+}
+
+/// A persisted variable, with where it comes from. Its `Debug` output redacts the value of a secret.
+#[derive(PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnvVar {
+    pub name: String,
+    pub value: String,
+    /// Scope of the target it was read from
+    pub scope: Scope,
+    /// File or registry key it is persisted in (see [`Target::location`])
+    pub source: String,
+    /// Flagged as secret (see [`SetOptions::secret`])
+    pub secret: bool,
+    /// Time its value or flags were last changed through this crate, if recorded
+    pub modified_at: Option<SystemTime>,
+}
+
+impl fmt::Debug for EnvVar {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.debug_struct("EnvVar")
+            .field("name", &self.name)
+            .field("value", &if self.secret { plan::REDACTED } else { &self.value })
+            .field("scope", &self.scope)
+            .field("source", &self.source)
+            .field("secret", &self.secret)
+            .field("modified_at", &self.modified_at)
+            .finish()
+    }
+}
+
+impl From<EnvVar> for (String, String) {
+    fn from(var: EnvVar) -> (String, String) {
+        (var.name, var.value)
+    }
+}
+
+/// Lists the globally persisted variables and their values.
+pub fn vars() -> Result<Vec<EnvVar>, EnvError> {
+    vars_in(&Target::default())
+}
+
+/// Lists the variables persisted in the given target.
+/// Example:
+/// ```rust
+/// use globalenv::{set_var_in, vars_in, Target};
+/// let file = std::env::temp_dir().join("globalenv_vars_doctest");
+/// std::fs::write(&file, "export EDITOR=vim\n").unwrap();
+/// let target = Target::new().file(&file);
+/// set_var_in(&target, "APP_MODE", "trial").unwrap();
+/// let vars = vars_in(&target).unwrap();
+/// assert_eq!((vars[0].name.as_str(), vars[0].modified_at), ("EDITOR", None));
+/// assert_eq!(vars[1].source, file.to_str().unwrap());
+/// assert!(vars[1].modified_at.is_some());
+/// ```
+pub fn vars_in(target: &Target) -> Result<Vec<EnvVar>, EnvError> {
+    env_vars_in(target, Filter::All)
+}
+
+/// Lists the variables set globally through this crate (on Unix, the ones of the block delimited by
+/// `# >>> globalenv >>>` and `# <<< globalenv <<<` comments).
+pub fn managed_vars() -> Result<Vec<EnvVar>, EnvError> {
+    managed_vars_in(&Target::default())
+}
+
+/// Lists the variables set in the given target through this crate.
+pub fn managed_vars_in(target: &Target) -> Result<Vec<EnvVar>, EnvError> {
+    env_vars_in(target, Filter::Managed)
+}
+
+pub(crate) fn env_vars_in(target: &Target, filter: Filter) -> Result<Vec<EnvVar>, EnvError> {
+    let store = target.store()?;
+    let source = target.location()?;
+    filtered_vars_in(target, filter)?
+        .into_iter()
+        .map(|(name, value)| {
+            let metadata = VarMetadata::from(meta_of(&store, &name)?);
+            Ok(EnvVar { name, value, scope: target.get_scope(), source: source.clone(), secret: metadata.secret, modified_at: metadata.updated })
+        })
+        .collect()
+}
+
+/// Names and values of the variables, without their metadata.
+pub(crate) fn filtered_vars_in(target: &Target, filter: Filter) -> Result<Vec<(String, String)>, EnvError> {
+    match (target.store()?, filter) {
+        (Store::File(path, syntax), Filter::All) => envfile::vars(&path, syntax),
+        (Store::File(path, syntax), Filter::Managed) => envfile::managed_vars(&path, syntax),
+        #[cfg(target_os = "windows")]
+        (Store::Registry(scope), Filter::All) => registry::vars(&scope),
+        #[cfg(target_os = "windows")]
+        (Store::Registry(scope), Filter::Managed) => registry::managed_vars(&scope),
+    }
 }
 
 /* Run the tests in a single thread context !
@@ -210,13 +1100,13 @@ mod tests {
             _ => panic!("Unsupported shell")
         };
 
-        let mut envfilepath = crate::PathBuf::from(homedir);
+        let mut envfilepath = std::path::PathBuf::from(homedir);
         envfilepath.push(envfile);
 
         // Reading the env file
-        let env = crate::fs::read_to_string(&envfilepath).unwrap();
+        let env = std::fs::read_to_string(&envfilepath).unwrap();
 
-        assert_eq!(env.contains("export ENVTEST=TESTVALUE\n"), true);
+        assert!(env.contains("export ENVTEST=TESTVALUE\n"));
     }
 
     #[test]
@@ -237,13 +1127,13 @@ mod tests {
             _ => panic!("Unsupported shell")
         };
 
-        let mut envfilepath = crate::PathBuf::from(homedir);
+        let mut envfilepath = std::path::PathBuf::from(homedir);
         envfilepath.push(envfile);
 
         // Reading the env file
-        let env = crate::fs::read_to_string(&envfilepath).unwrap();
+        let env = std::fs::read_to_string(&envfilepath).unwrap();
 
-        assert_eq!(env.contains("export ENVTEST=TESTVALUE\n"), false);
+        assert!(!env.contains("export ENVTEST=TESTVALUE\n"));
     }
 
     #[test]
@@ -254,3 +1144,17 @@ mod tests {
 }
 
     
+
+#[cfg(not(any(target_os = "windows", target_family = "unix")))]
+#[cfg(test)]
+mod tests {
+    use crate::EnvError;
+    #[test]
+    fn is_unsupported() {
+        assert_eq!(crate::set_var("ENVTEST", "TESTVALUE"), Err(EnvError::UnsupportedPlatform));
+        assert_eq!(crate::get_var("ENVTEST"), Err(EnvError::UnsupportedPlatform));
+        assert_eq!(crate::unset_var("ENVTEST"), Err(EnvError::UnsupportedPlatform));
+        assert_eq!(crate::vars(), Err(EnvError::UnsupportedPlatform));
+        assert!(crate::GlobalEnv::open().is_err());
+    }
+}
@@ -0,0 +1,64 @@
+//! Length limits of Windows environment values, beyond which other tools silently truncate them.
+
+use std::fmt;
+
+/// A limit on the length of a value, in UTF-16 code units, see [`length_warning`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum LengthLimit {
+    /// 1024: `setx` truncates longer values, e.g. when a script rewrites `PATH` with it
+    Setx,
+    /// 8191: cmd.exe fails to expand longer values (and command lines)
+    CommandLine,
+    /// 32767: the size of a process environment variable, longer values are not passed to processes
+    Variable,
+}
+
+impl LengthLimit {
+    /// Maximum length of a value.
+    pub fn max(&self) -> usize {
+        match self {
+            LengthLimit::Setx => 1024,
+            LengthLimit::CommandLine => 8191,
+            LengthLimit::Variable => 32767,
+        }
+    }
+}
+
+impl fmt::Display for LengthLimit {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let tool = match self {
+            LengthLimit::Setx => "setx",
+            LengthLimit::CommandLine => "cmd.exe",
+            LengthLimit::Variable => "the process environment",
+        };
+        write!(f, "{} characters ({})", self.max(), tool)
+    }
+}
+
+/// The highest of the limits a value exceeds, if any. `%VAR%` references are
+/// counted as written, not expanded. The registry itself takes longer values: they are only truncated, or
+/// dropped, by the tools reading them.
+/// Example:
+/// ```rust
+/// use globalenv::{length_warning, LengthLimit};
+/// assert_eq!(length_warning(&"C:\\bin;".repeat(200)), Some(LengthLimit::Setx));
+/// assert_eq!(length_warning("C:\\bin"), None);
+/// ```
+pub fn length_warning(value: &str) -> Option<LengthLimit> {
+    let len = value.encode_utf16().count();
+    [LengthLimit::Variable, LengthLimit::CommandLine, LengthLimit::Setx].into_iter().find(|limit| len > limit.max())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_exceeded_limit() {
+        assert_eq!(length_warning(&"a".repeat(1024)), None);
+        assert_eq!(length_warning(&"a".repeat(1025)), Some(LengthLimit::Setx));
+        assert_eq!(length_warning(&"é".repeat(8192)), Some(LengthLimit::CommandLine));
+        assert_eq!(length_warning(&"a".repeat(40000)), Some(LengthLimit::Variable));
+    }
+}
@@ -0,0 +1,149 @@
+//! Lint of a startup file: redundant definitions, values the shell would split, stale managed entries.
+
+use crate::{envfile::{parse_meta_line, Endings, BLOCK_END, BLOCK_START}, syntax::Syntax, EnvError, Shell};
+use std::{fs, path::Path};
+
+/// What is wrong with a line.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum IssueKind {
+    /// The variable is defined again later with the same value
+    Duplicate,
+    /// The variable is defined again later with another value, so this definition has no effect
+    Shadowed,
+    /// Unquoted value containing spaces, of which the shell only exports the first word
+    UnquotedSpaces,
+    /// Managed entry whose time-to-live has elapsed, or metadata comment without its definition
+    Stale,
+}
+
+/// An issue found by [`lint_env_file`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Issue {
+    /// Line number, from 1
+    pub line: usize,
+    /// Variable defined by the line, if any
+    pub var: Option<String>,
+    pub kind: IssueKind,
+}
+
+/// The value as written, if it is unquoted and has spaces.
+fn split_value(syntax: Syntax, line: &str) -> Option<&str> {
+    if syntax != Syntax::Posix {
+        return None;
+    }
+    let raw = line.trim().strip_prefix("export ")?.split_once('=')?.1.trim();
+    (!raw.starts_with(['\'', '"', '$']) && raw.contains(char::is_whitespace) && !raw.contains(" #")).then_some(raw)
+}
+
+/// Issues, in line order (0-based line indexes).
+fn lint(lines: &[&str], syntax: Syntax) -> Vec<(usize, Option<String>, IssueKind)> {
+    let definitions: Vec<(usize, &str, String)> =
+        lines.iter().enumerate().filter_map(|(i, l)| syntax.parse(l).map(|(name, value)| (i, name, value))).collect();
+    let mut issues = Vec::new();
+    for (i, line) in lines.iter().enumerate() {
+        if let Some(meta) = parse_meta_line(line) {
+            let defined = lines.get(i + 1).and_then(|next| syntax.parse(next));
+            match defined {
+                None => issues.push((i, None, IssueKind::Stale)),
+                Some((name, _)) if meta.is_expired() => issues.push((i + 1, Some(name.to_string()), IssueKind::Stale)),
+                Some(_) => (),
+            }
+            continue;
+        }
+        let Some((_, name, value)) = definitions.iter().find(|(d, _, _)| *d == i) else { continue };
+        // Already reported with its metadata
+        if issues.iter().any(|(l, _, kind)| *l == i && *kind == IssueKind::Stale) {
+            continue;
+        }
+        match definitions.iter().rev().find(|(d, n, _)| n == name && *d > i) {
+            Some((_, _, last)) => {
+                let kind = if last == value { IssueKind::Duplicate } else { IssueKind::Shadowed };
+                issues.push((i, Some(name.to_string()), kind));
+            }
+            None if split_value(syntax, line).is_some() => issues.push((i, Some(name.to_string()), IssueKind::UnquotedSpaces)),
+            None => (),
+        }
+    }
+    issues
+}
+
+/// Finds the issues of a startup file, whose syntax is guessed from its name.
+/// Example:
+/// ```rust
+/// use globalenv::{lint_env_file, IssueKind};
+/// let file = std::env::temp_dir().join("globalenv_lint_doctest");
+/// std::fs::write(&file, "export EDITOR=vi\nexport EDITOR=vim\n").unwrap();
+/// let issues = lint_env_file(&file).unwrap();
+/// assert_eq!((issues[0].line, issues[0].kind), (1, IssueKind::Shadowed));
+/// ```
+pub fn lint_env_file<P: AsRef<Path>>(path: P) -> Result<Vec<Issue>, EnvError> {
+    let content = fs::read_to_string(path.as_ref())?;
+    let lines: Vec<&str> = content.lines().collect();
+    let issues = lint(&lines, Shell::syntax_of(path.as_ref()));
+    Ok(issues.into_iter().map(|(line, var, kind)| Issue { line: line + 1, var, kind }).collect())
+}
+
+/// Rewrites the file without its issues, returning them: redundant and stale definitions are removed (with their
+/// metadata), and values with spaces quoted.
+pub fn fix_env_file<P: AsRef<Path>>(path: P) -> Result<Vec<Issue>, EnvError> {
+    let content = fs::read_to_string(path.as_ref())?;
+    let lines: Vec<&str> = content.lines().collect();
+    let syntax = Shell::syntax_of(path.as_ref());
+    let issues = lint(&lines, syntax);
+    if issues.is_empty() {
+        return Ok(Vec::new());
+    }
+    let mut fixed: Vec<Option<String>> = lines.iter().map(|l| Some(l.to_string())).collect();
+    for (i, var, kind) in &issues {
+        match kind {
+            IssueKind::UnquotedSpaces => {
+                let (var, value) = (var.as_deref().unwrap_or_default(), split_value(syntax, lines[*i]).unwrap_or_default());
+                fixed[*i] = Some(syntax.render(var, value));
+            }
+            _ => {
+                fixed[*i] = None;
+                if *i > 0 && parse_meta_line(lines[i - 1]).is_some() {
+                    fixed[i - 1] = None;
+                }
+            }
+        }
+    }
+    let mut fixed: Vec<String> = fixed.into_iter().flatten().collect();
+    // The managed block left empty
+    if let Some(i) = fixed.windows(2).position(|pair| pair[0].trim() == BLOCK_START && pair[1].trim() == BLOCK_END) {
+        fixed.drain(i..i + 2);
+    }
+    crate::config::backup(path.as_ref())?;
+    crate::retry::write(path.as_ref(), Endings::of(&content).join(&fixed))?;
+    Ok(issues.into_iter().map(|(line, var, kind)| Issue { line: line + 1, var, kind }).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn finds_and_fixes_issues() {
+        let file = std::env::temp_dir().join("globalenv_lint");
+        fs::write(
+            &file,
+            "export A=1\nexport B=x y\nexport A=1\nexport C=old\n# >>> globalenv >>>\n# globalenv: expires=1\nexport D=1\n\
+             # globalenv: secret\nexport C=new\n# <<< globalenv <<<\n",
+        )
+        .unwrap();
+        let kinds: Vec<_> = lint_env_file(&file).unwrap().into_iter().map(|i| (i.line, i.kind)).collect();
+        assert_eq!(
+            kinds,
+            [(1, IssueKind::Duplicate), (2, IssueKind::UnquotedSpaces), (4, IssueKind::Shadowed), (7, IssueKind::Stale)]
+        );
+        assert_eq!(fix_env_file(&file).unwrap().len(), 4);
+        assert_eq!(
+            fs::read_to_string(&file).unwrap(),
+            "export B='x y'\nexport A=1\n# >>> globalenv >>>\n# globalenv: secret\nexport C=new\n# <<< globalenv <<<\n"
+        );
+        assert_eq!(lint_env_file(&file).unwrap(), []);
+        fs::remove_file(&file).unwrap();
+    }
+}
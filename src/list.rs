@@ -0,0 +1,112 @@
+//! List values of the registry (`REG_MULTI_SZ`), kept by some tools in the environment keys.
+
+use crate::{meta_of, registry, set_var_in, target::Store, EnvError, SetOptions, Target};
+use std::env;
+
+/// `%VAR%` references expanded with the lookup, unknown ones being left as they are (as Windows does).
+fn expand_with(value: &str, lookup: impl Fn(&str) -> Option<String>) -> String {
+    let mut expanded = String::new();
+    let mut rest = value;
+    while let Some(start) = rest.find('%') {
+        expanded.push_str(&rest[..start]);
+        let after = &rest[start + 1..];
+        match after.find('%') {
+            Some(end) => match lookup(&after[..end]).filter(|_| end > 0) {
+                Some(value) => {
+                    expanded.push_str(&value);
+                    rest = &after[end + 1..];
+                }
+                // The closing `%` may open the next reference
+                None => {
+                    expanded.push('%');
+                    expanded.push_str(&after[..end]);
+                    rest = &after[end..];
+                }
+            },
+            None => {
+                expanded.push_str(&rest[start..]);
+                rest = "";
+            }
+        }
+    }
+    expanded.push_str(rest);
+    expanded
+}
+
+/// The list as the value of a variable: its elements joined with `;`, their `%VAR%` references expanded from
+/// the current process environment. Processes do not get `REG_MULTI_SZ` values in their environment, this is
+/// the form they expect lists in.
+/// Example:
+/// ```rust
+/// assert_eq!(globalenv::join_list(&[r"C:\Tools", r"D:\bin"]), r"C:\Tools;D:\bin");
+/// ```
+pub fn join_list<S: AsRef<str>>(list: &[S]) -> String {
+    let lookup = |name: &str| env::var(name).ok();
+    list.iter().map(|s| expand_with(s.as_ref(), lookup)).collect::<Vec<_>>().join(";")
+}
+
+/// Elements of a `;`-separated value, without the empty ones.
+pub fn split_list(value: &str) -> Vec<String> {
+    value.split(';').filter(|s| !s.is_empty()).map(String::from).collect()
+}
+
+/// Gets a list variable: the strings of a `REG_MULTI_SZ` value, or the elements of a string value separated
+/// with `;`.
+/// Example:
+/// ```rust,no_run
+/// let dirs = globalenv::get_var_list("TOOL_DIRS").unwrap().unwrap_or_default();
+/// ```
+pub fn get_var_list(var: &str) -> Result<Option<Vec<String>>, EnvError> {
+    get_var_list_in(&Target::default(), var)
+}
+
+/// Gets a list variable from the given target, a file target splitting the value.
+pub fn get_var_list_in(target: &Target, var: &str) -> Result<Option<Vec<String>>, EnvError> {
+    match target.store()? {
+        Store::Registry(scope) => registry::get_list(&scope, var),
+        Store::File(..) => Ok(crate::get_var_in(target, var)?.map(|value| split_list(&value))),
+    }
+}
+
+/// Sets a list variable, as a `REG_MULTI_SZ` value, for the tools which read it so. The current process gets it
+/// as [`join_list`] renders it.
+/// Example:
+/// ```rust,no_run
+/// globalenv::set_var_list("TOOL_DIRS", &[r"%ProgramFiles%\Tool", r"D:\bin"]).unwrap();
+/// ```
+pub fn set_var_list<S: AsRef<str>>(var: &str, list: &[S]) -> Result<(), EnvError> {
+    set_var_list_in(&Target::default(), var, list)
+}
+
+/// Sets a list variable in the given target, a file target getting the elements joined with `;`.
+pub fn set_var_list_in<S: AsRef<str>>(target: &Target, var: &str, list: &[S]) -> Result<(), EnvError> {
+    target.check(var)?;
+    let list: Vec<String> = list.iter().map(|s| s.as_ref().to_string()).collect();
+    let store = target.store()?;
+    let scope = match &store {
+        Store::Registry(scope) => scope,
+        Store::File(..) => return set_var_in(target, var, &list.join(";")),
+    };
+    let meta = SetOptions::new().meta(&meta_of(&store, var)?, true);
+    registry::set_list(scope, var, &list, &meta)?;
+    let joined = join_list(&list);
+    #[cfg(feature = "kv")]
+    crate::kv::mirror(var, Some(&joined))?;
+    env::set_var(var, joined);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn expands_references() {
+        let lookup = |name: &str| (name == "ROOT").then(|| String::from(r"C:\App"));
+        assert_eq!(expand_with(r"%ROOT%\bin", lookup), r"C:\App\bin");
+        assert_eq!(expand_with("%MISSING%%ROOT%", lookup), r"%MISSING%C:\App");
+        assert_eq!(expand_with("100% %ROOT", lookup), "100% %ROOT");
+        assert_eq!(expand_with("%%ROOT%", lookup), r"%C:\App");
+        assert_eq!(split_list("a;;b;"), vec![String::from("a"), String::from("b")]);
+    }
+}
@@ -0,0 +1,693 @@
+//! `globalenv` command line tool.
+
+use globalenv::{
+    doctor_in, gc_in, get_var_in, import_dotenv_in, import_reg_in, is_secret_in, managed_vars_in, render_dotenv_in, render_script_in,
+    set_var_with_in, unset_var_in, vars_in, Diagnostics, DirenvWarning, EnvError, Filter, Format, ImportOptions, ImportOutcome, ImportResult, Policy,
+    Scope, SetOptions, Target,
+};
+use std::{env, fmt, fs, process, time::Duration};
+
+const USAGE: &str = "Usage: globalenv [OPTIONS] <COMMAND>
+
+Commands:
+  set <NAME> <VALUE>    Sets a variable globally
+  unset <NAME>          Unsets a variable globally
+  get <NAME>            Prints the persisted value of a variable
+  list                  Lists the persisted variables
+  import <FILE>         Sets globally every variable of a .env (or .reg, .json, .toml, .yaml) file
+  export [FILE]         Writes the persisted variables to a file (default: standard output)
+  gc                    Unsets the variables whose time-to-live has elapsed
+  doctor                Diagnoses shell detection and the target file
+  completions <SHELL>   Prints the completion script for bash, zsh, fish or powershell
+
+Options:
+  --scope <SCOPE>       user (default), system, service or project (the .envrc or .env of the current directory)
+  --shell <SHELL>       sh, ksh, bash, zsh, tcsh, fish, powershell or ion (default: detected from SHELL)
+  --file <PATH>         Persists in this file instead
+  --policy <FILE>       Only modifies the variables this policy file permits
+  --allow-protected     Lets the variables protected by the policy be modified
+  --format <FORMAT>     Export format: dotenv (default), a shell, batch or powershell-apply for a script of the managed variables
+                        (json, toml or yaml for a document, when built with the serde feature)
+  --secret              Set: flags the variable as secret, its value being hidden from reports
+  --ttl <SECONDS>       Set: makes the variable expire, gc then unsetting it
+  --keep-existing       Import: keeps variables already persisted with another value
+  --managed             List, export: only the variables set through globalenv
+  --tmux                Set, unset, import: also updates the running tmux server
+  --direnv              Set, unset: runs direnv allow on the .envrc written, warning if it fails
+  --json                Prints the outcome as JSON
+  -h, --help            Prints this help
+  -V, --version         Prints the version";
+
+#[derive(Debug, PartialEq, Eq)]
+enum Command {
+    Set(String, String),
+    Unset(String),
+    Get(String),
+    List,
+    Import(String),
+    Export(Option<String>),
+    Gc,
+    Doctor,
+    Completions(String),
+    Help,
+    Version,
+}
+
+impl Command {
+    fn name(&self) -> &'static str {
+        match self {
+            Command::Set(..) => "set",
+            Command::Unset(_) => "unset",
+            Command::Get(_) => "get",
+            Command::List => "list",
+            Command::Import(_) => "import",
+            Command::Export(_) => "export",
+            Command::Gc => "gc",
+            Command::Doctor => "doctor",
+            Command::Completions(_) => "completions",
+            Command::Help => "help",
+            Command::Version => "version",
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+struct Args {
+    target: Target,
+    set: SetOptions,
+    import: ImportOptions,
+    filter: Filter,
+    /// Kind of exported script, `.env` format if `None`
+    format: Option<Format>,
+    json: bool,
+    command: Command,
+}
+
+fn parse_args<I: Iterator<Item = String>>(mut args: I) -> Result<Args, String> {
+    let mut target = Target::new();
+    let mut set = SetOptions::new();
+    let mut import = ImportOptions::new();
+    let mut filter = Filter::All;
+    let mut format = None;
+    let mut json = false;
+    let mut positional = Vec::new();
+    while let Some(arg) = args.next() {
+        let mut flag_value = || args.next().ok_or(format!("missing value for {}", arg));
+        match arg.as_str() {
+            "-h" | "--help" => return Ok(Args { target, set, import, filter, format, json, command: Command::Help }),
+            "-V" | "--version" => return Ok(Args { target, set, import, filter, format, json, command: Command::Version }),
+            "--scope" => {
+                let scope = match flag_value()?.as_str() {
+                    "user" => Scope::User,
+                    "system" => Scope::System,
+                    "service" => Scope::Service,
+                    "project" => Scope::Project(env::current_dir().map_err(|e| e.to_string())?),
+                    s => return Err(format!("unknown scope: {}", s)),
+                };
+                target = target.scope(scope);
+            }
+            "--shell" => {
+                let shell = flag_value()?;
+                target = target.shell(shell.parse().map_err(|_| format!("unsupported shell: {}", shell))?);
+            }
+            "--file" => target = target.file(flag_value()?),
+            "--policy" => {
+                let file = flag_value()?;
+                target = target.policy(Policy::from_file(&file).map_err(|e| format!("{}: {}", file, e))?);
+            }
+            "--allow-protected" => target = target.allow_protected(true),
+            "--format" => match flag_value()?.as_str() {
+                "dotenv" => format = None,
+                f => format = Some(f.parse().map_err(|_| format!("unknown format: {}", f))?),
+            },
+            "--secret" => set = set.secret(true),
+            "--ttl" => {
+                let ttl = flag_value()?;
+                set = set.ttl(Duration::from_secs(ttl.parse().map_err(|_| format!("invalid time-to-live: {}", ttl))?));
+            }
+            "--keep-existing" => import = import.overwrite(false),
+            "--managed" => filter = Filter::Managed,
+            "--tmux" => target = target.tmux(true),
+            "--direnv" => target = target.direnv(true),
+            "--json" => json = true,
+            // The next arguments are positional, e.g. a value starting with a dash
+            "--" => {
+                positional.extend(args.by_ref());
+                break;
+            }
+            s if s.starts_with('-') && s.len() > 1 => return Err(format!("unknown option: {}", s)),
+            _ => positional.push(arg),
+        }
+    }
+
+    let mut positional = positional.into_iter();
+    let command = match positional.next().as_deref() {
+        Some("set") => match (positional.next(), positional.next()) {
+            (Some(name), Some(value)) => Command::Set(name, value),
+            _ => return Err(String::from("set requires a name and a value")),
+        },
+        Some("unset") => Command::Unset(positional.next().ok_or("unset requires a name")?),
+        Some("get") => Command::Get(positional.next().ok_or("get requires a name")?),
+        Some("list") => Command::List,
+        Some("import") => Command::Import(positional.next().ok_or("import requires a file")?),
+        Some("export") => Command::Export(positional.next()),
+        Some("gc") => Command::Gc,
+        Some("doctor") => Command::Doctor,
+        Some("completions") => match positional.next() {
+            Some(shell) if completion_script(&shell).is_some() => Command::Completions(shell),
+            Some(shell) => return Err(format!("no completions for shell: {}", shell)),
+            None => return Err(String::from("completions requires a shell")),
+        },
+        Some(c) => return Err(format!("unknown command: {}", c)),
+        None => return Err(String::from("missing command")),
+    };
+    if let Some(extra) = positional.next() {
+        return Err(format!("unexpected argument: {}", extra));
+    }
+    Ok(Args { target, set, import, filter, format, json, command })
+}
+
+/// Minimal JSON value, enough for the command reports.
+enum Json {
+    Null,
+    Bool(bool),
+    Str(String),
+    Array(Vec<Json>),
+    Object(Vec<(&'static str, Json)>),
+}
+
+impl From<&str> for Json {
+    fn from(s: &str) -> Json {
+        Json::Str(s.to_string())
+    }
+}
+
+impl From<Option<String>> for Json {
+    fn from(s: Option<String>) -> Json {
+        s.map_or(Json::Null, Json::Str)
+    }
+}
+
+impl fmt::Display for Json {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Json::Null => f.write_str("null"),
+            Json::Bool(b) => write!(f, "{}", b),
+            Json::Str(s) => {
+                f.write_str("\"")?;
+                for c in s.chars() {
+                    match c {
+                        '"' => f.write_str("\\\"")?,
+                        '\\' => f.write_str("\\\\")?,
+                        '\n' => f.write_str("\\n")?,
+                        '\r' => f.write_str("\\r")?,
+                        '\t' => f.write_str("\\t")?,
+                        c if (c as u32) < 0x20 => write!(f, "\\u{:04x}", c as u32)?,
+                        c => write!(f, "{}", c)?,
+                    }
+                }
+                f.write_str("\"")
+            }
+            Json::Array(items) => {
+                f.write_str("[")?;
+                for (i, item) in items.iter().enumerate() {
+                    if i > 0 { f.write_str(",")?; }
+                    write!(f, "{}", item)?;
+                }
+                f.write_str("]")
+            }
+            Json::Object(fields) => {
+                f.write_str("{")?;
+                for (i, (key, value)) in fields.iter().enumerate() {
+                    if i > 0 { f.write_str(",")?; }
+                    write!(f, "{}:{}", Json::from(*key), value)?;
+                }
+                f.write_str("}")
+            }
+        }
+    }
+}
+
+/// Outcome of a command: printed as text, or as a JSON object with `--json`.
+struct Report {
+    code: i32,
+    text: Vec<String>,
+    fields: Vec<(&'static str, Json)>,
+}
+
+impl Report {
+    fn new(outcome: &str) -> Report {
+        Report { code: 0, text: Vec::new(), fields: vec![("outcome", outcome.into())] }
+    }
+
+    fn field(mut self, key: &'static str, value: Json) -> Report {
+        self.fields.push((key, value));
+        self
+    }
+}
+
+/// Values of secret variables are not shown (except by `get`, which is explicitly asked for).
+fn redact(value: String, secret: bool) -> String {
+    if secret { String::from("********") } else { value }
+}
+
+/// A write direnv didn't approve is done: why is a warning.
+fn approval(result: Result<(), EnvError>) -> Result<Option<DirenvWarning>, EnvError> {
+    match result {
+        Err(EnvError::NotApproved(warning)) => Ok(Some(warning)),
+        result => result.map(|()| None),
+    }
+}
+
+/// Warnings about a target which new shells would not read, and about the approval of an `.envrc` written
+/// (also printed without `--json`).
+fn target_warnings(args: &Args, approval: Option<DirenvWarning>) -> Json {
+    let d = doctor_in(&args.target);
+    let mut warnings = Vec::new();
+    if !d.sourced_by_login {
+        warnings.push(Json::from("the target is not read by login shells"));
+    }
+    if !d.sourced_by_interactive {
+        warnings.push(Json::from("the target is not read by interactive shells"));
+    }
+    if let Some(warning) = approval {
+        if !args.json {
+            eprintln!("globalenv: warning: {}", warning);
+        }
+        warnings.push(Json::from(warning.to_string().as_str()));
+    }
+    Json::Array(warnings)
+}
+
+/// Imports a JSON, TOML or YAML document, told by its extension, `None` for other files.
+#[cfg(feature = "serde")]
+fn import_document(target: &Target, file: &str, options: &ImportOptions) -> Option<Result<Vec<ImportResult>, EnvError>> {
+    let format = file.rsplit_once('.').and_then(|(_, ext)| ext.to_ascii_lowercase().parse().ok())?;
+    matches!(format, Format::Json | Format::Toml | Format::Yaml).then(|| globalenv::import_from_in(target, file, format, options))
+}
+
+#[cfg(not(feature = "serde"))]
+fn import_document(_target: &Target, _file: &str, _options: &ImportOptions) -> Option<Result<Vec<ImportResult>, EnvError>> {
+    None
+}
+
+/// Exports a JSON, TOML or YAML document of the variables the filter keeps, `None` for scripts.
+#[cfg(feature = "serde")]
+fn export_document(target: &Target, format: Format, filter: Filter) -> Option<Result<String, EnvError>> {
+    matches!(format, Format::Json | Format::Toml | Format::Yaml).then(|| globalenv::export_as_in(target, format, filter))
+}
+
+#[cfg(not(feature = "serde"))]
+fn export_document(_target: &Target, _format: Format, _filter: Filter) -> Option<Result<String, EnvError>> {
+    None
+}
+
+fn run(args: &Args) -> Result<Report, EnvError> {
+    let target = &args.target;
+    let location = || target.location().ok().into();
+    Ok(match &args.command {
+        Command::Set(name, value) => {
+            let old = get_var_in(target, name)?;
+            let approval = approval(set_var_with_in(target, name, value, &args.set))?;
+            let outcome = if old.as_deref() == Some(value.as_str()) { "unchanged" } else { "set" };
+            let secret = is_secret_in(target, name)?;
+            Report::new(outcome)
+                .field("target", location())
+                .field("name", name.as_str().into())
+                .field("old", old.map(|old| redact(old, secret)).into())
+                .field("new", Json::Str(redact(value.clone(), secret)))
+                .field("warnings", target_warnings(args, approval))
+        }
+        Command::Unset(name) => {
+            let secret = is_secret_in(target, name)?;
+            let old = get_var_in(target, name)?.map(|old| redact(old, secret));
+            let approval = approval(unset_var_in(target, name))?;
+            Report::new(if old.is_some() { "unset" } else { "absent" })
+                .field("target", location())
+                .field("name", name.as_str().into())
+                .field("old", old.into())
+                .field("new", Json::Null)
+                .field("warnings", target_warnings(args, approval))
+        }
+        Command::Get(name) => {
+            let value = get_var_in(target, name)?;
+            let mut report = Report::new(if value.is_some() { "found" } else { "absent" });
+            match &value {
+                Some(value) => report.text.push(value.clone()),
+                None => report.code = 1,
+            }
+            report
+                .field("target", location())
+                .field("name", name.as_str().into())
+                .field("value", value.into())
+        }
+        Command::List => {
+            let vars: Vec<(String, String)> = match args.filter {
+                Filter::All => vars_in(target)?,
+                Filter::Managed => managed_vars_in(target)?,
+            }
+            .into_iter()
+            .map(|var| (var.name, redact(var.value, var.secret)))
+            .collect();
+            let mut report = Report::new("ok");
+            report.text = vars.iter().map(|(name, value)| format!("{}={}", name, value)).collect();
+            let vars = vars
+                .into_iter()
+                .map(|(name, value)| Json::Object(vec![("name", Json::Str(name)), ("value", Json::Str(value))]))
+                .collect();
+            report.field("target", location()).field("vars", Json::Array(vars))
+        }
+        Command::Import(file) => {
+            let results = if file.to_ascii_lowercase().ends_with(".reg") {
+                import_reg_in(target, file, &args.import)?
+            } else if let Some(results) = import_document(target, file, &args.import) {
+                results?
+            } else {
+                import_dotenv_in(target, file, &args.import)?
+            };
+            let mut report = Report::new("ok");
+            let mut entries = Vec::new();
+            for result in results {
+                let (outcome, error) = match result.outcome {
+                    ImportOutcome::Set => ("set", None),
+                    ImportOutcome::Unchanged => ("unchanged", None),
+                    ImportOutcome::Skipped => ("skipped", None),
+                    ImportOutcome::Failed(e) => {
+                        report.code = 1;
+                        ("failed", Some(e.to_string()))
+                    }
+                };
+                match &error {
+                    Some(e) => report.text.push(format!("{}: {} ({})", result.name, outcome, e)),
+                    None => report.text.push(format!("{}: {}", result.name, outcome)),
+                }
+                entries.push(Json::Object(vec![
+                    ("name", Json::Str(result.name)),
+                    ("outcome", outcome.into()),
+                    ("error", error.into()),
+                ]));
+            }
+            report
+                .field("target", location())
+                .field("file", file.as_str().into())
+                .field("imported", Json::Array(entries))
+        }
+        Command::Export(file) => {
+            let format = args.format.map_or(String::from("dotenv"), |format| format.to_string());
+            let mut report = Report::new("ok").field("target", location()).field("format", Json::Str(format));
+            let content = match args.format {
+                None => render_dotenv_in(target, args.filter)?,
+                Some(format) => export_document(target, format, args.filter).unwrap_or_else(|| render_script_in(target, format))?,
+            };
+            match file {
+                Some(file) => {
+                    fs::write(file, content)?;
+                    report.field("file", file.as_str().into())
+                }
+                None => {
+                    report.text.push(content.trim_end().to_string());
+                    report.field("content", Json::Str(content))
+                }
+            }
+        }
+        Command::Gc => {
+            let expired = gc_in(target)?;
+            let mut report = Report::new("ok");
+            report.text = expired.iter().map(|name| format!("{}: unset", name)).collect();
+            let expired = expired.into_iter().map(Json::Str).collect();
+            report.field("target", location()).field("expired", Json::Array(expired))
+        }
+        Command::Doctor => {
+            let d = doctor_in(target);
+            let mut report = Report::new("ok");
+            report.text = diagnostics_text(&d);
+            report.fields.extend(diagnostics_json(d));
+            report
+        }
+        Command::Completions(shell) => {
+            let script = completion_script(shell).unwrap_or_default();
+            let mut report = Report::new("ok")
+                .field("shell", shell.as_str().into())
+                .field("script", script.into());
+            report.text.push(script.trim_end().to_string());
+            report
+        }
+        Command::Help => {
+            let mut report = Report::new("ok");
+            report.text.push(String::from(USAGE));
+            report
+        }
+        Command::Version => {
+            let mut report = Report::new("ok").field("version", env!("CARGO_PKG_VERSION").into());
+            report.text.push(format!("globalenv {}", env!("CARGO_PKG_VERSION")));
+            report
+        }
+    })
+}
+
+// Variable names are completed for get/unset/set from the output of `globalenv list`.
+const BASH_COMPLETION: &str = r##"_globalenv() {
+    local cur="${COMP_WORDS[COMP_CWORD]}"
+    local prev="${COMP_WORDS[COMP_CWORD-1]}"
+    case "$prev" in
+        --scope) COMPREPLY=($(compgen -W "user system service project" -- "$cur")); return ;;
+        --shell) COMPREPLY=($(compgen -W "sh ksh bash zsh tcsh fish powershell ion" -- "$cur")); return ;;
+        --file|--policy|import|export) COMPREPLY=($(compgen -f -- "$cur")); return ;;
+        --format) COMPREPLY=($(compgen -W "dotenv sh ksh bash zsh tcsh fish powershell ion batch powershell-apply" -- "$cur")); return ;;
+        get|unset|set) COMPREPLY=($(compgen -W "$(globalenv list 2>/dev/null | cut -d= -f1)" -- "$cur")); return ;;
+        completions) COMPREPLY=($(compgen -W "bash zsh fish powershell" -- "$cur")); return ;;
+    esac
+    if [[ "$cur" == -* ]]; then
+        COMPREPLY=($(compgen -W "--scope --shell --file --policy --allow-protected --format --secret --ttl --keep-existing --managed --tmux --direnv --json --help --version" -- "$cur"))
+    else
+        COMPREPLY=($(compgen -W "set unset get list import export gc doctor completions" -- "$cur"))
+    fi
+}
+complete -F _globalenv globalenv
+"##;
+
+const ZSH_COMPLETION: &str = r##"#compdef globalenv
+
+_globalenv_vars() {
+    local -a vars
+    vars=(${(f)"$(globalenv list 2>/dev/null | cut -d= -f1)"})
+    _describe 'variable' vars
+}
+
+_globalenv() {
+    local state
+    _arguments \
+        '--scope[Scope]:scope:(user system service project)' \
+        '--shell[Shell]:shell:(sh ksh bash zsh tcsh fish powershell ion)' \
+        '--file[Persists in this file instead]:file:_files' \
+        '--policy[Policy file]:file:_files' \
+        '--allow-protected[Lets protected variables be modified]' \
+        '--format[Export format]:format:(dotenv sh ksh bash zsh tcsh fish powershell ion batch powershell-apply)' \
+        '--secret[Flags the variable as secret]' \
+        '--ttl[Time-to-live in seconds]:seconds:' \
+        '--keep-existing[Keeps variables already persisted on import]' \
+        '--tmux[Also updates the running tmux server]' \
+        '--direnv[Runs direnv allow on the .envrc written]' \
+        '--managed[Only the variables set through globalenv]' \
+        '--json[Prints the outcome as JSON]' \
+        '(-h --help)'{-h,--help}'[Prints help]' \
+        '(-V --version)'{-V,--version}'[Prints the version]' \
+        '1:command:(set unset get list import export gc doctor completions)' \
+        '*::argument:->args'
+    case $state in
+        args)
+            case $words[1] in
+                get|unset|set) (( CURRENT == 2 )) && _globalenv_vars ;;
+                import|export) _files ;;
+                completions) _values 'shell' bash zsh fish powershell ;;
+            esac
+            ;;
+    esac
+}
+
+_globalenv "$@"
+"##;
+
+const FISH_COMPLETION: &str = r##"set -l commands set unset get list import export gc doctor completions
+complete -c globalenv -f
+complete -c globalenv -l scope -x -a 'user system service project' -d 'Scope'
+complete -c globalenv -l shell -x -a 'sh ksh bash zsh tcsh fish powershell ion' -d 'Shell'
+complete -c globalenv -l file -r -F -d 'Persists in this file instead'
+complete -c globalenv -l policy -r -F -d 'Policy file'
+complete -c globalenv -l allow-protected -d 'Lets protected variables be modified'
+complete -c globalenv -l format -x -a 'dotenv sh ksh bash zsh tcsh fish powershell ion batch powershell-apply' -d 'Export format'
+complete -c globalenv -l secret -d 'Flags the variable as secret'
+complete -c globalenv -l ttl -x -d 'Time-to-live in seconds'
+complete -c globalenv -l keep-existing -d 'Keeps variables already persisted on import'
+complete -c globalenv -l tmux -d 'Also updates the running tmux server'
+complete -c globalenv -l direnv -d 'Runs direnv allow on the .envrc written'
+complete -c globalenv -l managed -d 'Only the variables set through globalenv'
+complete -c globalenv -l json -d 'Prints the outcome as JSON'
+complete -c globalenv -s h -l help -d 'Prints help'
+complete -c globalenv -s V -l version -d 'Prints the version'
+complete -c globalenv -n "not __fish_seen_subcommand_from $commands" -a set -d 'Sets a variable globally'
+complete -c globalenv -n "not __fish_seen_subcommand_from $commands" -a unset -d 'Unsets a variable globally'
+complete -c globalenv -n "not __fish_seen_subcommand_from $commands" -a get -d 'Prints the persisted value of a variable'
+complete -c globalenv -n "not __fish_seen_subcommand_from $commands" -a list -d 'Lists the persisted variables'
+complete -c globalenv -n "not __fish_seen_subcommand_from $commands" -a import -d 'Sets globally every variable of a .env file'
+complete -c globalenv -n "not __fish_seen_subcommand_from $commands" -a export -d 'Writes the persisted variables to a file'
+complete -c globalenv -n "__fish_seen_subcommand_from import export" -F
+complete -c globalenv -n "not __fish_seen_subcommand_from $commands" -a gc -d 'Unsets the expired variables'
+complete -c globalenv -n "not __fish_seen_subcommand_from $commands" -a doctor -d 'Diagnoses shell detection and the target file'
+complete -c globalenv -n "not __fish_seen_subcommand_from $commands" -a completions -d 'Prints a completion script'
+complete -c globalenv -n "__fish_seen_subcommand_from get unset set" -a "(globalenv list 2>/dev/null | string split -f1 =)"
+complete -c globalenv -n "__fish_seen_subcommand_from completions" -a 'bash zsh fish powershell'
+"##;
+
+const POWERSHELL_COMPLETION: &str = r##"Register-ArgumentCompleter -Native -CommandName globalenv -ScriptBlock {
+    param($wordToComplete, $commandAst, $cursorPosition)
+    $words = @($commandAst.CommandElements | ForEach-Object { $_.ToString() })
+    $previous = if ($wordToComplete) { $words[-2] } else { $words[-1] }
+    $candidates = switch ($previous) {
+        '--scope' { 'user', 'system', 'service', 'project' }
+        '--shell' { 'sh', 'ksh', 'bash', 'zsh', 'tcsh', 'fish', 'powershell', 'ion' }
+        { $_ -in '--file', 'import', 'export' } { }
+        '--format' { 'dotenv', 'sh', 'ksh', 'bash', 'zsh', 'tcsh', 'fish', 'powershell', 'ion', 'batch', 'powershell-apply' }
+        { $_ -in 'get', 'unset', 'set' } { globalenv list 2>$null | ForEach-Object { ($_ -split '=', 2)[0] } }
+        'completions' { 'bash', 'zsh', 'fish', 'powershell' }
+        default {
+            if ($wordToComplete -like '-*') { '--scope', '--shell', '--file', '--policy', '--allow-protected', '--format', '--secret', '--ttl', '--keep-existing', '--managed', '--tmux', '--direnv', '--json', '--help', '--version' }
+            else { 'set', 'unset', 'get', 'list', 'import', 'export', 'gc', 'doctor', 'completions' }
+        }
+    }
+    $candidates | Where-Object { $_ -like "$wordToComplete*" } | ForEach-Object {
+        [System.Management.Automation.CompletionResult]::new($_, $_, 'ParameterValue', $_)
+    }
+}
+"##;
+
+fn completion_script(shell: &str) -> Option<&'static str> {
+    match shell {
+        "bash" => Some(BASH_COMPLETION),
+        "zsh" => Some(ZSH_COMPLETION),
+        "fish" => Some(FISH_COMPLETION),
+        "powershell" => Some(POWERSHELL_COMPLETION),
+        _ => None,
+    }
+}
+
+fn diagnostics_text(d: &Diagnostics) -> Vec<String> {
+    let yes_no = |b: bool| if b { "yes" } else { "no" };
+    let mut text = vec![format!("SHELL: {}", d.shell_var.as_deref().unwrap_or("(not set)"))];
+    match d.shell {
+        Some(shell) => text.push(format!("Shell: {}", shell)),
+        None => text.push(String::from("Shell: unsupported")),
+    }
+    for f in &d.startup_files {
+        text.push(format!(
+            "  {} (exists: {}, login: {}, interactive: {})",
+            f.path.display(), yes_no(f.exists), yes_no(f.login), yes_no(f.interactive)
+        ));
+    }
+    text.push(format!("Target: {}", d.target.as_deref().unwrap_or("(cannot be resolved)")));
+    text.push(format!("Read by login shells: {}", yes_no(d.sourced_by_login)));
+    text.push(format!("Read by interactive shells: {}", yes_no(d.sourced_by_interactive)));
+    if let Some(accessible) = d.registry_accessible {
+        text.push(format!("Registry accessible: {}", yes_no(accessible)));
+    }
+    text.push(format!("Elevated: {}", yes_no(d.elevated)));
+    text
+}
+
+fn diagnostics_json(d: Diagnostics) -> Vec<(&'static str, Json)> {
+    let files = d
+        .startup_files
+        .into_iter()
+        .map(|f| {
+            Json::Object(vec![
+                ("path", Json::Str(f.path.display().to_string())),
+                ("exists", Json::Bool(f.exists)),
+                ("login", Json::Bool(f.login)),
+                ("interactive", Json::Bool(f.interactive)),
+            ])
+        })
+        .collect();
+    vec![
+        ("shell_var", d.shell_var.into()),
+        ("shell", d.shell.map(|s| s.to_string()).into()),
+        ("startup_files", Json::Array(files)),
+        ("target", d.target.into()),
+        ("sourced_by_login", Json::Bool(d.sourced_by_login)),
+        ("sourced_by_interactive", Json::Bool(d.sourced_by_interactive)),
+        ("registry_accessible", d.registry_accessible.map_or(Json::Null, Json::Bool)),
+        ("elevated", Json::Bool(d.elevated)),
+    ]
+}
+
+fn main() {
+    let args = match parse_args(env::args().skip(1)) {
+        Ok(args) => args,
+        Err(e) => {
+            if env::args().any(|a| a == "--json") {
+                println!("{}", Json::Object(vec![("outcome", "error".into()), ("error", e.as_str().into())]));
+            } else {
+                eprintln!("globalenv: {}\n\n{}", e, USAGE);
+            }
+            process::exit(2);
+        }
+    };
+    let command = ("command", args.command.name().into());
+    match run(&args) {
+        Ok(report) => {
+            if args.json {
+                let mut fields = vec![command];
+                fields.extend(report.fields);
+                println!("{}", Json::Object(fields));
+            } else {
+                for line in report.text {
+                    println!("{}", line);
+                }
+            }
+            process::exit(report.code);
+        }
+        Err(e) => {
+            if args.json {
+                println!("{}", Json::Object(vec![command, ("outcome", "error".into()), ("error", e.to_string().as_str().into())]));
+            } else {
+                eprintln!("globalenv: {}", e);
+            }
+            process::exit(1);
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use globalenv::Shell;
+
+    fn parse(args: &[&str]) -> Result<Args, String> {
+        parse_args(args.iter().map(|s| s.to_string()))
+    }
+
+    #[test]
+    fn parses_commands_and_flags() {
+        let args = parse(&["--shell", "zsh", "set", "FOO", "bar"]).unwrap();
+        assert_eq!(args.target, Target::new().shell(Shell::Zsh));
+        assert_eq!(args.command, Command::Set(String::from("FOO"), String::from("bar")));
+        let args = parse(&["get", "FOO", "--file", "/tmp/env", "--json"]).unwrap();
+        assert_eq!(args.target, Target::new().file("/tmp/env"));
+        assert_eq!(args.command, Command::Get(String::from("FOO")));
+        assert!(args.json);
+        assert_eq!(parse(&["--direnv", "unset", "FOO"]).unwrap().target, Target::new().direnv(true));
+        assert!(parse(&["set", "FOO"]).is_err());
+        assert!(parse(&["--scope", "galaxy", "list"]).is_err());
+        assert!(parse(&["completions", "tcsh"]).is_err());
+        assert_eq!(parse(&["--format", "fish", "export"]).unwrap().format, Some(Shell::Fish.into()));
+        assert_eq!(parse(&["--format", "cmd", "export"]).unwrap().format, Some(Format::Batch));
+        assert_eq!(parse(&["--ttl", "60", "set", "A", "b"]).unwrap().set, SetOptions::new().ttl(Duration::from_secs(60)));
+        assert!(parse(&["--ttl", "1h", "set", "A", "b"]).is_err());
+        assert_eq!(parse(&["set", "--", "JAVA_OPTS", "-Xmx2g"]).unwrap().command, Command::Set(String::from("JAVA_OPTS"), String::from("-Xmx2g")));
+    }
+
+    #[test]
+    fn escapes_json_strings() {
+        let json = Json::Object(vec![("value", "a \"b\"\n\\".into()), ("old", Json::Null)]);
+        assert_eq!(json.to_string(), r#"{"value":"a \"b\"\n\\","old":null}"#);
+    }
+}
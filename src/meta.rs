@@ -0,0 +1,90 @@
+//! Metadata of the managed variables: a `# globalenv: ...` comment preceding their line on Unix,
+//! their value in the sidecar key on Windows.
+
+use crate::Condition;
+use std::{fmt, time::{SystemTime, UNIX_EPOCH}};
+
+/// Seconds since the Unix epoch.
+pub(crate) fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |d| d.as_secs())
+}
+
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub(crate) struct Meta {
+    /// The value is redacted from reports
+    pub(crate) secret: bool,
+    /// The value is stored encrypted in a sidecar file, the definition decrypts it
+    pub(crate) encrypted: bool,
+    /// Time (seconds since the Unix epoch) after which the variable is removed by `gc`
+    pub(crate) expires: Option<u64>,
+    /// Time the variable was first set through this crate
+    pub(crate) created: Option<u64>,
+    /// Time the value or metadata were last changed
+    pub(crate) updated: Option<u64>,
+    /// Where the definition applies, its line being guarded by the condition
+    pub(crate) when: Option<Condition>,
+}
+
+impl Meta {
+    /// Parses space-separated flags, ignoring unknown ones (written by a later version).
+    pub(crate) fn parse(s: &str) -> Meta {
+        let mut meta = Meta::default();
+        for flag in s.split_whitespace() {
+            match flag {
+                "secret" => meta.secret = true,
+                "encrypted" => meta.encrypted = true,
+                _ => {
+                    let Some((key, time)) = flag.split_once('=') else { continue };
+                    if key == "when" {
+                        meta.when = Condition::parse(time);
+                        continue;
+                    }
+                    let time = time.parse().ok();
+                    match key {
+                        "expires" => meta.expires = time,
+                        "created" => meta.created = time,
+                        "updated" => meta.updated = time,
+                        _ => (),
+                    }
+                }
+            }
+        }
+        meta
+    }
+
+    pub(crate) fn is_empty(&self) -> bool {
+        *self == Meta::default()
+    }
+
+    /// Records a change made now.
+    pub(crate) fn touch(&mut self) {
+        let now = now();
+        self.created.get_or_insert(now);
+        self.updated = Some(now);
+    }
+
+    pub(crate) fn is_expired(&self) -> bool {
+        self.expires.is_some_and(|expires| expires <= now())
+    }
+}
+
+impl fmt::Display for Meta {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let mut flags = Vec::new();
+        if self.secret {
+            flags.push(String::from("secret"));
+        }
+        if self.encrypted {
+            flags.push(String::from("encrypted"));
+        }
+        for (key, time) in [("expires", self.expires), ("created", self.created), ("updated", self.updated)] {
+            if let Some(time) = time {
+                flags.push(format!("{}={}", key, time));
+            }
+        }
+        if let Some(condition) = &self.when {
+            flags.push(format!("when={}", condition));
+        }
+        f.write_str(&flags.join(" "))
+    }
+}
@@ -0,0 +1,69 @@
+//! Migration of the managed variables from a shell's startup file to another's.
+
+use crate::{envfile, filtered_vars_in, meta_of, target::Store, EnvError, Filter, Shell, Target};
+use std::fs;
+
+/// Moves the variables set through this crate from the startup file of a shell to the one of another, e.g. when
+/// the user switches from bash to fish: they are rewritten in the syntax of the new shell, with their metadata,
+/// and removed from the old file. Returns their names.
+/// Encrypted variables (see `set_var_encrypted`) are left in place, to be set again.
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{migrate, Shell};
+/// migrate(Shell::Bash, Shell::Fish).unwrap();
+/// ```
+pub fn migrate(from: Shell, to: Shell) -> Result<Vec<String>, EnvError> {
+    migrate_in(&Target::new().shell(from), &Target::new().shell(to))
+}
+
+/// Moves the variables set through this crate from a target to another.
+/// Example:
+/// ```rust
+/// use globalenv::{migrate_in, set_var_in, Shell, Target};
+/// let (bashrc, fish) = (std::env::temp_dir().join("globalenv_migrate.bashrc"), std::env::temp_dir().join("globalenv_migrate.fish"));
+/// std::fs::write(&bashrc, "").unwrap();
+/// let _ = std::fs::remove_file(&fish);
+/// set_var_in(&Target::new().file(&bashrc), "EDITOR", "hx").unwrap();
+/// migrate_in(&Target::new().file(&bashrc), &Target::new().file(&fish)).unwrap();
+/// assert!(std::fs::read_to_string(&fish).unwrap().contains("set -gx EDITOR hx\n"));
+/// assert_eq!(globalenv::get_var_in(&Target::new().file(&bashrc), "EDITOR").unwrap(), None);
+/// ```
+pub fn migrate_in(from: &Target, to: &Target) -> Result<Vec<String>, EnvError> {
+    let (from_store, to_store) = (from.store()?, to.store()?);
+    match &to_store {
+        // The new shell's file may not exist yet, e.g. ~/.config/fish/config.fish
+        Store::File(path, _) if !path.exists() => {
+            if let Some(parent) = path.parent() {
+                to.create_dir(parent)?;
+            }
+            fs::write(path, "")?;
+            to.own(path)?;
+        }
+        _ => (),
+    }
+    let mut migrated = Vec::new();
+    for (var, value) in filtered_vars_in(from, Filter::Managed)? {
+        let meta = meta_of(&from_store, &var)?;
+        if meta.encrypted {
+            continue;
+        }
+        from.check(&var)?;
+        to.check(&var)?;
+        match &to_store {
+            Store::File(path, syntax) => envfile::set(path, *syntax, &var, &value, &meta)?,
+            #[cfg(target_os = "windows")]
+            Store::Registry(scope) => crate::registry::set(scope, &var, &value, &meta)?,
+        }
+        match &from_store {
+            Store::File(path, syntax) => {
+                envfile::unset(path, *syntax, &var, false)?;
+            }
+            #[cfg(target_os = "windows")]
+            Store::Registry(scope) => {
+                crate::registry::unset(scope, &var)?;
+            }
+        }
+        migrated.push(var);
+    }
+    Ok(migrated)
+}
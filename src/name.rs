@@ -0,0 +1,128 @@
+//! Variable names checked once, at compile time with [`env_name!`](crate::env_name).
+
+use crate::EnvError;
+use std::{borrow::Cow, fmt, ops::Deref};
+
+/// Why the name is not a POSIX one (letters, digits and underscores, not starting with a digit), if it is not.
+pub(crate) const fn invalid_posix(name: &str) -> Option<&'static str> {
+    let bytes = name.as_bytes();
+    if bytes.is_empty() {
+        return Some("empty");
+    }
+    if bytes[0].is_ascii_digit() {
+        return Some("starts with a digit");
+    }
+    let mut i = 0;
+    while i < bytes.len() {
+        if bytes[i] == b'=' {
+            return Some("contains '='");
+        }
+        if !(bytes[i].is_ascii_alphanumeric() || bytes[i] == b'_') {
+            return Some("not made of letters, digits and underscores");
+        }
+        i += 1;
+    }
+    None
+}
+
+/// Name of a variable, valid in every shell: the POSIX charset (letters, digits and underscores, not starting
+/// with a digit). It dereferences to `&str`, so it is accepted wherever the API takes a name.
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{env_name, VarName};
+/// const EDITOR: VarName = env_name!("EDITOR");
+/// globalenv::set_var(&EDITOR, "vim").unwrap();
+/// ```
+#[derive(Debug, PartialEq, Eq, Hash, Clone, PartialOrd, Ord)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(try_from = "String", into = "String"))]
+pub struct VarName(Cow<'static, str>);
+
+impl VarName {
+    /// Checks a name known at runtime.
+    pub fn new(name: &str) -> Result<VarName, EnvError> {
+        match invalid_posix(name) {
+            Some(reason) => Err(EnvError::InvalidName(String::from(reason))),
+            None => Ok(VarName(Cow::Owned(name.to_string()))),
+        }
+    }
+
+    /// Checks a name in a constant, which [`env_name!`](crate::env_name) does.
+    ///
+    /// # Panics
+    /// If the name is invalid: at compile time when evaluated in a constant.
+    pub const fn from_static(name: &'static str) -> VarName {
+        if invalid_posix(name).is_some() {
+            panic!("invalid variable name: POSIX names are made of letters, digits and underscores, not starting with a digit");
+        }
+        VarName(Cow::Borrowed(name))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl Deref for VarName {
+    type Target = str;
+
+    fn deref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl AsRef<str> for VarName {
+    fn as_ref(&self) -> &str {
+        &self.0
+    }
+}
+
+impl fmt::Display for VarName {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl TryFrom<String> for VarName {
+    type Error = EnvError;
+
+    fn try_from(name: String) -> Result<VarName, EnvError> {
+        VarName::new(&name)
+    }
+}
+
+impl From<VarName> for String {
+    fn from(name: VarName) -> String {
+        name.0.into_owned()
+    }
+}
+
+/// A [`VarName`] checked at compile time: a typo making the name invalid fails the build.
+/// Example:
+/// ```rust
+/// let name = globalenv::env_name!("JAVA_HOME");
+/// assert_eq!(&*name, "JAVA_HOME");
+/// ```
+/// ```rust,compile_fail
+/// let name = globalenv::env_name!("JAVA-HOME");
+/// ```
+#[macro_export]
+macro_rules! env_name {
+    ($name:literal) => {{
+        const NAME: $crate::VarName = $crate::VarName::from_static($name);
+        NAME
+    }};
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_posix_names() {
+        assert_eq!(VarName::new("_PATH2").unwrap().as_str(), "_PATH2");
+        assert_eq!(VarName::new("2PATH"), Err(EnvError::InvalidName(String::from("starts with a digit"))));
+        assert_eq!(VarName::new("A=B"), Err(EnvError::InvalidName(String::from("contains '='"))));
+        assert!(VarName::new("").is_err() && VarName::new("ProgramFiles(x86)").is_err());
+        assert_eq!(crate::env_name!("HOME"), VarName::new("HOME").unwrap());
+    }
+}
@@ -0,0 +1,54 @@
+//! Accounts of the passwd database (`/etc/passwd`, or whatever NSS is configured with, e.g. LDAP).
+
+use std::{
+    ffi::{c_char, CStr, CString},
+    mem,
+    path::PathBuf,
+    ptr,
+};
+
+/// Entry of the passwd database.
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub(crate) struct Account {
+    pub(crate) uid: u32,
+    pub(crate) gid: u32,
+    pub(crate) home: PathBuf,
+    pub(crate) shell: String,
+}
+
+/// Calls `getpwuid_r` (or `getpwnam_r`) with a buffer large enough for the strings of the entry.
+fn lookup(call: impl Fn(*mut libc::passwd, *mut c_char, usize, *mut *mut libc::passwd) -> libc::c_int) -> Option<Account> {
+    let mut buf = vec![0 as c_char; 1024];
+    loop {
+        let mut pwd: libc::passwd = unsafe { mem::zeroed() };
+        let mut result = ptr::null_mut();
+        match call(&mut pwd, buf.as_mut_ptr(), buf.len(), &mut result) {
+            libc::ERANGE if buf.len() < 1 << 20 => buf.resize(buf.len() * 2, 0),
+            0 if !result.is_null() => {
+                let string = |s: *const c_char| if s.is_null() { String::new() } else { unsafe { CStr::from_ptr(s) }.to_string_lossy().into_owned() };
+                return Some(Account {
+                    uid: pwd.pw_uid,
+                    gid: pwd.pw_gid,
+                    home: PathBuf::from(string(pwd.pw_dir)),
+                    shell: string(pwd.pw_shell),
+                });
+            }
+            _ => return None,
+        }
+    }
+}
+
+pub(crate) fn by_uid(uid: libc::uid_t) -> Option<Account> {
+    lookup(|pwd, buf, len, result| unsafe { libc::getpwuid_r(uid, pwd, buf, len, result) })
+}
+
+pub(crate) fn by_name(name: &str) -> Option<Account> {
+    let name = CString::new(name).ok()?;
+    lookup(|pwd, buf, len, result| unsafe { libc::getpwnam_r(name.as_ptr(), pwd, buf, len, result) })
+}
+
+/// The account of the user running the process.
+pub(crate) fn current() -> Option<Account> {
+    by_uid(unsafe { libc::getuid() })
+}
+
@@ -0,0 +1,85 @@
+//! `PATHEXT`, the extensions of the files Windows runs without their extension being typed.
+
+use crate::{resolve_var_in, set_var_in, stored_var_in, EnvError, Target};
+
+/// Value of a pristine Windows, when the variable is set nowhere.
+const DEFAULT_PATHEXT: &str = ".COM;.EXE;.BAT;.CMD;.VBS;.VBE;.JS;.JSE;.WSF;.WSH;.MSC";
+
+/// The extension, uppercase and with its dot.
+fn normalized(ext: &str) -> Result<String, EnvError> {
+    let ext = ext.trim().trim_start_matches('.').to_uppercase();
+    if ext.is_empty() || ext.contains(|c: char| c == ';' || c == '.' || c.is_whitespace()) {
+        return Err(EnvError::VarError);
+    }
+    Ok(format!(".{}", ext))
+}
+
+/// The list with the extension added at its end (or removed), without duplicates nor empty entries. Entries
+/// keep their order, and are uppercased.
+fn with_extension(list: &str, ext: &str, add: bool) -> String {
+    let mut exts: Vec<String> = Vec::new();
+    for entry in list.split(';').map(|e| e.trim().to_uppercase()).filter(|e| !e.is_empty()) {
+        if !exts.contains(&entry) && (add || entry != ext) {
+            exts.push(entry);
+        }
+    }
+    if add && !exts.iter().any(|e| e == ext) {
+        exts.push(ext.to_string());
+    }
+    exts.join(";")
+}
+
+fn edit(target: &Target, ext: &str, add: bool) -> Result<(), EnvError> {
+    let ext = normalized(ext)?;
+    // A user value replaces the machine one, which it thus starts from
+    let current = match stored_var_in(target, "PATHEXT")? {
+        Some(current) => current,
+        None => resolve_var_in(target, "PATHEXT")?.unwrap_or_else(|| DEFAULT_PATHEXT.to_string()),
+    };
+    let updated = with_extension(&current, &ext, add);
+    if updated != current {
+        set_var_in(target, "PATHEXT", &updated)?;
+    }
+    Ok(())
+}
+
+/// Adds an extension to `PATHEXT`, e.g. `.PY` for an installer of an interpreter, after the existing ones.
+/// The list is also cleaned up: extensions are uppercased, and duplicates removed.
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{add_pathext_in, Scope, Target};
+/// add_pathext_in(&Target::new().scope(Scope::System), ".PY").unwrap();
+/// ```
+pub fn add_pathext(ext: &str) -> Result<(), EnvError> {
+    add_pathext_in(&Target::default(), ext)
+}
+
+/// Adds an extension to `PATHEXT` in the given target. A user `PATHEXT` replaces the machine one instead of
+/// extending it: when there is none, it is created from the machine one.
+pub fn add_pathext_in(target: &Target, ext: &str) -> Result<(), EnvError> {
+    edit(target, ext, true)
+}
+
+/// Removes an extension from `PATHEXT`.
+pub fn remove_pathext(ext: &str) -> Result<(), EnvError> {
+    remove_pathext_in(&Target::default(), ext)
+}
+
+/// Removes an extension from `PATHEXT` in the given target.
+pub fn remove_pathext_in(target: &Target, ext: &str) -> Result<(), EnvError> {
+    edit(target, ext, false)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn edits_extension_list() {
+        assert_eq!(normalized("py"), Ok(String::from(".PY")));
+        assert_eq!(normalized(".a;b"), Err(EnvError::VarError));
+        assert_eq!(with_extension(".COM;.exe;;.EXE", ".PY", true), ".COM;.EXE;.PY");
+        assert_eq!(with_extension(".PY;.COM", ".PY", true), ".PY;.COM");
+        assert_eq!(with_extension(".COM;.py;.EXE", ".PY", false), ".COM;.EXE");
+    }
+}
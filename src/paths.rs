@@ -0,0 +1,61 @@
+//! Directories of the crate's own files, following the XDG base directory specification (Known Folders on Windows).
+
+use crate::{target::home_dir, EnvError};
+use std::{env, ffi::OsString, path::{Path, PathBuf}};
+
+/// Where the crate keeps its own files, see [`paths`].
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Paths {
+    /// `$XDG_CONFIG_HOME/globalenv` (`~/.config/globalenv`), `%APPDATA%\globalenv` on Windows: the age identity
+    /// and the encrypted values
+    pub config_dir: PathBuf,
+    /// `$XDG_DATA_HOME/globalenv` (`~/.local/share/globalenv`), `%LOCALAPPDATA%\globalenv` on Windows
+    pub data_dir: PathBuf,
+    /// `env.sh` in the configuration directory: the managed variables in hook mode (see
+    /// [`install_hook`](crate::install_hook))
+    pub env_file: PathBuf,
+}
+
+/// The base directory from this variable, unless it is unset or relative (which the specification says to ignore).
+fn base_dir(value: Option<OsString>) -> Option<PathBuf> {
+    value.map(PathBuf::from).filter(|dir| dir.is_absolute())
+}
+
+fn paths_from(home: &Path, config: Option<OsString>, data: Option<OsString>) -> Paths {
+    let (config_fallback, data_fallback) = if cfg!(target_os = "windows") {
+        (home.join(r"AppData\Roaming"), home.join(r"AppData\Local"))
+    } else {
+        (home.join(".config"), home.join(".local/share"))
+    };
+    let config_dir = base_dir(config).unwrap_or(config_fallback).join("globalenv");
+    let data_dir = base_dir(data).unwrap_or(data_fallback).join("globalenv");
+    Paths { env_file: config_dir.join("env.sh"), config_dir, data_dir }
+}
+
+/// Directories of the crate's own files. They are read from `XDG_CONFIG_HOME` and `XDG_DATA_HOME` on Unix,
+/// and from `APPDATA` and `LOCALAPPDATA` (the roaming and local application data Known Folders) on Windows,
+/// with the usual fallbacks in the home directory.
+/// Example:
+/// ```rust,no_run
+/// println!("{}", globalenv::paths().unwrap().env_file.display());
+/// ```
+pub fn paths() -> Result<Paths, EnvError> {
+    let (config, data) = if cfg!(target_os = "windows") { ("APPDATA", "LOCALAPPDATA") } else { ("XDG_CONFIG_HOME", "XDG_DATA_HOME") };
+    Ok(paths_from(&home_dir()?, env::var_os(config), env::var_os(data)))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn falls_back_to_home() {
+        let home = env::temp_dir().join("home");
+        let base = env::temp_dir().join("config");
+        let paths = paths_from(&home, Some(base.clone().into()), Some("relative".into()));
+        assert_eq!(paths.env_file, base.join("globalenv").join("env.sh"));
+        assert!(paths.data_dir.starts_with(&home));
+        assert!(paths.data_dir.ends_with("globalenv"));
+    }
+}
@@ -0,0 +1,89 @@
+//! Plans: the changes a [`GlobalEnv`](struct@crate::GlobalEnv) session would make when flushed, for automation to
+//! review (and approve) them first.
+
+use crate::{EnvError, GlobalEnv};
+
+/// Version of the JSON schema of [`Plan`], increased when a field changes meaning or is removed.
+pub const PLAN_VERSION: u32 = 1;
+
+/// What a change does to its variable.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize), serde(rename_all = "lowercase"))]
+pub enum Action {
+    /// Creates or modifies the variable
+    Set,
+    /// Removes the variable
+    Unset,
+}
+
+/// A change of one variable.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Change {
+    pub action: Action,
+    /// File or registry key the change is written to (see [`Target::location`](crate::Target::location))
+    pub target: String,
+    pub name: String,
+    /// Persisted value, `None` if the variable isn't set
+    pub before: Option<String>,
+    /// Value once flushed, `None` if the variable is unset
+    pub after: Option<String>,
+    /// The variable is secret (see [`SetOptions::secret`](crate::SetOptions::secret)): its values are redacted
+    pub secret: bool,
+}
+
+/// The changes pending in a session, see [`GlobalEnv::plan`](crate::GlobalEnv::plan).
+///
+/// With the `serde` feature, it serializes to a stable JSON schema, whose `version` is [`PLAN_VERSION`]:
+/// ```text
+/// {"version": 1, "changes": [
+///   {"action": "set", "target": "/home/me/.bashrc", "name": "EDITOR", "before": "vi", "after": "vim", "secret": false},
+///   {"action": "unset", "target": "/home/me/.bashrc", "name": "VISUAL", "before": "vi", "after": null, "secret": false}
+/// ]}
+/// ```
+#[derive(Debug, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Plan {
+    pub version: u32,
+    pub changes: Vec<Change>,
+    /// The session as it was planned, which [`Plan::apply`] flushes; `None` once deserialized
+    #[cfg_attr(feature = "serde", serde(skip))]
+    session: Option<Box<GlobalEnv>>,
+}
+
+/// Plans are equal if they make the same changes.
+impl PartialEq for Plan {
+    fn eq(&self, other: &Plan) -> bool {
+        self.version == other.version && self.changes == other.changes
+    }
+}
+
+impl Eq for Plan {}
+
+/// Replacement of the redacted values.
+pub(crate) const REDACTED: &str = "********";
+
+impl Plan {
+    pub(crate) fn new(changes: Vec<Change>, session: GlobalEnv) -> Plan {
+        Plan { version: PLAN_VERSION, changes, session: Some(Box::new(session)) }
+    }
+
+    /// Writes the changes, as flushing the session they were planned in would. Fails with
+    /// [`EnvError::Conflict`], writing nothing, if the file or registry key changed since the session read it
+    /// (before the plan was made), e.g. since the plan was approved; applying it twice is such a conflict. A
+    /// deserialized plan only describes changes: it fails with [`EnvError::VarError`].
+    pub fn apply(&self) -> Result<(), EnvError> {
+        self.session.clone().ok_or(EnvError::VarError)?.flush()
+    }
+
+    /// Whether flushing would change nothing.
+    pub fn is_empty(&self) -> bool {
+        self.changes.is_empty()
+    }
+
+    /// The plan as JSON, in the schema above.
+    #[cfg(feature = "serde")]
+    pub fn to_json(&self) -> String {
+        serde_json::to_string_pretty(self).unwrap_or_default()
+    }
+}
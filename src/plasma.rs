@@ -0,0 +1,59 @@
+//! KDE Plasma session scripts, sourced by `startplasma` before the desktop starts.
+
+use crate::{script, EnvError, Shell, Target};
+use std::{env, fs, path::PathBuf};
+
+const SCRIPT: &str = "globalenv.sh";
+
+/// `$XDG_CONFIG_HOME/plasma-workspace/env`, whose scripts Plasma sources.
+fn env_dir(target: &Target) -> Result<PathBuf, EnvError> {
+    let config_home = env::var_os("XDG_CONFIG_HOME").map(PathBuf::from).filter(|dir| dir.is_absolute() && !target.has_home());
+    Ok(config_home.unwrap_or(target.get_home()?.join(".config")).join("plasma-workspace/env"))
+}
+
+/// Writes a script exporting the variables into `~/.config/plasma-workspace/env`, the supported way to set the
+/// environment of a Plasma session (on X11 as on Wayland): it applies from the next session. The names are
+/// checked as [`set_var`](crate::set_var) does, nothing being written if one is rejected.
+/// Example:
+/// ```rust,no_run
+/// let vars: Vec<(String, String)> = globalenv::managed_vars().unwrap().into_iter().map(Into::into).collect();
+/// globalenv::write_plasma_env(&vars).unwrap();
+/// ```
+pub fn write_plasma_env(vars: &[(String, String)]) -> Result<(), EnvError> {
+    write_plasma_env_in(&Target::default(), vars)
+}
+
+/// Writes the script of the target's user.
+pub fn write_plasma_env_in(target: &Target, vars: &[(String, String)]) -> Result<(), EnvError> {
+    for (var, _) in vars {
+        target.check(var)?;
+    }
+    let dir = env_dir(target)?;
+    target.create_dir(&dir)?;
+    fs::write(dir.join(SCRIPT), script::render(vars, Shell::Sh))?;
+    target.own(&dir.join(SCRIPT))
+}
+
+/// Removes the script written by [`write_plasma_env`].
+pub fn remove_plasma_env() -> Result<(), EnvError> {
+    remove_plasma_env_in(&Target::default())
+}
+
+/// Removes the script written by [`write_plasma_env_in`].
+pub fn remove_plasma_env_in(target: &Target) -> Result<(), EnvError> {
+    match fs::remove_file(env_dir(target)?.join(SCRIPT)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn rejects_invalid_names() {
+        let vars = vec![(String::from("A"), String::from("1")), (String::from("A=B"), String::from("2"))];
+        assert!(matches!(write_plasma_env_in(&Target::new(), &vars), Err(EnvError::InvalidName(_))));
+    }
+}
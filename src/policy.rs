@@ -0,0 +1,107 @@
+//! Policies restricting which variables may be modified.
+
+use crate::{target::same_name, EnvError};
+use std::{fs, path::Path};
+
+/// Names which may (allowlist) or may not (denylist) be modified, and protected names which may only be
+/// modified through a target allowing it (see [`Target::allow_protected`](crate::Target::allow_protected)).
+/// Names ending with `*` match every name with that prefix. On Windows, names are compared ignoring case.
+///
+/// Policy files have `allow`, `deny` and `protected` lines of comma-separated names, and `#` comments:
+/// ```text
+/// allow = MYAPP_*, PATH
+/// deny = LD_PRELOAD, DYLD_*
+/// protected = PATH
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Policy {
+    allow: Option<Vec<String>>,
+    deny: Vec<String>,
+    protected: Vec<String>,
+}
+
+fn matches(patterns: &[String], var: &str) -> bool {
+    patterns.iter().any(|p| match p.strip_suffix('*') {
+        Some(prefix) => var.get(..prefix.len()).is_some_and(|head| same_name(head, prefix)),
+        None => same_name(p, var),
+    })
+}
+
+impl Policy {
+    /// A policy permitting everything.
+    pub fn new() -> Policy {
+        Policy::default()
+    }
+
+    /// Only these names may be modified.
+    pub fn allow<I: IntoIterator<Item = S>, S: Into<String>>(mut self, names: I) -> Policy {
+        self.allow.get_or_insert_with(Vec::new).extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// These names may not be modified.
+    pub fn deny<I: IntoIterator<Item = S>, S: Into<String>>(mut self, names: I) -> Policy {
+        self.deny.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// These names may only be modified explicitly.
+    pub fn protect<I: IntoIterator<Item = S>, S: Into<String>>(mut self, names: I) -> Policy {
+        self.protected.extend(names.into_iter().map(Into::into));
+        self
+    }
+
+    /// Parses a policy file.
+    pub fn parse(content: &str) -> Result<Policy, EnvError> {
+        let mut policy = Policy::new();
+        for line in content.lines().map(str::trim).filter(|l| !l.is_empty() && !l.starts_with('#')) {
+            let (key, names) = line.split_once('=').ok_or(EnvError::ParseError)?;
+            let names = names.split(',').map(str::trim).filter(|n| !n.is_empty());
+            policy = match key.trim() {
+                "allow" => policy.allow(names),
+                "deny" => policy.deny(names),
+                "protected" => policy.protect(names),
+                _ => return Err(EnvError::ParseError),
+            };
+        }
+        Ok(policy)
+    }
+
+    /// Reads a policy file.
+    /// Example:
+    /// ```rust,no_run
+    /// use globalenv::{set_var_in, Policy, Target};
+    /// let target = Target::new().policy(Policy::from_file("/etc/globalenv/policy.conf").unwrap());
+    /// set_var_in(&target, "MYAPP_HOME", "/opt/myapp").unwrap();
+    /// ```
+    pub fn from_file<P: AsRef<Path>>(path: P) -> Result<Policy, EnvError> {
+        Policy::parse(&fs::read_to_string(path)?)
+    }
+
+    /// Fails with [`EnvError::PolicyError`] if the variable may not be modified.
+    pub fn check(&self, var: &str, allow_protected: bool) -> Result<(), EnvError> {
+        let allowed = self.allow.as_ref().is_none_or(|allow| matches(allow, var));
+        if !allowed || matches(&self.deny, var) || (!allow_protected && matches(&self.protected, var)) {
+            return Err(EnvError::PolicyError);
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_names() {
+        let policy = Policy::parse("# company policy\nallow = MYAPP_*, PATH\ndeny = MYAPP_SECRET\nprotected = PATH\n").unwrap();
+        assert_eq!(policy.check("MYAPP_HOME", false), Ok(()));
+        assert_eq!(policy.check("MYAPP_SECRET", true), Err(EnvError::PolicyError));
+        assert_eq!(policy.check("OTHER", false), Err(EnvError::PolicyError));
+        assert_eq!(policy.check("PATH", false), Err(EnvError::PolicyError));
+        assert_eq!(policy.check("PATH", true), Ok(()));
+        assert_eq!(Policy::new().check("ANYTHING", false), Ok(()));
+        assert_eq!(Policy::parse("allowed = X"), Err(EnvError::ParseError));
+    }
+}
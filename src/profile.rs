@@ -0,0 +1,110 @@
+//! Profiles: sets of variables selected by host name, so the machines sharing a dotfiles repository (laptops,
+//! workstations...) each converge to their own.
+
+use crate::{condition::hostname, EnvError, GlobalEnv, Target};
+
+/// A named set of variables, for the hosts whose name matches one of its patterns.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Profile {
+    name: String,
+    hosts: Vec<String>,
+    vars: Vec<(String, String)>,
+}
+
+/// Whether the host name matches the pattern, where `*` stands for any characters and `?` for one, ignoring case.
+fn matches(pattern: &[u8], host: &[u8]) -> bool {
+    match (pattern.split_first(), host.split_first()) {
+        (None, _) => host.is_empty(),
+        (Some((b'*', rest)), _) => (0..=host.len()).any(|i| matches(rest, &host[i..])),
+        (Some((b'?', rest)), Some((_, host))) => matches(rest, host),
+        (Some((p, rest)), Some((h, host))) => p.eq_ignore_ascii_case(h) && matches(rest, host),
+        (Some(_), None) => false,
+    }
+}
+
+impl Profile {
+    /// An empty profile, for no host.
+    pub fn new(name: &str) -> Profile {
+        Profile { name: name.to_string(), hosts: Vec::new(), vars: Vec::new() }
+    }
+
+    /// The profile applies to the hosts matching these patterns (e.g. `*-laptop`, `ws-??`).
+    pub fn hosts<I: IntoIterator<Item = S>, S: Into<String>>(mut self, patterns: I) -> Profile {
+        self.hosts.extend(patterns.into_iter().map(Into::into));
+        self
+    }
+
+    /// Adds a variable to the profile.
+    pub fn var(mut self, var: &str, value: &str) -> Profile {
+        self.vars.push((var.to_string(), value.to_string()));
+        self
+    }
+
+    /// Name of the profile.
+    pub fn name(&self) -> &str {
+        &self.name
+    }
+
+    /// Whether the profile applies to the host of this name.
+    pub fn applies_to(&self, host: &str) -> bool {
+        self.hosts.iter().any(|pattern| matches(pattern.as_bytes(), host.as_bytes()))
+    }
+}
+
+/// The first profile applying to the host.
+fn select<'a>(profiles: &'a [Profile], host: &str) -> Option<&'a Profile> {
+    profiles.iter().find(|profile| profile.applies_to(host))
+}
+
+/// Applies the first profile whose patterns match the host name of this machine: its variables are set, and the
+/// ones only the other profiles define are unset, so that each machine ends up with the variables of its
+/// profile alone. Returns the name of the profile applied, `None` if none matches (nothing is changed then).
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{apply_for_host, Profile};
+/// let profiles = [
+///     Profile::new("laptop").hosts(["*-laptop"]).var("GOMAXPROCS", "4").var("POWER_SAVING", "1"),
+///     Profile::new("workstation").hosts(["ws-*", "build??"]).var("GOMAXPROCS", "32"),
+/// ];
+/// if let Some(name) = apply_for_host(&profiles).unwrap() {
+///     println!("applied the {} profile", name);
+/// }
+/// ```
+pub fn apply_for_host(profiles: &[Profile]) -> Result<Option<String>, EnvError> {
+    apply_for_host_in(&Target::default(), profiles)
+}
+
+/// Applies the profile of this machine to the given target, in a single write.
+pub fn apply_for_host_in(target: &Target, profiles: &[Profile]) -> Result<Option<String>, EnvError> {
+    let host = hostname().ok_or(EnvError::VarError)?;
+    let Some(profile) = select(profiles, &host) else { return Ok(None) };
+    let mut session = GlobalEnv::open_in(target)?;
+    let others = profiles.iter().flat_map(|other| &other.vars).map(|(var, _)| var);
+    for var in others.filter(|var| !profile.vars.iter().any(|(own, _)| own == *var)) {
+        session.unset(var)?;
+    }
+    for (var, value) in &profile.vars {
+        session.set(var, value)?;
+    }
+    session.flush()?;
+    Ok(Some(profile.name.clone()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn selects_profile_by_host() {
+        let profiles = [
+            Profile::new("laptop").hosts(["*-laptop"]),
+            Profile::new("workstation").hosts(["ws-??", "BUILD*"]),
+        ];
+        assert_eq!(select(&profiles, "alice-laptop").map(Profile::name), Some("laptop"));
+        assert_eq!(select(&profiles, "ws-01").map(Profile::name), Some("workstation"));
+        assert_eq!(select(&profiles, "build-farm").map(Profile::name), Some("workstation"));
+        assert_eq!(select(&profiles, "ws-100"), None);
+        assert_eq!(select(&profiles, "laptop"), None);
+    }
+}
@@ -0,0 +1,92 @@
+//! Configuration provider for [figment](https://docs.rs/figment).
+
+use crate::{filtered_vars_in, Filter, Target};
+use figment::{
+    value::{Dict, Map},
+    Error, Metadata, Profile, Provider,
+};
+
+/// A [`figment::Provider`] of the persisted variables of a target (the values future sessions
+/// will have, rather than those of the current process). Like `figment::providers::Env`, keys are
+/// lowercased and, with a prefix, only the prefixed variables are provided, without their prefix.
+/// Example:
+/// ```rust,no_run
+/// use figment::Figment;
+/// use globalenv::GlobalEnvProvider;
+/// let figment = Figment::new().merge(GlobalEnvProvider::new().prefixed("APP_"));
+/// let url: String = figment.extract_inner("url").unwrap();
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+pub struct GlobalEnvProvider {
+    target: Target,
+    prefix: Option<String>,
+}
+
+impl GlobalEnvProvider {
+    pub fn new() -> GlobalEnvProvider {
+        GlobalEnvProvider::default()
+    }
+
+    /// Reads the variables persisted in this target instead of the default one.
+    pub fn target(mut self, target: Target) -> GlobalEnvProvider {
+        self.target = target;
+        self
+    }
+
+    /// Only provides the variables whose name starts with this prefix (ignoring case).
+    pub fn prefixed(mut self, prefix: &str) -> GlobalEnvProvider {
+        self.prefix = Some(prefix.to_string());
+        self
+    }
+
+    fn key(&self, name: &str) -> Option<String> {
+        let key = match &self.prefix {
+            Some(prefix) => {
+                let head = name.get(..prefix.len())?;
+                if !head.eq_ignore_ascii_case(prefix) {
+                    return None;
+                }
+                &name[prefix.len()..]
+            }
+            None => name,
+        };
+        (!key.is_empty()).then(|| key.to_ascii_lowercase())
+    }
+}
+
+impl Provider for GlobalEnvProvider {
+    fn metadata(&self) -> Metadata {
+        match self.target.location() {
+            Ok(location) => Metadata::named(format!("globalenv ({})", location)),
+            Err(_) => Metadata::named("globalenv"),
+        }
+    }
+
+    fn data(&self) -> Result<Map<Profile, Dict>, Error> {
+        let mut dict = Dict::new();
+        for (name, value) in filtered_vars_in(&self.target, Filter::All).map_err(|e| Error::from(e.to_string()))? {
+            if let Some(key) = self.key(&name) {
+                dict.insert(key, value.parse().expect("infallible"));
+            }
+        }
+        Ok(Profile::Default.collect(dict))
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use figment::Figment;
+    use std::fs;
+
+    #[test]
+    fn provides_persisted_prefixed_variables() {
+        let file = std::env::temp_dir().join("globalenv_provider");
+        fs::write(&file, "export APP_URL=http://localhost\nexport APP_RETRIES=3\nexport OTHER=1\n").unwrap();
+        let figment = Figment::new().merge(GlobalEnvProvider::new().target(Target::new().file(&file)).prefixed("app_"));
+        assert_eq!(figment.extract_inner::<String>("url").unwrap(), "http://localhost");
+        assert_eq!(figment.extract_inner::<u32>("retries").unwrap(), 3);
+        assert!(figment.find_value("other").is_err());
+        fs::remove_file(&file).unwrap();
+    }
+}
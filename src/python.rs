@@ -0,0 +1,76 @@
+//! Python module, for installer scripts: `globalenv.set_var("VAR", "value")`. Built with
+//! [maturin](https://www.maturin.rs) (`maturin build --release`), which enables the `python` feature.
+
+use crate::{get_var, set_var, unset_var, vars, EnvError};
+use pyo3::{
+    create_exception,
+    exceptions::{PyException, PyOSError, PyPermissionError, PyValueError},
+    prelude::*,
+};
+
+create_exception!(globalenv, GlobalEnvError, PyException, "Environment operation error.");
+
+// I/O errors are `OSError`s, policy denials `PermissionError`s and rejected values `ValueError`s,
+// the other errors being `GlobalEnvError`s.
+impl From<EnvError> for PyErr {
+    fn from(e: EnvError) -> PyErr {
+        let message = e.to_string();
+        match e {
+            EnvError::IOError => PyOSError::new_err(message),
+            EnvError::PolicyError => PyPermissionError::new_err(message),
+            EnvError::ValidationError(_) | EnvError::InvalidName(_) | EnvError::ValueTooLong(_) => PyValueError::new_err(message),
+            _ => GlobalEnvError::new_err(message),
+        }
+    }
+}
+
+/// Sets a global environment variable.
+#[pyfunction(name = "set_var")]
+fn py_set_var(var: &str, value: &str) -> Result<(), EnvError> {
+    set_var(var, value)
+}
+
+/// Unsets a global environment variable.
+#[pyfunction(name = "unset_var")]
+fn py_unset_var(var: &str) -> Result<(), EnvError> {
+    unset_var(var)
+}
+
+/// Gets the globally persisted value of a variable, or `None`.
+#[pyfunction(name = "get_var")]
+fn py_get_var(var: &str) -> Result<Option<String>, EnvError> {
+    get_var(var)
+}
+
+/// Lists the globally persisted variables, as `(name, value)` tuples.
+#[pyfunction(name = "vars")]
+fn py_vars() -> Result<Vec<(String, String)>, EnvError> {
+    Ok(vars()?.into_iter().map(Into::into).collect())
+}
+
+#[pymodule]
+fn globalenv(m: &Bound<'_, PyModule>) -> PyResult<()> {
+    m.add("GlobalEnvError", m.py().get_type::<GlobalEnvError>())?;
+    m.add_function(wrap_pyfunction!(py_set_var, m)?)?;
+    m.add_function(wrap_pyfunction!(py_unset_var, m)?)?;
+    m.add_function(wrap_pyfunction!(py_get_var, m)?)?;
+    m.add_function(wrap_pyfunction!(py_vars, m)?)?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn maps_errors_to_exceptions() {
+        Python::initialize();
+        Python::attach(|py| {
+            assert!(PyErr::from(EnvError::IOError).is_instance_of::<PyOSError>(py));
+            assert!(PyErr::from(EnvError::PolicyError).is_instance_of::<PyPermissionError>(py));
+            let e = PyErr::from(EnvError::UnsupportedShell);
+            assert!(e.is_instance_of::<GlobalEnvError>(py));
+            assert_eq!(e.value(py).to_string(), "ENV operation error : Unsupported shell");
+        });
+    }
+}
@@ -0,0 +1,172 @@
+//! `.reg` files (regedit export format) of environment variables.
+
+use crate::{dotenv, filtered_vars_in, EnvError, Filter, ImportOptions, ImportResult, Target};
+use std::{fs, path::Path};
+
+const HEADER: &str = "Windows Registry Editor Version 5.00";
+const USER_ENVIRONMENT: &str = r"HKEY_CURRENT_USER\Environment";
+
+fn escape(s: &str) -> String {
+    s.replace('\\', r"\\").replace('"', "\\\"")
+}
+
+/// Reads a quoted string at the start of `s`, returning it unescaped and the rest of `s`.
+fn unescape(s: &str) -> Option<(String, &str)> {
+    let mut value = String::new();
+    let mut chars = s.strip_prefix('"')?.char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Some((value, &s[i + 2..])),
+            '\\' => value.push(chars.next()?.1),
+            c => value.push(c),
+        }
+    }
+    None
+}
+
+/// Decodes the `hex(2):` data of an expandable string: comma-separated bytes of UTF-16LE text.
+fn decode_expand_sz(hex: &str) -> Option<String> {
+    let bytes = hex
+        .split(',')
+        .map(str::trim)
+        .filter(|b| !b.is_empty())
+        .map(|b| u8::from_str_radix(b, 16).ok())
+        .collect::<Option<Vec<u8>>>()?;
+    let units: Vec<u16> = bytes.chunks(2).map(|c| u16::from_le_bytes([c[0], *c.get(1).unwrap_or(&0)])).collect();
+    Some(String::from_utf16(&units).ok()?.trim_end_matches('\0').to_string())
+}
+
+/// Parses the string and expandable string values of the `Environment` keys of a `.reg` file,
+/// other keys and value types are ignored.
+pub(crate) fn parse(content: &str) -> Result<Vec<(String, String)>, EnvError> {
+    let content = content.trim_start_matches('\u{feff}');
+    let mut lines = content.lines();
+    match lines.next().map(str::trim) {
+        Some(HEADER) | Some("REGEDIT4") => (),
+        _ => return Err(EnvError::ParseError),
+    }
+    let mut vars = Vec::new();
+    let mut environment = false;
+    while let Some(line) = lines.next() {
+        // Hexadecimal data is wrapped with trailing backslashes
+        let mut line = line.trim().to_string();
+        while line.ends_with('\\') {
+            line.pop();
+            line.push_str(lines.next().ok_or(EnvError::ParseError)?.trim());
+        }
+        if line.is_empty() || line.starts_with(';') {
+            continue;
+        }
+        if let Some(key) = line.strip_prefix('[') {
+            let key = key.strip_suffix(']').ok_or(EnvError::ParseError)?;
+            environment = !key.starts_with('-') && key.to_ascii_lowercase().ends_with(r"\environment");
+            continue;
+        }
+        if !environment || line.starts_with('@') {
+            continue;
+        }
+        let (name, data) = unescape(&line).ok_or(EnvError::ParseError)?;
+        let data = data.trim_start().strip_prefix('=').ok_or(EnvError::ParseError)?.trim();
+        if data.starts_with('"') {
+            match unescape(data) {
+                Some((value, rest)) if rest.trim().is_empty() => vars.push((name, value)),
+                _ => return Err(EnvError::ParseError),
+            }
+        } else if let Some(hex) = data.strip_prefix("hex(2):") {
+            vars.push((name, decode_expand_sz(hex).ok_or(EnvError::ParseError)?));
+        }
+    }
+    Ok(vars)
+}
+
+/// Renders the variables as string values of the given key.
+pub(crate) fn render(key: &str, vars: &[(String, String)]) -> String {
+    let mut reg = format!("{}\r\n\r\n[{}]\r\n", HEADER, key);
+    for (name, value) in vars {
+        reg.push_str(&format!("\"{}\"=\"{}\"\r\n", escape(name), escape(value)));
+    }
+    reg.push_str("\r\n");
+    reg
+}
+
+/// Writes a `.reg` file of the globally managed variables, which `regedit` (or `reg import`)
+/// applies under HKCU\Environment.
+/// Example:
+/// ```rust,no_run
+/// globalenv::export_reg("environment.reg").unwrap();
+/// ```
+pub fn export_reg<P: AsRef<Path>>(path: P) -> Result<(), EnvError> {
+    export_reg_in(&Target::default(), path)
+}
+
+/// Writes a `.reg` file of the variables managed in the given target. The key is the target's
+/// registry key on Windows, HKCU\Environment for file targets.
+pub fn export_reg_in<P: AsRef<Path>>(target: &Target, path: P) -> Result<(), EnvError> {
+    let key = match target.store()? {
+        #[cfg(target_os = "windows")]
+        crate::target::Store::Registry(scope) => crate::registry::key_path(&scope),
+        _ => String::from(USER_ENVIRONMENT),
+    };
+    let reg = render(&key, &filtered_vars_in(target, Filter::Managed)?);
+    // regedit expects UTF-16LE with a byte order mark
+    let mut bytes = vec![0xFF, 0xFE];
+    bytes.extend(reg.encode_utf16().flat_map(|u| u.to_le_bytes()));
+    fs::write(path, bytes)?;
+    Ok(())
+}
+
+/// Sets globally every variable of the `Environment` keys of a `.reg` file, such as one exported
+/// from another machine by [`export_reg`] or `regedit`.
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{import_reg, ImportOptions};
+/// import_reg("environment.reg", &ImportOptions::new()).unwrap();
+/// ```
+pub fn import_reg<P: AsRef<Path>>(path: P, options: &ImportOptions) -> Result<Vec<ImportResult>, EnvError> {
+    import_reg_in(&Target::default(), path, options)
+}
+
+/// Sets in the given target every variable of the `Environment` keys of a `.reg` file.
+pub fn import_reg_in<P: AsRef<Path>>(target: &Target, path: P, options: &ImportOptions) -> Result<Vec<ImportResult>, EnvError> {
+    let bytes = fs::read(path)?;
+    let content = match bytes.strip_prefix(&[0xFF, 0xFE]) {
+        Some(utf16) => {
+            let units: Vec<u16> = utf16.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+            String::from_utf16(&units).map_err(|_| EnvError::ParseError)?
+        }
+        None => String::from_utf8(bytes).map_err(|_| EnvError::ParseError)?,
+    };
+    Ok(dotenv::apply(target, parse(&content)?, options))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_escaped_values() {
+        let vars = vec![(String::from("DIR"), String::from(r#"C:\Program Files\"x""#))];
+        assert_eq!(
+            render(USER_ENVIRONMENT, &vars),
+            "Windows Registry Editor Version 5.00\r\n\r\n[HKEY_CURRENT_USER\\Environment]\r\n\"DIR\"=\"C:\\\\Program Files\\\\\\\"x\\\"\"\r\n\r\n"
+        );
+        assert_eq!(parse(&render(USER_ENVIRONMENT, &vars)).unwrap(), vars);
+    }
+
+    #[test]
+    fn parses_environment_keys_only() {
+        let reg = "Windows Registry Editor Version 5.00\r\n\r\n\
+            [HKEY_CURRENT_USER\\Software\\Other]\r\n\"IGNORED\"=\"1\"\r\n\r\n\
+            [HKEY_CURRENT_USER\\Environment]\r\n\
+            ; comment\r\n\
+            \"TEMP\"=hex(2):25,00,55,00,53,00,45,00,52,00,50,00,52,00,4f,00,46,00,49,00,4c,00,45,00,\\\r\n  \
+            25,00,00,00\r\n\
+            \"COUNT\"=dword:00000001\r\n\
+            \"NAME\"=\"value\"\r\n";
+        assert_eq!(parse(reg).unwrap(), vec![
+            (String::from("TEMP"), String::from("%USERPROFILE%")),
+            (String::from("NAME"), String::from("value")),
+        ]);
+        assert_eq!(parse("not a reg file"), Err(EnvError::ParseError));
+    }
+}
@@ -0,0 +1,518 @@
+//! Windows registry backend.
+
+use crate::{meta::Meta, retry, target::same_name, EnvError, Scope, TypedValue};
+use std::{io, ptr};
+use sys::*;
+
+#[cfg(all(feature = "windows-sys", not(feature = "minimal")))]
+mod sys {
+    pub(super) use windows_sys::Win32::{
+        Foundation::{ERROR_MORE_DATA, ERROR_NO_MORE_ITEMS, ERROR_SUCCESS, FILETIME, WIN32_ERROR},
+        System::Registry::*,
+    };
+}
+
+/// The few declarations of the registry API which are needed, without the `windows-sys` dependency.
+#[cfg(any(feature = "minimal", not(feature = "windows-sys")))]
+#[allow(non_camel_case_types, non_snake_case, clippy::upper_case_acronyms)]
+mod sys {
+    use std::ffi::c_void;
+
+    pub(super) type HKEY = *mut c_void;
+    pub(super) type REG_SAM_FLAGS = u32;
+    pub(super) type REG_VALUE_TYPE = u32;
+    pub(super) type WIN32_ERROR = u32;
+
+    pub(super) const HKEY_CURRENT_USER: HKEY = 0x8000_0001usize as HKEY;
+    pub(super) const HKEY_LOCAL_MACHINE: HKEY = 0x8000_0002usize as HKEY;
+    pub(super) const HKEY_USERS: HKEY = 0x8000_0003usize as HKEY;
+    pub(super) const KEY_READ: REG_SAM_FLAGS = 0x20019;
+    pub(super) const KEY_SET_VALUE: REG_SAM_FLAGS = 0x2;
+    pub(super) const REG_OPTION_NON_VOLATILE: u32 = 0;
+    pub(super) const REG_SZ: REG_VALUE_TYPE = 1;
+    pub(super) const REG_EXPAND_SZ: REG_VALUE_TYPE = 2;
+    pub(super) const REG_DWORD: REG_VALUE_TYPE = 4;
+    pub(super) const REG_MULTI_SZ: REG_VALUE_TYPE = 7;
+    pub(super) const ERROR_SUCCESS: WIN32_ERROR = 0;
+    pub(super) const ERROR_MORE_DATA: WIN32_ERROR = 234;
+    pub(super) const ERROR_NO_MORE_ITEMS: WIN32_ERROR = 259;
+
+    #[repr(C)]
+    pub(super) struct FILETIME {
+        pub(super) dwLowDateTime: u32,
+        pub(super) dwHighDateTime: u32,
+    }
+
+    #[link(name = "advapi32")]
+    extern "system" {
+        pub(super) fn RegCloseKey(key: HKEY) -> WIN32_ERROR;
+        pub(super) fn RegOpenKeyExW(key: HKEY, subkey: *const u16, options: u32, access: REG_SAM_FLAGS, result: *mut HKEY) -> WIN32_ERROR;
+        pub(super) fn RegCreateKeyExW(
+            key: HKEY,
+            subkey: *const u16,
+            reserved: u32,
+            class: *const u16,
+            options: u32,
+            access: REG_SAM_FLAGS,
+            security: *const c_void,
+            result: *mut HKEY,
+            disposition: *mut u32,
+        ) -> WIN32_ERROR;
+        pub(super) fn RegQueryValueExW(
+            key: HKEY,
+            name: *const u16,
+            reserved: *const u32,
+            kind: *mut REG_VALUE_TYPE,
+            data: *mut u8,
+            len: *mut u32,
+        ) -> WIN32_ERROR;
+        pub(super) fn RegSetValueExW(key: HKEY, name: *const u16, reserved: u32, kind: REG_VALUE_TYPE, data: *const u8, len: u32) -> WIN32_ERROR;
+        pub(super) fn RegDeleteValueW(key: HKEY, name: *const u16) -> WIN32_ERROR;
+        pub(super) fn RegFlushKey(key: HKEY) -> WIN32_ERROR;
+        pub(super) fn RegQueryInfoKeyW(
+            key: HKEY,
+            class: *mut u16,
+            class_len: *mut u32,
+            reserved: *const u32,
+            subkeys: *mut u32,
+            max_subkey_len: *mut u32,
+            max_class_len: *mut u32,
+            values: *mut u32,
+            max_value_name_len: *mut u32,
+            max_value_len: *mut u32,
+            security_descriptor_len: *mut u32,
+            last_write_time: *mut FILETIME,
+        ) -> WIN32_ERROR;
+        pub(super) fn RegEnumValueW(
+            key: HKEY,
+            index: u32,
+            name: *mut u16,
+            name_len: *mut u32,
+            reserved: *const u32,
+            kind: *mut u32,
+            data: *mut u8,
+            len: *mut u32,
+        ) -> WIN32_ERROR;
+    }
+}
+
+const SYSTEM_ENVIRONMENT: &str = r"SYSTEM\CurrentControlSet\Control\Session Manager\Environment";
+/// Profile of LocalSystem, under HKU
+const DEFAULT_PROFILE: &str = ".DEFAULT";
+/// Keys of the services, under HKLM
+const SERVICES: &str = r"SYSTEM\CurrentControlSet\Services";
+/// Sidecar key listing the variables set by this crate (with their metadata), under HKCU, HKLM or HKU\.DEFAULT
+const MANAGED: &str = r"Software\globalenv\Managed";
+
+/// Null-terminated UTF-16.
+fn wide(s: &str) -> Vec<u16> {
+    s.encode_utf16().chain(Some(0)).collect()
+}
+
+fn check(code: WIN32_ERROR) -> io::Result<()> {
+    match code {
+        ERROR_SUCCESS => Ok(()),
+        code => Err(io::Error::from_raw_os_error(code as i32)),
+    }
+}
+
+/// Registry value: its type and raw data.
+struct Value {
+    kind: REG_VALUE_TYPE,
+    data: Vec<u8>,
+}
+
+impl Value {
+    /// String value (`REG_SZ`, `REG_EXPAND_SZ` left unexpanded, or `REG_MULTI_SZ` joined with newlines).
+    fn text(&self) -> Option<String> {
+        let units: Vec<u16> = self.data.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+        let s = String::from_utf16(&units).ok()?;
+        match self.kind {
+            REG_SZ | REG_EXPAND_SZ => Some(s.trim_end_matches('\0').to_string()),
+            REG_MULTI_SZ => Some(s.trim_end_matches('\0').replace('\0', "\n")),
+            _ => None,
+        }
+    }
+
+    /// Elements of a `REG_MULTI_SZ` value, or of a string one separated with `;`.
+    fn strings(&self) -> Option<Vec<String>> {
+        match self.kind {
+            REG_MULTI_SZ => {
+                let units: Vec<u16> = self.data.chunks_exact(2).map(|c| u16::from_le_bytes([c[0], c[1]])).collect();
+                let units = units.strip_suffix(&[0]).unwrap_or(&units);
+                let units = units.strip_suffix(&[0]).unwrap_or(units);
+                match units.is_empty() {
+                    true => Some(Vec::new()),
+                    false => units.split(|u| *u == 0).map(|s| String::from_utf16(s).ok()).collect(),
+                }
+            }
+            _ => Some(self.text()?.split(';').filter(|s| !s.is_empty()).map(String::from).collect()),
+        }
+    }
+}
+
+/// Opened key, closed when dropped.
+struct Key(HKEY);
+
+impl Drop for Key {
+    fn drop(&mut self) {
+        unsafe { RegCloseKey(self.0) };
+    }
+}
+
+impl Key {
+    fn open(hive: HKEY, path: &str, access: REG_SAM_FLAGS) -> io::Result<Key> {
+        let mut key = ptr::null_mut();
+        check(unsafe { RegOpenKeyExW(hive, wide(path).as_ptr(), 0, access, &mut key) })?;
+        Ok(Key(key))
+    }
+
+    fn create(hive: HKEY, path: &str, access: REG_SAM_FLAGS) -> io::Result<Key> {
+        let mut key = ptr::null_mut();
+        check(unsafe {
+            RegCreateKeyExW(
+                hive, wide(path).as_ptr(), 0, ptr::null(), REG_OPTION_NON_VOLATILE, access, ptr::null(), &mut key, ptr::null_mut(),
+            )
+        })?;
+        Ok(Key(key))
+    }
+
+    fn get(&self, name: &str) -> io::Result<Value> {
+        let name = wide(name);
+        let mut data = vec![0u8; 256];
+        loop {
+            let (mut kind, mut len) = (0, data.len() as u32);
+            match unsafe { RegQueryValueExW(self.0, name.as_ptr(), ptr::null(), &mut kind, data.as_mut_ptr(), &mut len) } {
+                // The value may have grown between calls
+                ERROR_MORE_DATA => data.resize(len.max(data.len() as u32 * 2) as usize, 0),
+                code => {
+                    check(code)?;
+                    data.truncate(len as usize);
+                    return Ok(Value { kind, data });
+                }
+            }
+        }
+    }
+
+    fn get_string(&self, name: &str) -> io::Result<String> {
+        self.get(name)?.text().ok_or_else(|| io::Error::from(io::ErrorKind::InvalidData))
+    }
+
+    fn set_string(&self, name: &str, value: &str, kind: REG_VALUE_TYPE) -> io::Result<()> {
+        let data: Vec<u8> = wide(value).iter().flat_map(|u| u.to_le_bytes()).collect();
+        retry::io(|| check(unsafe { RegSetValueExW(self.0, wide(name).as_ptr(), 0, kind, data.as_ptr(), data.len() as u32) }))?;
+        self.flush()
+    }
+
+    /// `REG_MULTI_SZ` value: null-terminated strings, and a final null.
+    fn set_strings(&self, name: &str, values: &[String]) -> io::Result<()> {
+        let units: Vec<u16> = values.iter().flat_map(|v| wide(v)).chain(Some(0)).collect();
+        let data: Vec<u8> = units.iter().flat_map(|u| u.to_le_bytes()).collect();
+        retry::io(|| check(unsafe { RegSetValueExW(self.0, wide(name).as_ptr(), 0, REG_MULTI_SZ, data.as_ptr(), data.len() as u32) }))?;
+        self.flush()
+    }
+
+    fn set_dword(&self, name: &str, value: u32) -> io::Result<()> {
+        let data = value.to_le_bytes();
+        retry::io(|| check(unsafe { RegSetValueExW(self.0, wide(name).as_ptr(), 0, REG_DWORD, data.as_ptr(), 4) }))?;
+        self.flush()
+    }
+
+    fn delete(&self, name: &str) -> io::Result<()> {
+        retry::io(|| check(unsafe { RegDeleteValueW(self.0, wide(name).as_ptr()) }))?;
+        self.flush()
+    }
+
+    /// Time of the last change of the key (or of one of its values), in 100 ns intervals.
+    fn last_write(&self) -> io::Result<u64> {
+        let mut time = FILETIME { dwLowDateTime: 0, dwHighDateTime: 0 };
+        let null = ptr::null_mut();
+        check(unsafe {
+            RegQueryInfoKeyW(self.0, null as *mut u16, null, ptr::null(), null, null, null, null, null, null, null, &mut time)
+        })?;
+        Ok(u64::from(time.dwHighDateTime) << 32 | u64::from(time.dwLowDateTime))
+    }
+
+    /// Writes the changes of the key to the disk, if writes are durable (see [`crate::set_durable`]).
+    fn flush(&self) -> io::Result<()> {
+        match crate::durability::durable() {
+            true => check(unsafe { RegFlushKey(self.0) }),
+            false => Ok(()),
+        }
+    }
+
+    /// Names and values of the key.
+    fn values(&self) -> io::Result<Vec<(String, Value)>> {
+        let mut values = Vec::new();
+        let (mut name, mut data) = (vec![0u16; 256], vec![0u8; 1024]);
+        let mut index = 0;
+        loop {
+            let (mut name_len, mut kind, mut len) = (name.len() as u32, 0, data.len() as u32);
+            let code = unsafe {
+                RegEnumValueW(self.0, index, name.as_mut_ptr(), &mut name_len, ptr::null(), &mut kind, data.as_mut_ptr(), &mut len)
+            };
+            match code {
+                ERROR_NO_MORE_ITEMS => return Ok(values),
+                // Retried with larger buffers (the name length is not reported)
+                ERROR_MORE_DATA => {
+                    name.resize(name.len() * 2, 0);
+                    data.resize((len as usize).max(data.len() * 2), 0);
+                }
+                code => {
+                    check(code)?;
+                    let value = Value { kind, data: data[..len as usize].to_vec() };
+                    values.push((String::from_utf16_lossy(&name[..name_len as usize]), value));
+                    index += 1;
+                }
+            }
+        }
+    }
+}
+
+/// The name of the variable as stored, if it is set with another casing: names are case-insensitive, and
+/// setting `PATH` keeps `Path`.
+fn stored_name(key: &Key, var: &str) -> io::Result<String> {
+    Ok(key.values()?.into_iter().map(|(name, _)| name).find(|name| same_name(name, var)).unwrap_or_else(|| var.to_string()))
+}
+
+/// The hive of the scope, a project having none.
+fn hive(scope: &Scope) -> io::Result<HKEY> {
+    match scope {
+        Scope::User => Ok(HKEY_CURRENT_USER),
+        Scope::System => Ok(HKEY_LOCAL_MACHINE),
+        Scope::Service => Ok(HKEY_USERS),
+        Scope::Project(_) => Err(io::ErrorKind::Unsupported.into()),
+    }
+}
+
+/// The sidecar key, in the profile of LocalSystem for the service scope (HKU only holds profiles).
+fn managed_path(scope: &Scope) -> String {
+    match scope {
+        Scope::Service => format!(r"{}\{}", DEFAULT_PROFILE, MANAGED),
+        _ => String::from(MANAGED),
+    }
+}
+
+fn open(scope: &Scope, flags: REG_SAM_FLAGS) -> io::Result<Key> {
+    match scope {
+        // Missing on some pristine accounts and server images
+        Scope::User => Key::create(hive(scope)?, "Environment", flags),
+        Scope::System => Key::open(hive(scope)?, SYSTEM_ENVIRONMENT, flags),
+        Scope::Service => Key::create(hive(scope)?, &format!(r"{}\Environment", DEFAULT_PROFILE), flags),
+        Scope::Project(_) => Err(io::ErrorKind::Unsupported.into()),
+    }
+}
+
+/// Stamp of the environment of the scope, which changes with any of its values.
+pub(crate) fn stamp(scope: &Scope) -> Result<u64, EnvError> {
+    Ok(open(scope, KEY_READ)?.last_write()?)
+}
+
+pub(crate) fn key_path(scope: &Scope) -> String {
+    match scope {
+        Scope::User => String::from(r"HKEY_CURRENT_USER\Environment"),
+        Scope::System => format!(r"HKEY_LOCAL_MACHINE\{}", SYSTEM_ENVIRONMENT),
+        Scope::Service => format!(r"HKEY_USERS\{}\Environment", DEFAULT_PROFILE),
+        Scope::Project(dir) => dir.display().to_string(),
+    }
+}
+
+/// The environment key of this scope can be opened for writing.
+pub(crate) fn writable(scope: &Scope) -> bool {
+    open(scope, KEY_READ | KEY_SET_VALUE).is_ok()
+}
+
+pub(crate) fn set(scope: &Scope, var: &str, value: &str, meta: &Meta) -> Result<(), EnvError> {
+    let key = open(scope, KEY_READ | KEY_SET_VALUE)?;
+    let var = &stored_name(&key, var)?;
+    // An expandable value (e.g. a Path referencing %USERPROFILE%) stays expandable
+    let kind = match key.get(var) {
+        Ok(current) if current.kind == REG_EXPAND_SZ => REG_EXPAND_SZ,
+        _ => REG_SZ,
+    };
+    key.set_string(var, value, kind)?;
+    let managed = Key::create(hive(scope)?, &managed_path(scope), KEY_SET_VALUE)?;
+    managed.set_string(var, &meta.to_string(), REG_SZ)?;
+    Ok(())
+}
+
+/// Appends to the value, which becomes expandable, unless it already has the suffix. Returns the new value.
+pub(crate) fn append(scope: &Scope, var: &str, suffix: &str, sep: &str, meta: &Meta) -> Result<String, EnvError> {
+    let key = open(scope, KEY_READ | KEY_SET_VALUE)?;
+    let var = &stored_name(&key, var)?;
+    let value = match key.get_string(var) {
+        Ok(current) if current.split(sep).any(|s| s == suffix) => current,
+        Ok(current) if !current.is_empty() => format!("{}{}{}", current, sep, suffix),
+        Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+        _ => suffix.to_string(),
+    };
+    key.set_string(var, &value, REG_EXPAND_SZ)?;
+    let managed = Key::create(hive(scope)?, &managed_path(scope), KEY_SET_VALUE)?;
+    managed.set_string(var, &meta.to_string(), REG_SZ)?;
+    Ok(value)
+}
+
+pub(crate) fn meta(scope: &Scope, var: &str) -> Result<Meta, EnvError> {
+    let managed = match Key::open(hive(scope)?, &managed_path(scope), KEY_READ) {
+        Ok(managed) => managed,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Meta::default()),
+        Err(e) => return Err(e.into()),
+    };
+    match managed.get_string(var) {
+        Ok(meta) => Ok(Meta::parse(&meta)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Meta::default()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Deletes the value, returning whether there was one.
+pub(crate) fn unset(scope: &Scope, var: &str) -> Result<bool, EnvError> {
+    let key = open(scope, KEY_SET_VALUE)?;
+    let existed = match key.delete(var) {
+        Ok(()) => true,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => false,
+        Err(e) => return Err(e.into()),
+    };
+    if let Ok(managed) = Key::open(hive(scope)?, &managed_path(scope), KEY_SET_VALUE) {
+        match managed.delete(var) {
+            Err(e) if e.kind() != io::ErrorKind::NotFound => return Err(e.into()),
+            _ => (),
+        }
+    }
+    Ok(existed)
+}
+
+pub(crate) fn get(scope: &Scope, var: &str) -> Result<Option<String>, EnvError> {
+    let key = open(scope, KEY_READ)?;
+    match key.get_string(var) {
+        Ok(value) => Ok(Some(value)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Elements of a list value, see [`Value::strings`].
+pub(crate) fn get_list(scope: &Scope, var: &str) -> Result<Option<Vec<String>>, EnvError> {
+    let key = open(scope, KEY_READ)?;
+    match key.get(var) {
+        Ok(value) => Ok(Some(value.strings().ok_or(EnvError::IOError)?)),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(None),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Writes the list as a `REG_MULTI_SZ` value.
+pub(crate) fn set_list(scope: &Scope, var: &str, list: &[String], meta: &Meta) -> Result<(), EnvError> {
+    let key = open(scope, KEY_READ | KEY_SET_VALUE)?;
+    let var = &stored_name(&key, var)?;
+    key.set_strings(var, list)?;
+    let managed = Key::create(hive(scope)?, &managed_path(scope), KEY_SET_VALUE)?;
+    managed.set_string(var, &meta.to_string(), REG_SZ)?;
+    Ok(())
+}
+
+/// Key of the scope's hive holding typed values: its environment key, or another one (created if needed).
+fn typed_key(scope: &Scope, key: Option<&str>, flags: REG_SAM_FLAGS) -> io::Result<Key> {
+    match key {
+        None => open(scope, flags),
+        Some(key) if *scope == Scope::Service => Key::create(hive(scope)?, &format!(r"{}\{}", DEFAULT_PROFILE, key), flags),
+        Some(key) => Key::create(hive(scope)?, key, flags),
+    }
+}
+
+pub(crate) fn get_typed(scope: &Scope, key: Option<&str>, var: &str) -> Result<Option<TypedValue>, EnvError> {
+    let value = match typed_key(scope, key, KEY_READ).and_then(|key| key.get(var)) {
+        Ok(value) => value,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(None),
+        Err(e) => return Err(e.into()),
+    };
+    match (value.kind, value.data.as_slice()) {
+        (REG_DWORD, &[a, b, c, d]) => Ok(Some(TypedValue::Dword(u32::from_le_bytes([a, b, c, d])))),
+        _ => Ok(Some(TypedValue::String(value.text().ok_or(EnvError::IOError)?))),
+    }
+}
+
+/// Writes the value with its type, recording it in the sidecar key when it is in the environment key.
+pub(crate) fn set_typed(scope: &Scope, key: Option<&str>, var: &str, value: &TypedValue, meta: &Meta) -> Result<(), EnvError> {
+    let typed = typed_key(scope, key, KEY_READ | KEY_SET_VALUE)?;
+    let var = &stored_name(&typed, var)?;
+    match value {
+        TypedValue::String(value) => typed.set_string(var, value, REG_SZ)?,
+        TypedValue::Dword(value) => typed.set_dword(var, *value)?,
+    }
+    if key.is_none() {
+        let managed = Key::create(hive(scope)?, &managed_path(scope), KEY_SET_VALUE)?;
+        managed.set_string(var, &meta.to_string(), REG_SZ)?;
+    }
+    Ok(())
+}
+
+pub(crate) fn vars(scope: &Scope) -> Result<Vec<(String, String)>, EnvError> {
+    let key = open(scope, KEY_READ)?;
+    let mut vars = Vec::new();
+    for (name, value) in key.values()? {
+        // Non-string values (DWORD, binary...) are not environment variables
+        if let Some(value) = value.text() {
+            vars.push((name, value));
+        }
+    }
+    Ok(vars)
+}
+
+/// Variables listed in the sidecar key which are still set, with the casing of their stored name.
+pub(crate) fn managed_vars(scope: &Scope) -> Result<Vec<(String, String)>, EnvError> {
+    let managed = match Key::open(hive(scope)?, &managed_path(scope), KEY_READ) {
+        Ok(managed) => managed,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => return Ok(Vec::new()),
+        Err(e) => return Err(e.into()),
+    };
+    let names: Vec<String> = managed.values()?.into_iter().map(|(name, _)| name).collect();
+    Ok(vars(scope)?.into_iter().filter(|(var, _)| names.iter().any(|name| same_name(name, var))).collect())
+}
+
+/// Key of a service, which must exist.
+fn service_key(service: &str, flags: REG_SAM_FLAGS) -> Result<Key, EnvError> {
+    if service.is_empty() || service.contains(['\\', '/']) {
+        return Err(EnvError::VarError);
+    }
+    Ok(Key::open(HKEY_LOCAL_MACHINE, &format!(r"{}\{}", SERVICES, service), flags)?)
+}
+
+/// The `VAR=value` strings of the `Environment` value of a service, which the Service Control Manager adds to
+/// the environment of its process.
+pub(crate) fn service_environment(service: &str) -> Result<Vec<String>, EnvError> {
+    let key = service_key(service, KEY_READ)?;
+    match key.get_string("Environment") {
+        Ok(entries) => Ok(entries.lines().filter(|e| !e.is_empty()).map(String::from).collect()),
+        Err(e) if e.kind() == io::ErrorKind::NotFound => Ok(Vec::new()),
+        Err(e) => Err(e.into()),
+    }
+}
+
+/// Replaces the `Environment` value of a service, which is deleted when there is no entry left.
+pub(crate) fn set_service_environment(service: &str, entries: &[String]) -> Result<(), EnvError> {
+    let key = service_key(service, KEY_SET_VALUE)?;
+    if !entries.is_empty() {
+        return Ok(key.set_strings("Environment", entries)?);
+    }
+    match key.delete("Environment") {
+        Err(e) if e.kind() != io::ErrorKind::NotFound => Err(e.into()),
+        _ => Ok(()),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn decodes_string_values() {
+        let utf16 = |s: &str| -> Vec<u8> { s.encode_utf16().flat_map(|u| u.to_le_bytes()).collect() };
+        assert_eq!(Value { kind: REG_EXPAND_SZ, data: utf16("%USERPROFILE%\\bin\0") }.text().as_deref(), Some("%USERPROFILE%\\bin"));
+        assert_eq!(Value { kind: REG_MULTI_SZ, data: utf16("a\0b\0\0") }.text().as_deref(), Some("a\nb"));
+        assert_eq!(Value { kind: REG_MULTI_SZ, data: utf16("a\0b\0\0") }.strings(), Some(vec![String::from("a"), String::from("b")]));
+        assert_eq!(Value { kind: REG_MULTI_SZ, data: utf16("\0") }.strings(), Some(Vec::new()));
+        assert_eq!(Value { kind: REG_SZ, data: utf16("a;;b\0") }.strings(), Some(vec![String::from("a"), String::from("b")]));
+        assert_eq!(Value { kind: REG_DWORD, data: vec![1, 0, 0, 0] }.text(), None);
+    }
+}
@@ -0,0 +1,209 @@
+//! Startup files of remote hosts, edited over SSH with the `ssh` command.
+
+use crate::{envfile, syntax::{quote_posix, Syntax}, target::check_name, EnvError, Policy, SetOptions, Shell, Target};
+use std::{
+    io::Write,
+    path::Path,
+    process::{Command, Stdio},
+};
+
+/// Variables of a remote host, persisted in the startup file of the remote user's shell. Each operation reads
+/// the file, patches it locally and writes it back atomically (through a temporary file renamed over it), with
+/// the `ssh` command: authentication is that of the local OpenSSH configuration, in batch mode.
+/// Example:
+/// ```rust,no_run
+/// use globalenv::RemoteBackend;
+/// for host in ["deploy@web1", "deploy@web2"] {
+///     RemoteBackend::new(host).set_var("APP_ENV", "production").unwrap();
+/// }
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+pub struct RemoteBackend {
+    host: String,
+    port: Option<u16>,
+    options: Vec<String>,
+    shell: Option<Shell>,
+    file: Option<String>,
+    policy: Policy,
+}
+
+impl RemoteBackend {
+    /// Host, as given to ssh (`host`, `user@host` or an alias of `~/.ssh/config`).
+    pub fn new(host: &str) -> RemoteBackend {
+        RemoteBackend { host: host.to_string(), port: None, options: Vec::new(), shell: None, file: None, policy: Policy::default() }
+    }
+
+    pub fn port(mut self, port: u16) -> RemoteBackend {
+        self.port = Some(port);
+        self
+    }
+
+    /// Adds an ssh option, e.g. `StrictHostKeyChecking=accept-new`.
+    pub fn option(mut self, option: &str) -> RemoteBackend {
+        self.options.push(option.to_string());
+        self
+    }
+
+    /// Edits the startup file of this shell instead of detecting the remote user's shell from `$SHELL`.
+    pub fn shell(mut self, shell: Shell) -> RemoteBackend {
+        self.shell = Some(shell);
+        self
+    }
+
+    /// Edits this remote file (relative to the remote home directory, if not absolute).
+    pub fn file(mut self, file: &str) -> RemoteBackend {
+        self.file = Some(file.to_string());
+        self
+    }
+
+    /// Only lets the policy's permitted variables be set or unset.
+    pub fn policy(mut self, policy: Policy) -> RemoteBackend {
+        self.policy = policy;
+        self
+    }
+
+    /// Fails if the name can't be written to a startup file, or if the policy doesn't permit modifying it.
+    fn check(&self, var: &str) -> Result<(), EnvError> {
+        check_name(var, false)?;
+        self.policy.check(var, false)
+    }
+
+    /// Runs the script with the remote `sh`, the login shell of the remote user being any.
+    fn command(&self, script: &str) -> Command {
+        let mut ssh = Command::new("ssh");
+        ssh.args(["-o", "BatchMode=yes"]);
+        if let Some(port) = self.port {
+            ssh.arg("-p").arg(port.to_string());
+        }
+        for option in &self.options {
+            ssh.arg("-o").arg(option);
+        }
+        ssh.arg("--").arg(&self.host).arg(format!("sh -c {}", quote_posix(script)));
+        ssh
+    }
+
+    fn run(&self, script: &str, input: Option<&str>) -> Result<String, EnvError> {
+        let mut child = self
+            .command(script)
+            .stdin(if input.is_some() { Stdio::piped() } else { Stdio::null() })
+            .stdout(Stdio::piped())
+            .stderr(Stdio::null())
+            .spawn()?;
+        if let (Some(input), Some(mut stdin)) = (input, child.stdin.take()) {
+            stdin.write_all(input.as_bytes())?;
+        }
+        let output = child.wait_with_output()?;
+        if !output.status.success() {
+            return Err(EnvError::IOError);
+        }
+        String::from_utf8(output.stdout).map_err(|_| EnvError::ParseError)
+    }
+
+    /// Remote file and its syntax.
+    fn location(&self) -> Result<(String, Syntax), EnvError> {
+        let shell = match (&self.file, self.shell) {
+            (Some(file), shell) => return Ok((file.clone(), shell.map_or_else(|| Shell::syntax_of(Path::new(file)), |s| s.syntax()))),
+            (None, Some(shell)) => shell,
+            (None, None) => Shell::from_path(self.run("printf %s \"$SHELL\"", None)?.trim())?,
+        };
+        Ok((shell.env_file().to_string(), shell.syntax()))
+    }
+
+    /// Content of the file, empty if it doesn't exist.
+    fn read(&self, file: &str) -> Result<String, EnvError> {
+        self.run(&read_script(file), None)
+    }
+
+    fn write(&self, file: &str, content: &str) -> Result<(), EnvError> {
+        self.run(&write_script(file), Some(content)).map(drop)
+    }
+
+    /// Sets a variable on the remote host.
+    pub fn set_var(&self, var: &str, value: &str) -> Result<(), EnvError> {
+        self.set_var_with(var, value, &SetOptions::default())
+    }
+
+    /// Sets a variable on the remote host with options. The placeholders of a template are resolved locally.
+    pub fn set_var_with(&self, var: &str, value: &str, options: &SetOptions) -> Result<(), EnvError> {
+        self.check(var)?;
+        let value = &*options.value(&Target::default(), value)?;
+        options.validate(var, value)?;
+        let (file, syntax) = self.location()?;
+        let content = self.read(&file)?;
+        let changed = envfile::get_from(&content, syntax, var).as_deref() != Some(value);
+        let meta = options.meta(&envfile::meta_content(&content, syntax, var), changed);
+        match envfile::set_content(&content, syntax, var, value, &meta) {
+            Some(content) => self.write(&file, &content),
+            None => Ok(()),
+        }
+    }
+
+    /// Unsets a variable on the remote host.
+    pub fn unset_var(&self, var: &str) -> Result<(), EnvError> {
+        self.check(var)?;
+        let (file, syntax) = self.location()?;
+        match envfile::unset_content(&self.read(&file)?, syntax, var) {
+            Some(content) => self.write(&file, &content),
+            None => Ok(()),
+        }
+    }
+
+    /// Gets the value of a variable persisted on the remote host.
+    pub fn get_var(&self, var: &str) -> Result<Option<String>, EnvError> {
+        let (file, syntax) = self.location()?;
+        Ok(envfile::get_from(&self.read(&file)?, syntax, var))
+    }
+
+    /// Lists the variables persisted on the remote host.
+    pub fn vars(&self) -> Result<Vec<(String, String)>, EnvError> {
+        let (file, syntax) = self.location()?;
+        Ok(envfile::vars_from(&self.read(&file)?, syntax))
+    }
+}
+
+fn read_script(file: &str) -> String {
+    let file = quote_posix(file);
+    format!("if [ -e {file} ]; then cat -- {file}; fi")
+}
+
+/// Writes stdin to a temporary file next to the file, then renames it over the file.
+fn write_script(file: &str) -> String {
+    let (dir, name) = match file.rsplit_once('/') {
+        Some((dir, name)) => (if dir.is_empty() { "/" } else { dir }, name),
+        None => (".", file),
+    };
+    let temp = quote_posix(&format!("{}/.{}.globalenv-tmp", dir, name));
+    format!(
+        "mkdir -p -- {dir} && cat > {temp} && {{ [ ! -e {file} ] || chmod \"$(stat -c %a -- {file} 2>/dev/null || stat -f %Lp -- {file})\" {temp}; }} && mv -f -- {temp} {file}",
+        dir = quote_posix(dir),
+        temp = temp,
+        file = quote_posix(file)
+    )
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn builds_ssh_commands() {
+        let remote = RemoteBackend::new("deploy@web1").port(2222).option("ConnectTimeout=5");
+        let args: Vec<_> = remote.command("true").get_args().map(|a| a.to_str().unwrap().to_string()).collect();
+        assert_eq!(args, ["-o", "BatchMode=yes", "-p", "2222", "-o", "ConnectTimeout=5", "--", "deploy@web1", "sh -c true"]);
+        assert_eq!(read_script(".bashrc"), "if [ -e .bashrc ]; then cat -- .bashrc; fi");
+        assert!(write_script(".config/fish/config.fish")
+            .starts_with("mkdir -p -- .config/fish && cat > .config/fish/.config.fish.globalenv-tmp && "));
+        assert_eq!(
+            RemoteBackend::new("h").file(".cshrc").location(),
+            Ok((String::from(".cshrc"), Syntax::Csh))
+        );
+    }
+
+    #[test]
+    fn checks_names_before_connecting() {
+        let remote = RemoteBackend::new("unreachable.invalid").policy(Policy::new().deny(["DENIED_*"]));
+        assert!(matches!(remote.set_var("A;B", "1"), Err(EnvError::InvalidName(_))));
+        assert!(matches!(remote.unset_var("$(reboot)"), Err(EnvError::InvalidName(_))));
+        assert_eq!(remote.set_var("DENIED_REMOTE", "1"), Err(EnvError::PolicyError));
+    }
+}
@@ -0,0 +1,178 @@
+//! Retries of the writes failing transiently, e.g. a startup file briefly locked by an editor or a sync client,
+//! or a registry key by another process.
+
+use std::{
+    fs, io,
+    path::{Path, PathBuf},
+    sync::RwLock,
+    thread,
+    time::Duration,
+};
+
+/// How writes failing transiently are retried, see [`set_retry`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Retry {
+    attempts: u32,
+    backoff: Duration,
+}
+
+/// 3 attempts, 50 ms apart then 100 ms.
+const DEFAULT: Retry = Retry { attempts: 3, backoff: Duration::from_millis(50) };
+
+impl Default for Retry {
+    fn default() -> Retry {
+        DEFAULT
+    }
+}
+
+impl Retry {
+    pub fn new() -> Retry {
+        Retry::default()
+    }
+
+    /// Number of attempts of a write, 1 not retrying it.
+    pub fn attempts(mut self, attempts: u32) -> Retry {
+        self.attempts = attempts.max(1);
+        self
+    }
+
+    /// Delay before the first retry, doubled before each of the next ones.
+    pub fn backoff(mut self, backoff: Duration) -> Retry {
+        self.backoff = backoff;
+        self
+    }
+}
+
+static RETRY: RwLock<Retry> = RwLock::new(DEFAULT);
+
+/// Sets how the writes of this process (to startup files, the registry...) are retried when they fail with a
+/// transient error: a file or key busy, locked or shared by another process (`EBUSY`, `EAGAIN`, Windows
+/// sharing and lock violations, and the access denials an antivirus scanning a file causes). Other errors fail
+/// at once. By default, a write is attempted 3 times.
+/// Example:
+/// ```rust
+/// use globalenv::{set_retry, Retry};
+/// use std::time::Duration;
+/// set_retry(Retry::new().attempts(5).backoff(Duration::from_millis(100)));
+/// ```
+pub fn set_retry(retry: Retry) {
+    *RETRY.write().unwrap_or_else(|e| e.into_inner()) = retry;
+}
+
+fn transient(e: &io::Error) -> bool {
+    if matches!(e.kind(), io::ErrorKind::ResourceBusy | io::ErrorKind::WouldBlock | io::ErrorKind::Interrupted) {
+        return true;
+    }
+    #[cfg(target_family = "unix")]
+    let codes = [libc::EBUSY, libc::EAGAIN, libc::ETXTBSY];
+    // Access denied, sharing violation, lock violation
+    #[cfg(target_os = "windows")]
+    let codes = [5, 32, 33];
+    #[cfg(not(any(target_family = "unix", target_os = "windows")))]
+    let codes: [i32; 0] = [];
+    e.raw_os_error().is_some_and(|code| codes.contains(&code))
+}
+
+fn retry_with<T>(retry: Retry, mut op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    let mut delay = retry.backoff;
+    for _ in 1..retry.attempts {
+        match op() {
+            Err(e) if transient(&e) => {
+                thread::sleep(delay);
+                delay = delay.saturating_mul(2);
+            }
+            result => return result,
+        }
+    }
+    op()
+}
+
+/// Runs the operation, retrying it as configured with [`set_retry`].
+pub(crate) fn io<T>(op: impl FnMut() -> io::Result<T>) -> io::Result<T> {
+    retry_with(*RETRY.read().unwrap_or_else(|e| e.into_inner()), op)
+}
+
+/// Temporary file, next to the one it replaces, a new content is written to.
+pub(crate) fn temp_path(path: &Path) -> PathBuf {
+    let file_name = path.file_name().map(|n| n.to_string_lossy().into_owned()).unwrap_or_default();
+    path.with_file_name(format!(".{}.globalenv-tmp", file_name))
+}
+
+/// Replaces the file with the temporary one, which gets its permissions and owner: readers never see a
+/// partially written file. The temporary file is removed if the replacement fails.
+pub(crate) fn replace(temppath: &Path, path: &Path) -> io::Result<()> {
+    let result = (|| {
+        if let Ok(metadata) = fs::metadata(path) {
+            fs::set_permissions(temppath, metadata.permissions())?;
+            // The file of another user, edited as root (e.g. under sudo), stays theirs
+            #[cfg(target_family = "unix")]
+            {
+                use std::os::unix::fs::MetadataExt;
+                if metadata.uid() != unsafe { libc::geteuid() } {
+                    std::os::unix::fs::chown(temppath, Some(metadata.uid()), Some(metadata.gid()))?;
+                }
+            }
+        }
+        io(|| fs::rename(temppath, path))
+    })();
+    if result.is_err() {
+        let _ = fs::remove_file(temppath);
+    }
+    result?;
+    crate::durability::sync(path)
+}
+
+/// Writes the file atomically (through a temporary file renamed over it), retried, and synced if writes are
+/// durable (see [`set_durable`](crate::set_durable)). A missing file is created.
+pub(crate) fn write<C: AsRef<[u8]>>(path: &Path, contents: C) -> io::Result<()> {
+    // Symbolic links (e.g. from a dotfiles manager) are kept, their target is rewritten
+    let path = match fs::canonicalize(path) {
+        Ok(path) => path,
+        Err(e) if e.kind() == io::ErrorKind::NotFound => path.to_path_buf(),
+        Err(e) => return Err(e),
+    };
+    let temppath = temp_path(&path);
+    if let Err(e) = io(|| fs::write(&temppath, contents.as_ref())) {
+        let _ = fs::remove_file(&temppath);
+        return Err(e);
+    }
+    replace(&temppath, &path)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn retries_busy_writes() {
+        let retry = Retry::new().backoff(Duration::ZERO);
+        let mut failures = 2;
+        let result = retry_with(retry, || match failures {
+            0 => Ok("written"),
+            _ => {
+                failures -= 1;
+                Err(io::Error::from(io::ErrorKind::ResourceBusy))
+            }
+        });
+        assert_eq!(result.unwrap(), "written");
+        let mut calls = 0;
+        let result: io::Result<()> = retry_with(retry, || {
+            calls += 1;
+            Err(io::Error::from(io::ErrorKind::NotFound))
+        });
+        assert!(result.is_err() && calls == 1);
+        assert!(retry_with(retry.attempts(2), || Err::<(), _>(io::Error::from(io::ErrorKind::WouldBlock))).is_err());
+    }
+
+    #[test]
+    fn writes_through_temporary_file() {
+        let file = std::env::temp_dir().join("globalenv_retry_write");
+        let _ = fs::remove_file(&file);
+        write(&file, "export A=1\n").unwrap();
+        write(&file, "export A=2\n").unwrap();
+        assert_eq!(fs::read_to_string(&file).unwrap(), "export A=2\n");
+        assert!(!temp_path(&file).exists());
+        fs::remove_file(&file).unwrap();
+    }
+}
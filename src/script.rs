@@ -0,0 +1,228 @@
+//! Generation of scripts applying the managed variables, for use on another machine.
+
+use crate::{env_vars_in, syntax::quote_powershell, EnvError, EnvVar, Filter, Scope, Shell, Target};
+use std::{fmt, str::FromStr};
+
+/// Kinds of scripts [`render_script`] generates, and of documents `export_as` generates: rendering those
+/// requires the `serde` feature, without it they fail with [`EnvError::UnsupportedPlatform`].
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Format {
+    /// Script to be sourced by the shell, setting the variables in the current session
+    Shell(Shell),
+    /// `.cmd` file of `setx` commands, persisting the variables in the registry
+    Batch,
+    /// PowerShell script of `[Environment]::SetEnvironmentVariable` calls, persisting the variables in the registry
+    PowerShell,
+    /// JSON document of the variables and their metadata
+    Json,
+    /// TOML document of the variables and their metadata
+    Toml,
+    /// YAML document of the variables and their metadata
+    Yaml,
+}
+
+impl From<Shell> for Format {
+    fn from(shell: Shell) -> Format {
+        Format::Shell(shell)
+    }
+}
+
+impl fmt::Display for Format {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Format::Shell(shell) => shell.fmt(f),
+            Format::Batch => f.write_str("batch"),
+            Format::PowerShell => f.write_str("powershell-apply"),
+            Format::Json => f.write_str("json"),
+            Format::Toml => f.write_str("toml"),
+            Format::Yaml => f.write_str("yaml"),
+        }
+    }
+}
+
+impl FromStr for Format {
+    type Err = EnvError;
+
+    fn from_str(s: &str) -> Result<Format, EnvError> {
+        match s {
+            "batch" | "cmd" => Ok(Format::Batch),
+            "powershell-apply" => Ok(Format::PowerShell),
+            "json" => Ok(Format::Json),
+            "toml" => Ok(Format::Toml),
+            "yaml" | "yml" => Ok(Format::Yaml),
+            s => Ok(Format::Shell(s.parse()?)),
+        }
+    }
+}
+
+/// Renders the globally managed variables as a script: `export` lines for bash and zsh, `setenv`
+/// for tcsh, `set -gx` for fish, `$env:` assignments for PowerShell, or commands persisting them
+/// (`setx`, `[Environment]::SetEnvironmentVariable`) which can be reviewed before being run.
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{render_script, Format, Shell};
+/// std::fs::write("env.sh", render_script(Shell::Bash).unwrap()).unwrap();
+/// std::fs::write("env.cmd", render_script(Format::Batch).unwrap()).unwrap();
+/// ```
+pub fn render_script<F: Into<Format>>(format: F) -> Result<String, EnvError> {
+    render_script_in(&Target::default(), format)
+}
+
+/// Renders the variables managed in the given target as a script. Batch and PowerShell scripts
+/// persist the variables in the scope of the target, the user or system one (they fail with
+/// [`EnvError::UnsupportedPlatform`] for the others), documents (JSON...) list the managed variables.
+pub fn render_script_in<F: Into<Format>>(target: &Target, format: F) -> Result<String, EnvError> {
+    render_as(&env_vars_in(target, Filter::Managed)?, target.get_scope(), format.into())
+}
+
+/// Renders the variables of a target (or of a snapshot of it) in the format: what both [`render_script`] and
+/// `export_as` generate.
+pub(crate) fn render_as(vars: &[EnvVar], scope: Scope, format: Format) -> Result<String, EnvError> {
+    let pairs: Vec<(String, String)> = vars.iter().map(|var| (var.name.clone(), var.value.clone())).collect();
+    Ok(match format {
+        Format::Shell(shell) => render(&pairs, shell),
+        Format::Batch => render_batch(&pairs, scope)?,
+        Format::PowerShell => render_powershell(&pairs, scope)?,
+        #[cfg(feature = "serde")]
+        Format::Json => crate::structured::render(vars, crate::structured::DocumentFormat::Json)?,
+        #[cfg(feature = "serde")]
+        Format::Toml => crate::structured::render(vars, crate::structured::DocumentFormat::Toml)?,
+        #[cfg(feature = "serde")]
+        Format::Yaml => crate::structured::render(vars, crate::structured::DocumentFormat::Yaml)?,
+        #[cfg(not(feature = "serde"))]
+        Format::Json | Format::Toml | Format::Yaml => return Err(EnvError::UnsupportedPlatform),
+    })
+}
+
+pub(crate) fn render(vars: &[(String, String)], shell: Shell) -> String {
+    let mut script = String::from("# Generated by globalenv\n");
+    for (name, value) in vars {
+        script.push_str(&shell.syntax().render(name, value));
+        script.push('\n');
+    }
+    script
+}
+
+/// Whether the script persists the variables for the machine (or the user): setx and
+/// `SetEnvironmentVariable` have no other scopes.
+fn machine(scope: Scope) -> Result<bool, EnvError> {
+    match scope {
+        Scope::User => Ok(false),
+        Scope::System => Ok(true),
+        Scope::Service | Scope::Project(_) => Err(EnvError::UnsupportedPlatform),
+    }
+}
+
+/// setx can't store newlines, nor values longer than 1024 characters.
+fn render_batch(vars: &[(String, String)], scope: Scope) -> Result<String, EnvError> {
+    let mut script = String::from("@echo off\r\nrem Generated by globalenv\r\n");
+    let machine = if machine(scope)? { " /M" } else { "" };
+    for (name, value) in vars {
+        if value.contains(['\n', '\r']) || value.chars().count() > 1024 {
+            script.push_str(&format!("rem {} skipped: setx can't store its value\r\n", name));
+        } else {
+            script.push_str(&format!("setx {} {}{} >nul\r\n", name, quote_cmd(value), machine));
+        }
+    }
+    Ok(script)
+}
+
+/// Double quotes for setx, with `\"` escapes, `%%` for the batch file and `^` before the cmd
+/// metacharacters which those escaped quotes leave unquoted.
+fn quote_cmd(value: &str) -> String {
+    let mut quoted = String::from("\"");
+    let mut cmd_quoted = true;
+    let mut backslashes = 0;
+    for c in value.chars() {
+        match c {
+            '\\' => {
+                backslashes += 1;
+                quoted.push(c);
+                continue;
+            }
+            '"' => {
+                // Backslashes before a quote are escapes for setx
+                quoted.push_str(&"\\".repeat(backslashes + 1));
+                quoted.push('"');
+                cmd_quoted = !cmd_quoted;
+            }
+            '%' => quoted.push_str("%%"),
+            '^' | '&' | '|' | '<' | '>' | '(' | ')' if !cmd_quoted => {
+                quoted.push('^');
+                quoted.push(c);
+            }
+            c => quoted.push(c),
+        }
+        backslashes = 0;
+    }
+    quoted.push_str(&"\\".repeat(backslashes));
+    quoted.push('"');
+    quoted
+}
+
+fn render_powershell(vars: &[(String, String)], scope: Scope) -> Result<String, EnvError> {
+    let mut script = String::from("# Generated by globalenv\n");
+    let scope = if machine(scope)? { "Machine" } else { "User" };
+    for (name, value) in vars {
+        script.push_str(&format!(
+            "[Environment]::SetEnvironmentVariable({}, {}, '{}')\n",
+            quote_powershell(name),
+            quote_powershell(value),
+            scope
+        ));
+    }
+    Ok(script)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_each_shell() {
+        let vars = vec![(String::from("A"), String::from("1")), (String::from("B"), String::from("x y"))];
+        assert_eq!(render(&vars, Shell::Bash), "# Generated by globalenv\nexport A=1\nexport B='x y'\n");
+        assert_eq!(render(&vars, Shell::Tcsh), "# Generated by globalenv\nsetenv A 1\nsetenv B 'x y'\n");
+        assert_eq!(render(&vars, Shell::Fish), "# Generated by globalenv\nset -gx A 1\nset -gx B 'x y'\n");
+        assert_eq!(render(&vars, Shell::PowerShell), "# Generated by globalenv\n$env:A = '1'\n$env:B = 'x y'\n");
+    }
+
+    #[test]
+    fn parses_document_formats() {
+        assert_eq!("yml".parse::<Format>(), Ok(Format::Yaml));
+        assert_eq!(Format::Json.to_string(), "json");
+        #[cfg(not(feature = "serde"))]
+        assert_eq!(render_as(&[], Scope::User, Format::Toml), Err(EnvError::UnsupportedPlatform));
+    }
+
+    #[test]
+    fn renders_setx_commands() {
+        let vars = vec![
+            (String::from("DIR"), String::from(r"C:\Program Files\")),
+            (String::from("ODD"), String::from(r#"100% "a&b""#)),
+            (String::from("LINES"), String::from("a\nb")),
+        ];
+        assert_eq!(
+            render_batch(&vars, Scope::System).unwrap(),
+            "@echo off\r\nrem Generated by globalenv\r\n\
+             setx DIR \"C:\\Program Files\\\\\" /M >nul\r\n\
+             setx ODD \"100%% \\\"a^&b\\\"\" /M >nul\r\n\
+             rem LINES skipped: setx can't store its value\r\n"
+        );
+        assert_eq!(render_batch(&vars, Scope::Service), Err(EnvError::UnsupportedPlatform));
+        assert_eq!(render_batch(&vars, Scope::Project(std::path::PathBuf::from("/src/app"))), Err(EnvError::UnsupportedPlatform));
+    }
+
+    #[test]
+    fn renders_set_environment_variable_calls() {
+        let vars = vec![(String::from("A"), String::from("it's")), (String::from("B"), String::from("a\nb"))];
+        assert_eq!(
+            render_powershell(&vars, Scope::User).unwrap(),
+            "# Generated by globalenv\n\
+             [Environment]::SetEnvironmentVariable('A', 'it''s', 'User')\n\
+             [Environment]::SetEnvironmentVariable('B', \"a`nb\", 'User')\n"
+        );
+        assert_eq!(render_powershell(&vars, Scope::Service), Err(EnvError::UnsupportedPlatform));
+    }
+}
@@ -0,0 +1,81 @@
+//! Secrets stored in the OS keychain, only a reference to them being persisted.
+
+use crate::{get_var_in, set_var_in, unset_var_in, EnvError, Target};
+use keyring::Entry;
+
+const SERVICE: &str = "globalenv";
+const REFERENCE_PREFIX: &str = "keyring:globalenv/";
+
+fn entry(var: &str) -> Result<Entry, EnvError> {
+    Entry::new(SERVICE, var).map_err(|_| EnvError::IOError)
+}
+
+/// Stores the value in the OS keychain (Windows Credential Manager, macOS Keychain, Secret Service)
+/// and sets globally a `keyring:globalenv/<VAR>` reference to it, which [`get_secret`] resolves.
+/// The current process gets the actual value.
+/// Example:
+/// ```rust,no_run
+/// globalenv::set_secret("API_TOKEN", "s3cr3t").unwrap();
+/// assert_eq!(globalenv::get_secret("API_TOKEN").unwrap().as_deref(), Some("s3cr3t"));
+/// ```
+pub fn set_secret(var: &str, value: &str) -> Result<(), EnvError> {
+    set_secret_in(&Target::default(), var, value)
+}
+
+/// Stores the value in the OS keychain and sets a reference to it in the given target.
+pub fn set_secret_in(target: &Target, var: &str, value: &str) -> Result<(), EnvError> {
+    // Nothing is left in the keychain for a name the target rejects
+    target.check(var)?;
+    entry(var)?.set_password(value).map_err(|_| EnvError::IOError)?;
+    set_var_in(target, var, &format!("{}{}", REFERENCE_PREFIX, var))?;
+    std::env::set_var(var, value);
+    Ok(())
+}
+
+/// Gets the globally persisted value of a variable, resolving it from the keychain if it is a secret.
+pub fn get_secret(var: &str) -> Result<Option<String>, EnvError> {
+    get_secret_in(&Target::default(), var)
+}
+
+/// Gets the value of a variable persisted in the given target, resolving it from the keychain if it is a secret.
+pub fn get_secret_in(target: &Target, var: &str) -> Result<Option<String>, EnvError> {
+    let value = match get_var_in(target, var)? {
+        Some(value) => value,
+        None => return Ok(None),
+    };
+    match value.strip_prefix(REFERENCE_PREFIX) {
+        Some(name) => match entry(name)?.get_password() {
+            Ok(secret) => Ok(Some(secret)),
+            Err(keyring::Error::NoEntry) => Ok(None),
+            Err(_) => Err(EnvError::IOError),
+        },
+        None => Ok(Some(value)),
+    }
+}
+
+/// Unsets a variable globally, and deletes its secret from the keychain.
+pub fn unset_secret(var: &str) -> Result<(), EnvError> {
+    unset_secret_in(&Target::default(), var)
+}
+
+/// Unsets a variable from the given target, and deletes its secret from the keychain.
+pub fn unset_secret_in(target: &Target, var: &str) -> Result<(), EnvError> {
+    target.check(var)?;
+    match entry(var)?.delete_credential() {
+        Ok(()) | Err(keyring::Error::NoEntry) => (),
+        Err(_) => return Err(EnvError::IOError),
+    }
+    unset_var_in(target, var)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checks_names_before_the_keychain() {
+        let target = Target::new().policy(crate::Policy::new().deny(["DENIED_*"]));
+        assert_eq!(set_secret_in(&target, "DENIED_TOKEN", "s3cr3t"), Err(EnvError::PolicyError));
+        assert_eq!(unset_secret_in(&target, "DENIED_TOKEN"), Err(EnvError::PolicyError));
+    }
+}
@@ -0,0 +1,63 @@
+//! Environment of a Windows service: the `Environment` value of its registry key, which the Service Control
+//! Manager adds to the environment of the service's process.
+
+use crate::{registry, target::invalid_name, EnvError};
+
+/// Entries with the variable set (or removed), and whether they changed. Names are compared ignoring case.
+fn with_entry(entries: &[String], var: &str, value: Option<&str>) -> (Vec<String>, bool) {
+    let defines = |entry: &String| entry.split_once('=').is_some_and(|(name, _)| name.eq_ignore_ascii_case(var));
+    let mut updated: Vec<String> = entries.iter().filter(|e| !defines(e)).cloned().collect();
+    if let Some(value) = value {
+        updated.push(format!("{}={}", var, value));
+    }
+    let changed = updated.len() != entries.len() || value.is_some_and(|value| !entries.contains(&format!("{}={}", var, value)));
+    (updated, changed)
+}
+
+/// Sets a variable in the environment of a Windows service, which it gets when it is (re)started. The service
+/// must exist, and changing it requires administrator rights.
+/// Example:
+/// ```rust,no_run
+/// globalenv::set_service_var("MyAgent", "AGENT_LOG", "debug").unwrap();
+/// ```
+pub fn set_service_var(service: &str, var: &str, value: &str) -> Result<(), EnvError> {
+    if let Some(reason) = invalid_name(var, true) {
+        return Err(EnvError::InvalidName(String::from(reason)));
+    }
+    if value.contains('\0') {
+        return Err(EnvError::VarError);
+    }
+    let (entries, changed) = with_entry(&registry::service_environment(service)?, var, Some(value));
+    if changed {
+        registry::set_service_environment(service, &entries)?;
+    }
+    Ok(())
+}
+
+/// Unsets a variable from the environment of a Windows service, returning whether it was set.
+pub fn unset_service_var(service: &str, var: &str) -> Result<bool, EnvError> {
+    let (entries, changed) = with_entry(&registry::service_environment(service)?, var, None);
+    if changed {
+        registry::set_service_environment(service, &entries)?;
+    }
+    Ok(changed)
+}
+
+/// Variables of the environment of a Windows service.
+pub fn service_vars(service: &str) -> Result<Vec<(String, String)>, EnvError> {
+    let entries = registry::service_environment(service)?;
+    Ok(entries.iter().filter_map(|e| e.split_once('=')).map(|(name, value)| (name.to_string(), value.to_string())).collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn updates_entries() {
+        let entries = vec![String::from("A=1"), String::from("Path=C:\\bin")];
+        assert_eq!(with_entry(&entries, "a", Some("2")), (vec![String::from("Path=C:\\bin"), String::from("a=2")], true));
+        assert_eq!(with_entry(&entries, "Path", None), (vec![String::from("A=1")], true));
+        assert!(!with_entry(&entries, "B", None).1);
+    }
+}
@@ -0,0 +1,404 @@
+//! Session handle batching operations on a target: the storage is resolved and read once, and written
+//! back once.
+
+#[cfg(target_os = "windows")]
+use crate::registry;
+use crate::{
+    envfile,
+    meta::Meta,
+    plan::{Action, Change, Plan, REDACTED},
+    syntax::Syntax,
+    target::Store,
+    tmux, EnvError, Policy, Scope, SetOptions, Shell, Target,
+};
+use std::{
+    env,
+    hash::{DefaultHasher, Hash, Hasher},
+    path::{Path, PathBuf},
+};
+
+/// Storage as it will be once flushed.
+#[derive(Debug, Clone)]
+enum State {
+    /// Content of the file, whether it differs from the file's, and the content and stamp of the file when it
+    /// was read (or last written)
+    File { path: PathBuf, syntax: Syntax, content: String, changed: bool, read: String, stamp: Stamp },
+    /// Values to write (`None` to delete), with their metadata, and the last write time of the key when opened
+    #[cfg(target_os = "windows")]
+    Registry { scope: Scope, changes: Vec<(String, Option<(String, Meta)>)>, stamp: u64 },
+}
+
+/// What the file was when read: another process changed it if its content differs. The modification time
+/// can't tell, as it only changes every few milliseconds, and changes when a file is saved as it was.
+#[derive(Debug, PartialEq, Eq, Clone)]
+struct Stamp {
+    hash: u64,
+}
+
+impl Stamp {
+    fn of(content: &str) -> Stamp {
+        let mut hasher = DefaultHasher::new();
+        content.hash(&mut hasher);
+        Stamp { hash: hasher.finish() }
+    }
+
+    /// Whether the file is still the one the stamp was taken of.
+    fn matches(&self, path: &Path) -> Result<bool, EnvError> {
+        Ok(Stamp::of(&envfile::read(path)?) == *self)
+    }
+}
+
+/// Handle on a target for many operations, e.g. by an installer setting dozens of variables: shell
+/// detection and reading the file happen once, in [`GlobalEnv::open`], and changes are only written by
+/// [`GlobalEnv::flush`] (the current process environment is updated at the same time). Changes which are
+/// not flushed are discarded. If another process changed the storage in the meantime, the flush fails with
+/// [`EnvError::Conflict`] instead of overwriting its change.
+/// Example:
+/// ```rust
+/// use globalenv::{GlobalEnv, Target};
+/// let file = std::env::temp_dir().join("globalenv_session_doctest");
+/// std::fs::write(&file, "").unwrap();
+/// let mut session = GlobalEnv::open_in(&Target::new().file(&file)).unwrap();
+/// for i in 0..20 {
+///     session.set(&format!("APP_VAR_{}", i), "value").unwrap();
+/// }
+/// session.unset("APP_VAR_0").unwrap();
+/// assert_eq!(session.get("APP_VAR_1").unwrap().as_deref(), Some("value"));
+/// session.flush().unwrap();
+/// assert_eq!(globalenv::managed_vars_in(&Target::new().file(&file)).unwrap().len(), 19);
+/// ```
+#[derive(Debug, Clone)]
+pub struct GlobalEnv {
+    target: Target,
+    state: State,
+    /// Process environment changes, applied when flushed
+    pending: Vec<(String, Option<String>)>,
+}
+
+/// Builder of a [`GlobalEnv`](struct@GlobalEnv) handle, see [`GlobalEnv::builder`].
+#[derive(Debug, Clone, Default)]
+pub struct GlobalEnvBuilder {
+    target: Target,
+}
+
+impl GlobalEnvBuilder {
+    pub fn scope(mut self, scope: Scope) -> GlobalEnvBuilder {
+        self.target = self.target.scope(scope);
+        self
+    }
+
+    pub fn shell(mut self, shell: Shell) -> GlobalEnvBuilder {
+        self.target = self.target.shell(shell);
+        self
+    }
+
+    pub fn file<P: Into<PathBuf>>(mut self, file: P) -> GlobalEnvBuilder {
+        self.target = self.target.file(file);
+        self
+    }
+
+    pub fn tmux(mut self, tmux: bool) -> GlobalEnvBuilder {
+        self.target = self.target.tmux(tmux);
+        self
+    }
+
+    pub fn policy(mut self, policy: Policy) -> GlobalEnvBuilder {
+        self.target = self.target.policy(policy);
+        self
+    }
+
+    /// Resolves the storage (detecting the shell if none was given) and reads it.
+    pub fn build(self) -> Result<GlobalEnv, EnvError> {
+        GlobalEnv::open_in(&self.target)
+    }
+}
+
+impl GlobalEnv {
+    /// Builder of a handle, as an alternative to a [`Target`] given to [`GlobalEnv::open_in`].
+    /// Example:
+    /// ```rust,no_run
+    /// use globalenv::{GlobalEnv, Scope, Shell};
+    /// let mut env = GlobalEnv::builder().shell(Shell::Zsh).scope(Scope::User).build().unwrap();
+    /// env.set("EDITOR", "vim").unwrap();
+    /// env.unset("VISUAL").unwrap();
+    /// env.flush().unwrap();
+    /// ```
+    pub fn builder() -> GlobalEnvBuilder {
+        GlobalEnvBuilder::default()
+    }
+
+    /// Opens the default target.
+    pub fn open() -> Result<GlobalEnv, EnvError> {
+        GlobalEnv::open_in(&Target::default())
+    }
+
+    /// Opens the given target.
+    pub fn open_in(target: &Target) -> Result<GlobalEnv, EnvError> {
+        let state = match target.store()? {
+            Store::File(path, syntax) => {
+                let content = envfile::read(&path)?;
+                let stamp = Stamp::of(&content);
+                State::File { path, syntax, read: content.clone(), content, changed: false, stamp }
+            }
+            #[cfg(target_os = "windows")]
+            Store::Registry(scope) => State::Registry { stamp: registry::stamp(&scope)?, scope, changes: Vec::new() },
+        };
+        Ok(GlobalEnv { target: target.clone(), state, pending: Vec::new() })
+    }
+
+    /// Value of the variable, including the changes not flushed yet.
+    pub fn get(&self, var: &str) -> Result<Option<String>, EnvError> {
+        match &self.state {
+            State::File { syntax, content, .. } => Ok(envfile::get_from(content, *syntax, var)),
+            #[cfg(target_os = "windows")]
+            State::Registry { scope, changes, .. } => match changes.iter().rev().find(|(name, _)| crate::target::same_name(name, var)) {
+                Some((_, change)) => Ok(change.as_ref().map(|(value, _)| value.clone())),
+                None => registry::get(scope, var),
+            },
+        }
+    }
+
+    fn meta(&self, var: &str) -> Result<Meta, EnvError> {
+        match &self.state {
+            State::File { syntax, content, .. } => Ok(envfile::meta_content(content, *syntax, var)),
+            #[cfg(target_os = "windows")]
+            State::Registry { scope, changes, .. } => match changes.iter().rev().find(|(name, _)| crate::target::same_name(name, var)) {
+                Some((_, change)) => Ok(change.as_ref().map(|(_, meta)| meta.clone()).unwrap_or_default()),
+                None => registry::meta(scope, var),
+            },
+        }
+    }
+
+    /// Value and metadata of the variable as read, the storage flush checks is unchanged. The registry is read
+    /// as it is: flush checks it wasn't written since it was opened.
+    fn read(&self, var: &str) -> Result<(Option<String>, Meta), EnvError> {
+        match &self.state {
+            State::File { syntax, read, .. } => Ok((envfile::get_from(read, *syntax, var), envfile::meta_content(read, *syntax, var))),
+            #[cfg(target_os = "windows")]
+            State::Registry { scope, .. } => Ok((registry::get(scope, var)?, registry::meta(scope, var)?)),
+        }
+    }
+
+    /// Sets a variable.
+    pub fn set(&mut self, var: &str, value: &str) -> Result<(), EnvError> {
+        self.set_with(var, value, &SetOptions::default())
+    }
+
+    /// Sets a variable with options.
+    pub fn set_with(&mut self, var: &str, value: &str, options: &SetOptions) -> Result<(), EnvError> {
+        self.target.check(var)?;
+        let value = &*options.value(&self.target, value)?;
+        options.validate(var, value)?;
+        let changed = self.get(var)?.as_deref() != Some(value);
+        let meta = options.meta(&self.meta(var)?, changed);
+        let syntax = match &self.state {
+            State::File { syntax, .. } => Some(*syntax),
+            #[cfg(target_os = "windows")]
+            State::Registry { .. } => None,
+        };
+        let applies = crate::condition::applies(&meta, syntax)?;
+        // The registry has no guards: a conditional variable is only set where the condition holds
+        if !applies && syntax.is_none() {
+            return Ok(());
+        }
+        match &mut self.state {
+            State::File { syntax, content, changed, .. } => {
+                if let Some(new) = envfile::set_content(content, *syntax, var, value, &meta) {
+                    *content = new;
+                    *changed = true;
+                }
+            }
+            #[cfg(target_os = "windows")]
+            State::Registry { changes, .. } => changes.push((var.to_string(), Some((value.to_string(), meta)))),
+        }
+        if applies {
+            self.pending.push((var.to_string(), Some(value.to_string())));
+        }
+        Ok(())
+    }
+
+    /// Unsets a variable.
+    pub fn unset(&mut self, var: &str) -> Result<(), EnvError> {
+        self.target.check(var)?;
+        if self.get(var)?.is_none() {
+            return Ok(());
+        }
+        match &mut self.state {
+            State::File { syntax, content, changed, .. } => {
+                if let Some(new) = envfile::unset_content(content, *syntax, var) {
+                    *content = new;
+                    *changed = true;
+                }
+            }
+            #[cfg(target_os = "windows")]
+            State::Registry { changes, .. } => changes.push((var.to_string(), None)),
+        }
+        self.pending.push((var.to_string(), None));
+        Ok(())
+    }
+
+    /// The changes which [`GlobalEnv::flush`] would write, with the values before and after them (redacted for
+    /// the secret variables), e.g. for a configuration management tool to show them for approval before
+    /// [`Plan::apply`] writes them.
+    /// Example:
+    /// ```rust
+    /// use globalenv::{Action, GlobalEnv, Target};
+    /// let file = std::env::temp_dir().join("globalenv_plan_doctest");
+    /// std::fs::write(&file, "export EDITOR=vi\n").unwrap();
+    /// let mut session = GlobalEnv::open_in(&Target::new().file(&file)).unwrap();
+    /// session.set("EDITOR", "vim").unwrap();
+    /// let plan = session.plan().unwrap();
+    /// assert_eq!(plan.changes[0].action, Action::Set);
+    /// assert_eq!((plan.changes[0].before.as_deref(), plan.changes[0].after.as_deref()), (Some("vi"), Some("vim")));
+    /// ```
+    pub fn plan(&self) -> Result<Plan, EnvError> {
+        let location = self.target.location()?;
+        let mut changes: Vec<Change> = Vec::new();
+        for (var, _) in &self.pending {
+            if changes.iter().any(|change| change.name == *var) {
+                continue;
+            }
+            let ((before, read_meta), after) = (self.read(var)?, self.get(var)?);
+            if before == after {
+                continue;
+            }
+            let secret = self.meta(var)?.secret || read_meta.secret;
+            let redact = |value: Option<String>| value.map(|value| if secret { String::from(REDACTED) } else { value });
+            changes.push(Change {
+                action: if after.is_some() { Action::Set } else { Action::Unset },
+                target: location.clone(),
+                name: var.clone(),
+                before: redact(before),
+                after: redact(after),
+                secret,
+            });
+        }
+        Ok(Plan::new(changes, self.clone()))
+    }
+
+    /// Writes the changes, then applies them to the current process (and tmux, if the target says so). Fails
+    /// with [`EnvError::Conflict`], writing nothing, if the file or registry key was changed since it was read,
+    /// and with [`EnvError::NotApproved`], once everything is applied, if direnv didn't approve the `.envrc`
+    /// written (see [`Target::direnv`]).
+    pub fn flush(&mut self) -> Result<(), EnvError> {
+        let mut approval = None;
+        match &mut self.state {
+            State::File { path, content, changed, read, stamp, .. } => {
+                if *changed {
+                    if !stamp.matches(path)? {
+                        return Err(EnvError::Conflict);
+                    }
+                    crate::config::backup(path)?;
+                    crate::retry::write(path, content.as_bytes())?;
+                    *stamp = Stamp::of(content);
+                    read.clone_from(content);
+                    *changed = false;
+                    approval = crate::direnv::approve(&self.target, path);
+                }
+            }
+            #[cfg(target_os = "windows")]
+            State::Registry { scope, changes, stamp } if !changes.is_empty() => {
+                if registry::stamp(scope)? != *stamp {
+                    return Err(EnvError::Conflict);
+                }
+                for (var, change) in changes.drain(..) {
+                    match change {
+                        Some((value, meta)) => registry::set(scope, &var, &value, &meta)?,
+                        None => {
+                            registry::unset(scope, &var)?;
+                        }
+                    }
+                }
+                *stamp = registry::stamp(scope)?;
+            }
+            #[cfg(target_os = "windows")]
+            State::Registry { .. } => {}
+        }
+        self.apply()?;
+        crate::direnv::approved(approval)
+    }
+
+    /// Applies the flushed changes to the current process, and tmux.
+    fn apply(&mut self) -> Result<(), EnvError> {
+        for (var, value) in self.pending.drain(..) {
+            #[cfg(feature = "kv")]
+            crate::kv::mirror(&var, value.as_deref())?;
+            match &value {
+                Some(value) => env::set_var(&var, value),
+                None => env::remove_var(&var),
+            }
+            if self.target.get_tmux() {
+                tmux::set_environment(&var, value.as_deref());
+            }
+        }
+        Ok(())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    #[test]
+    fn refuses_concurrent_changes() {
+        let file = env::temp_dir().join("globalenv_session_conflict");
+        fs::write(&file, "export A=1\n").unwrap();
+        let mut session = GlobalEnv::builder().file(&file).shell(Shell::Bash).build().unwrap();
+        session.set("GLOBALENV_SESSION_B", "2").unwrap();
+        fs::write(&file, "export A=1\n").unwrap();
+        session.flush().unwrap();
+        session.set("GLOBALENV_SESSION_C", "3").unwrap();
+        fs::write(&file, "export A=2\n").unwrap();
+        assert_eq!(session.flush(), Err(EnvError::Conflict));
+        assert_eq!(fs::read_to_string(&file).unwrap(), "export A=2\n");
+        fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn plans_changes() {
+        let file = env::temp_dir().join("globalenv_session_plan");
+        fs::write(&file, "export A=1\nexport B=2\n").unwrap();
+        let mut session = GlobalEnv::builder().file(&file).shell(Shell::Bash).build().unwrap();
+        session.set("A", "1").unwrap();
+        session.unset("B").unwrap();
+        session.set_with("TOKEN", "hunter2", &SetOptions::new().secret(true)).unwrap();
+        let plan = session.plan().unwrap();
+        assert_eq!(plan.changes.iter().map(|c| (c.action, c.name.as_str())).collect::<Vec<_>>(), [(Action::Unset, "B"), (Action::Set, "TOKEN")]);
+        assert_eq!((plan.changes[0].before.as_deref(), plan.changes[0].after.as_deref()), (Some("2"), None));
+        assert_eq!(plan.changes[1].after.as_deref(), Some(REDACTED));
+        #[cfg(feature = "serde")]
+        assert!(plan.to_json().starts_with("{\n  \"version\": 1,\n  \"changes\": [\n    {\n      \"action\": \"unset\""));
+        session.flush().unwrap();
+        assert!(session.plan().unwrap().is_empty());
+        fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn applies_plans_unless_changed() {
+        let file = env::temp_dir().join("globalenv_session_apply");
+        fs::write(&file, "export A=1\n").unwrap();
+        let mut session = GlobalEnv::open_in(&Target::new().file(&file).shell(Shell::Bash)).unwrap();
+        session.set("A", "2").unwrap();
+        let plan = session.plan().unwrap();
+        plan.apply().unwrap();
+        assert_eq!(crate::get_var_in(&Target::new().file(&file).shell(Shell::Bash), "A").unwrap().as_deref(), Some("2"));
+        // Applied, the file is no longer the one it was planned on
+        assert_eq!(plan.apply(), Err(EnvError::Conflict));
+        assert_eq!(session.flush(), Err(EnvError::Conflict));
+        fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn plans_from_what_was_read() {
+        let file = env::temp_dir().join("globalenv_session_plan_read");
+        fs::write(&file, "export A=1\n").unwrap();
+        let mut session = GlobalEnv::open_in(&Target::new().file(&file).shell(Shell::Bash)).unwrap();
+        session.set("A", "2").unwrap();
+        fs::write(&file, "export A=2\n").unwrap();
+        let plan = session.plan().unwrap();
+        assert_eq!((plan.changes[0].before.as_deref(), plan.changes[0].after.as_deref()), (Some("1"), Some("2")));
+        assert_eq!(plan.apply(), Err(EnvError::Conflict));
+        fs::remove_file(&file).unwrap();
+    }
+}
@@ -0,0 +1,115 @@
+//! Snapshots: the variables persisted in a target at some point, to store (with the `serde` feature) and compare
+//! with a later one.
+
+use crate::{
+    plan::{Action, Change, REDACTED},
+    script, vars_in, EnvError, EnvVar, Format, Scope, Target,
+};
+use std::time::SystemTime;
+
+/// The variables persisted in a target when the snapshot was taken, with their metadata.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct EnvSnapshot {
+    /// File or registry key the variables were read from (see [`Target::location`])
+    pub target: String,
+    pub taken_at: SystemTime,
+    pub vars: Vec<EnvVar>,
+}
+
+impl EnvSnapshot {
+    /// The changes from this snapshot to a later one, in the order of the later variables then of the removed ones:
+    /// the values of the variables secret in either snapshot are redacted, as in a [`Plan`](crate::Plan).
+    pub fn diff(&self, later: &EnvSnapshot) -> Vec<Change> {
+        let find = |snapshot: &EnvSnapshot, name: &str| snapshot.vars.iter().find(|var| var.name == name).cloned();
+        let change = |action, before: Option<EnvVar>, after: Option<EnvVar>| {
+            let secret = before.iter().chain(&after).any(|var| var.secret);
+            let value = |var: Option<EnvVar>| var.map(|var| if secret { String::from(REDACTED) } else { var.value });
+            let name = before.as_ref().or(after.as_ref()).map(|var| var.name.clone()).unwrap_or_default();
+            Change { action, target: later.target.clone(), name, before: value(before), after: value(after), secret }
+        };
+        let mut changes = Vec::new();
+        for var in &later.vars {
+            match find(self, &var.name) {
+                Some(old) if old.value == var.value => (),
+                old => changes.push(change(Action::Set, old, Some(var.clone()))),
+            }
+        }
+        for var in self.vars.iter().filter(|var| find(later, &var.name).is_none()) {
+            changes.push(change(Action::Unset, Some(var.clone()), None));
+        }
+        changes
+    }
+
+    /// Renders the variables of the snapshot as a script, or with the `serde` feature as the JSON, TOML or YAML
+    /// document `export_as` renders for a target, which `import_from` restores.
+    /// Example:
+    /// ```rust,no_run
+    /// use globalenv::{snapshot, Shell};
+    /// std::fs::write("before-install.sh", snapshot().unwrap().export_as(Shell::Bash).unwrap()).unwrap();
+    /// ```
+    pub fn export_as<F: Into<Format>>(&self, format: F) -> Result<String, EnvError> {
+        // The variables of a snapshot all come from the same target
+        let scope = self.vars.first().map_or(Scope::User, |var| var.scope.clone());
+        script::render_as(&self.vars, scope, format.into())
+    }
+}
+
+/// Takes a snapshot of the globally persisted variables, e.g. before an installer runs, to review what it
+/// changed afterwards.
+/// Example:
+/// ```rust,no_run
+/// let before = globalenv::snapshot().unwrap();
+/// globalenv::set_var("EDITOR", "vim").unwrap();
+/// for change in before.diff(&globalenv::snapshot().unwrap()) {
+///     println!("{:?} {}: {:?} -> {:?}", change.action, change.name, change.before, change.after);
+/// }
+/// ```
+pub fn snapshot() -> Result<EnvSnapshot, EnvError> {
+    snapshot_in(&Target::default())
+}
+
+/// Takes a snapshot of the variables persisted in the given target.
+pub fn snapshot_in(target: &Target) -> Result<EnvSnapshot, EnvError> {
+    Ok(EnvSnapshot { target: target.location()?, taken_at: SystemTime::now(), vars: vars_in(target)? })
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn diffs_snapshots() {
+        let file = std::env::temp_dir().join("globalenv_snapshot");
+        std::fs::write(&file, "export KEPT=1\nexport CHANGED=old\nexport REMOVED=1\n").unwrap();
+        let target = Target::new().file(&file);
+        let before = snapshot_in(&target).unwrap();
+        std::fs::write(&file, "export KEPT=1\nexport CHANGED=new\nexport ADDED=1\n").unwrap();
+        let changes = before.diff(&snapshot_in(&target).unwrap());
+        let summary: Vec<_> = changes.iter().map(|c| (c.action, c.name.as_str(), c.before.as_deref(), c.after.as_deref())).collect();
+        assert_eq!(summary, [
+            (Action::Set, "CHANGED", Some("old"), Some("new")),
+            (Action::Set, "ADDED", None, Some("1")),
+            (Action::Unset, "REMOVED", Some("1"), None),
+        ]);
+        assert!(before.diff(&before).is_empty());
+        assert!(before.export_as(crate::Shell::Bash).unwrap().ends_with("export CHANGED=old\nexport REMOVED=1\n"));
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn debug_redacts_secrets() {
+        let var = |value: &str, secret| EnvVar {
+            name: String::from("TOKEN"),
+            value: String::from(value),
+            scope: Scope::User,
+            source: String::from("/home/me/.bashrc"),
+            secret,
+            modified_at: None,
+        };
+        let snapshot = EnvSnapshot { target: String::from("/home/me/.bashrc"), taken_at: SystemTime::UNIX_EPOCH, vars: vec![var("hunter2", true)] };
+        let debug = format!("{:?}", snapshot);
+        assert!(debug.contains(&format!("value: \"{}\"", REDACTED)) && !debug.contains("hunter2"));
+        assert!(format!("{:?}", var("public", false)).contains("value: \"public\""));
+    }
+}
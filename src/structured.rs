@@ -0,0 +1,213 @@
+//! Export of the persisted variables as JSON, TOML or YAML documents, for inventory and backup tools, and import
+//! of such documents.
+
+use crate::{env_vars_in, script, EnvError, EnvVar, Filter, Format, GlobalEnv, ImportOptions, ImportOutcome, ImportResult, Scope, Target};
+use std::{fs, path::Path};
+
+/// The formats of documents, the others being scripts.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum DocumentFormat {
+    Json,
+    Toml,
+    Yaml,
+}
+
+impl DocumentFormat {
+    fn of(format: Format) -> Option<DocumentFormat> {
+        match format {
+            Format::Json => Some(DocumentFormat::Json),
+            Format::Toml => Some(DocumentFormat::Toml),
+            Format::Yaml => Some(DocumentFormat::Yaml),
+            _ => None,
+        }
+    }
+}
+
+/// The document: a `vars` list (an array of tables in TOML, which has no top-level array).
+#[derive(serde::Serialize)]
+struct Document<'a> {
+    vars: &'a [EnvVar],
+}
+
+/// The variables, with their metadata, as a document in this format.
+pub(crate) fn render(vars: &[EnvVar], format: DocumentFormat) -> Result<String, EnvError> {
+    let document = Document { vars };
+    match format {
+        DocumentFormat::Json => serde_json::to_string_pretty(&document).map_err(|_| EnvError::VarError),
+        DocumentFormat::Toml => toml::to_string(&document).map_err(|_| EnvError::VarError),
+        DocumentFormat::Yaml => serde_yaml::to_string(&document).map_err(|_| EnvError::VarError),
+    }
+}
+
+/// Exports the globally persisted variables (all of them, or only the managed ones): with [`Format::Json`],
+/// [`Format::Toml`] or [`Format::Yaml`], as a document listing each of them as an [`EnvVar`] (name, value,
+/// scope, source, secret flag and time of the last change), the values of the secret ones included; as a
+/// script for the other formats. [`render_script`](crate::render_script) renders the same as
+/// [`Filter::Managed`], and [`EnvSnapshot::export_as`](crate::EnvSnapshot::export_as) the variables of a snapshot.
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{export_as, Filter, Format};
+/// std::fs::write("inventory.json", export_as(Format::Json, Filter::All).unwrap()).unwrap();
+/// ```
+pub fn export_as<F: Into<Format>>(format: F, filter: Filter) -> Result<String, EnvError> {
+    export_as_in(&Target::default(), format, filter)
+}
+
+/// Exports the variables persisted in the given target.
+pub fn export_as_in<F: Into<Format>>(target: &Target, format: F, filter: Filter) -> Result<String, EnvError> {
+    script::render_as(&env_vars_in(target, filter)?, target.get_scope(), format.into())
+}
+
+/// An entry of an imported document: the `scope` (and the other fields of an [`EnvVar`]) may be left out.
+#[derive(serde::Deserialize)]
+struct Entry {
+    name: String,
+    value: String,
+    scope: Option<Scope>,
+}
+
+#[derive(serde::Deserialize)]
+struct Entries {
+    vars: Vec<Entry>,
+}
+
+fn parse(content: &str, format: DocumentFormat) -> Result<Vec<Entry>, EnvError> {
+    let document: Entries = match format {
+        DocumentFormat::Json => serde_json::from_str(content).map_err(|_| EnvError::ParseError)?,
+        DocumentFormat::Toml => toml::from_str(content).map_err(|_| EnvError::ParseError)?,
+        DocumentFormat::Yaml => serde_yaml::from_str(content).map_err(|_| EnvError::ParseError)?,
+    };
+    Ok(document.vars)
+}
+
+/// Sets the entries of one scope in a session, their outcomes being recorded at their index.
+fn apply(target: &Target, entries: &[(usize, &Entry)], options: &ImportOptions, outcomes: &mut [Option<ImportOutcome>]) {
+    let mut session = match GlobalEnv::open_in(target) {
+        Ok(session) => session,
+        Err(e) => return entries.iter().for_each(|(i, _)| outcomes[*i] = Some(ImportOutcome::Failed(e.clone()))),
+    };
+    for (i, entry) in entries {
+        // Checked before the process environment is touched, even if the value is unchanged
+        if let Err(e) = target.check(&entry.name) {
+            outcomes[*i] = Some(ImportOutcome::Failed(e));
+            continue;
+        }
+        outcomes[*i] = Some(match session.get(&entry.name) {
+            Ok(Some(old)) if old == entry.value => {
+                std::env::set_var(&entry.name, &entry.value);
+                ImportOutcome::Unchanged
+            }
+            Ok(Some(_)) if !options.get_overwrite() => ImportOutcome::Skipped,
+            _ => match session.set(&entry.name, &entry.value) {
+                Ok(()) => ImportOutcome::Set,
+                Err(e) => ImportOutcome::Failed(e),
+            },
+        });
+    }
+    // Nothing was written if the session couldn't be
+    if let Err(e) = session.flush() {
+        for (i, _) in entries {
+            if outcomes[*i] == Some(ImportOutcome::Set) {
+                outcomes[*i] = Some(ImportOutcome::Failed(e.clone()));
+            }
+        }
+    }
+}
+
+/// Sets globally the variables of a document as [`export_as`] renders them ([`Format::Json`], [`Format::Toml`]
+/// or [`Format::Yaml`]): a `vars` list of entries with a `name`, a `value` and optionally a `scope` (`"User"`,
+/// `"System"`...), the other fields being ignored. The entries of each scope are applied in a single
+/// [`GlobalEnv`] session; a variable which can't be persisted doesn't stop the import, the failure is recorded
+/// in its report. Other formats fail with [`EnvError::ParseError`].
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{import_from, Format, ImportOptions};
+/// // {"vars": [{"name": "EDITOR", "value": "vim"}, {"name": "JAVA_HOME", "value": "/opt/jdk", "scope": "System"}]}
+/// for result in import_from("inventory.json", Format::Json, &ImportOptions::new()).unwrap() {
+///     println!("{}: {:?}", result.name, result.outcome);
+/// }
+/// ```
+pub fn import_from<P: AsRef<Path>>(path: P, format: Format, options: &ImportOptions) -> Result<Vec<ImportResult>, EnvError> {
+    import_from_in(&Target::default(), path, format, options)
+}
+
+/// Sets in the given target the variables of a document, the scope of an entry replacing the target's.
+pub fn import_from_in<P: AsRef<Path>>(target: &Target, path: P, format: Format, options: &ImportOptions) -> Result<Vec<ImportResult>, EnvError> {
+    let format = DocumentFormat::of(format).ok_or(EnvError::ParseError)?;
+    let entries = parse(&fs::read_to_string(path)?, format)?;
+    let mut scopes: Vec<(Scope, Vec<(usize, &Entry)>)> = Vec::new();
+    for (i, entry) in entries.iter().enumerate() {
+        let scope = entry.scope.clone().unwrap_or_else(|| target.get_scope());
+        match scopes.iter_mut().find(|(s, _)| *s == scope) {
+            Some((_, of_scope)) => of_scope.push((i, entry)),
+            None => scopes.push((scope, vec![(i, entry)])),
+        }
+    }
+    let mut outcomes = vec![None; entries.len()];
+    for (scope, of_scope) in scopes {
+        apply(&target.clone().scope(scope), &of_scope, options, &mut outcomes);
+    }
+    Ok(entries
+        .into_iter()
+        .zip(outcomes)
+        .map(|(entry, outcome)| ImportResult { name: entry.name, outcome: outcome.unwrap_or(ImportOutcome::Skipped) })
+        .collect())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_documents() {
+        let vars = [EnvVar {
+            name: String::from("EDITOR"),
+            value: String::from("vim"),
+            scope: Scope::User,
+            source: String::from("/home/me/.bashrc"),
+            secret: false,
+            modified_at: None,
+        }];
+        let json = render(&vars, DocumentFormat::Json).unwrap();
+        assert!(json.starts_with("{\n  \"vars\": [\n") && json.contains("\"name\": \"EDITOR\""));
+        let toml = render(&vars, DocumentFormat::Toml).unwrap();
+        assert!(toml.starts_with("[[vars]]\nname = \"EDITOR\"\nvalue = \"vim\"\nscope = \"User\"\n"));
+        assert!(render(&vars, DocumentFormat::Yaml).unwrap().starts_with("vars:\n- name: EDITOR\n  value: vim\n"));
+        assert_eq!(DocumentFormat::of(Format::Batch), None);
+    }
+
+    #[test]
+    fn exports_filtered_documents() {
+        let file = std::env::temp_dir().join("globalenv_structured_export");
+        std::fs::write(&file, "export OWN=1\n").unwrap();
+        let target = Target::new().file(&file);
+        crate::set_var_in(&target, "EXPORT_MANAGED", "1").unwrap();
+        let managed = export_as_in(&target, Format::Json, Filter::Managed).unwrap();
+        assert_eq!(managed, crate::render_script_in(&target, Format::Json).unwrap());
+        assert!(!managed.contains("OWN") && export_as_in(&target, Format::Json, Filter::All).unwrap().contains("OWN"));
+        std::fs::remove_file(&file).unwrap();
+    }
+
+    #[test]
+    fn imports_documents() {
+        let file = std::env::temp_dir().join("globalenv_structured_import");
+        std::fs::write(&file, "").unwrap();
+        let document = std::env::temp_dir().join("globalenv_structured_import.toml");
+        std::fs::write(&document, "[[vars]]\nname = \"IMPORT_A\"\nvalue = \"1\"\n\n[[vars]]\nname = \"A=B\"\nvalue = \"2\"\n").unwrap();
+        let target = Target::new().file(&file);
+        let results = import_from_in(&target, &document, Format::Toml, &ImportOptions::new()).unwrap();
+        assert_eq!(results[0], ImportResult { name: String::from("IMPORT_A"), outcome: ImportOutcome::Set });
+        assert!(matches!(results[1].outcome, ImportOutcome::Failed(EnvError::InvalidName(_))));
+        assert_eq!(crate::get_var_in(&target, "IMPORT_A").unwrap().as_deref(), Some("1"));
+        std::env::remove_var("IMPORT_A");
+        let denied = target.clone().policy(crate::Policy::new().deny(["IMPORT_A"]));
+        let results = import_from_in(&denied, &document, Format::Toml, &ImportOptions::new()).unwrap();
+        assert_eq!(results[0].outcome, ImportOutcome::Failed(EnvError::PolicyError));
+        assert_eq!(std::env::var("IMPORT_A").ok(), None);
+        let vars = crate::vars_in(&target).unwrap();
+        assert_eq!(parse(&render(&vars, DocumentFormat::Json).unwrap(), DocumentFormat::Json).unwrap()[0].value, "1");
+        assert_eq!(parse("{}", DocumentFormat::Json).err(), Some(EnvError::ParseError));
+        std::fs::remove_file(&file).unwrap();
+        std::fs::remove_file(&document).unwrap();
+    }
+}
@@ -0,0 +1,572 @@
+//! Per-shell syntax of the lines defining a variable, and quoting of their values.
+
+/// Families of shells sharing the same syntax to export a variable.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+pub(crate) enum Syntax {
+    /// `export VAR=value` (sh, bash, zsh...)
+    Posix,
+    /// `setenv VAR value` (csh, tcsh)
+    Csh,
+    /// `set -gx VAR value`
+    Fish,
+    /// `$env:VAR = 'value'`
+    PowerShell,
+    /// `export VAR = 'value'` (ion, Redox's shell)
+    Ion,
+}
+
+fn is_name(name: &str) -> bool {
+    !name.is_empty() && name.chars().all(|c| c.is_ascii_alphanumeric() || c == '_')
+}
+
+/// Whether the value can be left unquoted, which the configured [`QuoteStyle`](crate::QuoteStyle) may forbid.
+fn is_safe(value: &str) -> bool {
+    crate::GlobalEnvConfig::defaults().quote_style == crate::QuoteStyle::Minimal
+        && !value.is_empty() && value.chars().all(|c| c.is_ascii_alphanumeric() || "_-./:@%+,".contains(c))
+}
+
+impl Syntax {
+    /// Line defining the variable. A reference to the variable itself (e.g. `$PATH:/opt/bin`) is kept
+    /// expandable, in double quotes, so the line appends to what the variable inherits.
+    pub(crate) fn render(&self, var: &str, value: &str) -> String {
+        if let Some(quoted) = self.quote_self_reference(var, value) {
+            return match self {
+                Syntax::Posix => format!("export {}={}", var, quoted),
+                Syntax::Csh => format!("setenv {} {}", var, quoted),
+                Syntax::Fish => format!("set -gx {} {}", var, quoted),
+                Syntax::PowerShell => format!("$env:{} = {}", var, quoted),
+                Syntax::Ion => format!("export {} = {}", var, quoted),
+            };
+        }
+        match self {
+            Syntax::Posix => format!("export {}={}", var, quote_posix(value)),
+            Syntax::Csh => format!("setenv {} {}", var, quote_csh(value)),
+            Syntax::Fish => format!("set -gx {} {}", var, quote_fish(value)),
+            Syntax::PowerShell => format!("$env:{} = {}", var, quote_powershell(value)),
+            Syntax::Ion => format!("export {} = {}", var, quote_ion(value)),
+        }
+    }
+
+    /// Line setting the variable to the output of a command, run by the shell at startup.
+    /// The command is made of words, which are quoted.
+    #[cfg_attr(not(feature = "age"), allow(dead_code))]
+    pub(crate) fn render_command(&self, var: &str, command: &[&str]) -> String {
+        let quote = |word: &&str| match self {
+            Syntax::Posix | Syntax::Csh => quote_posix(word),
+            Syntax::Fish => quote_fish(word),
+            Syntax::PowerShell => quote_powershell(word),
+            Syntax::Ion => quote_ion(word),
+        };
+        let command = command.iter().map(quote).collect::<Vec<_>>().join(" ");
+        match self {
+            Syntax::Posix => format!("export {}=\"$({})\"", var, command),
+            Syntax::Csh => format!("setenv {} \"`{}`\"", var, command),
+            Syntax::Fish => format!("set -gx {} ({} | string collect)", var, command),
+            Syntax::PowerShell => format!("$env:{} = (& {}) -join \"`n\"", var, command),
+            Syntax::Ion => format!("export {} = \"$({})\"", var, command),
+        }
+    }
+
+    /// Parses a line defining a variable, returning the variable name and its unquoted value.
+    /// For POSIX shells, plain `VAR=value` assignments (exported by a later `export VAR` or a `set -a`, or read
+    /// by pam_env in `/etc/environment`) are definitions too, unless a command follows them.
+    /// A definition in the `if` guard of a condition (see [`crate::Condition`]) is parsed as the definition.
+    pub(crate) fn parse<'a>(&self, line: &'a str) -> Option<(&'a str, String)> {
+        let line = line.trim();
+        let line = crate::condition::unguard(*self, line).map_or(line, |(_, definition)| definition);
+        let (name, value) = match self {
+            Syntax::Posix => match line.strip_prefix("export ") {
+                Some(rest) => rest.split_once('=')?,
+                None => {
+                    let (name, value) = line.split_once('=')?;
+                    let (value, rest) = value.split_at(word_end(value));
+                    let rest = rest.trim_start();
+                    let exported = rest.strip_prefix(';').map(str::trim).and_then(|r| r.strip_prefix("export "));
+                    if !(rest.is_empty() || rest.starts_with('#') || exported.is_some_and(|r| r.trim() == name)) {
+                        return None;
+                    }
+                    (name, value)
+                }
+            },
+            Syntax::Ion => line.strip_prefix("export ")?.split_once('=')?,
+            Syntax::Csh => split_word(line.strip_prefix("setenv ")?),
+            Syntax::Fish => {
+                let rest = line.strip_prefix("set ")?.trim_start();
+                let rest = ["-gx ", "-xg ", "-Ux ", "-xU ", "-x "].iter().find_map(|f| rest.strip_prefix(f))?;
+                split_word(rest)
+            }
+            Syntax::PowerShell => {
+                let (name, value) = line.strip_prefix("$env:")?.split_once('=')?;
+                (name, value)
+            }
+        };
+        let name = name.trim();
+        if !is_name(name) {
+            return None;
+        }
+        let value = value.trim();
+        let unquoted = match self {
+            Syntax::Posix | Syntax::Csh => unquote_posix(value),
+            Syntax::Fish => unquote_fish(value),
+            Syntax::PowerShell => unquote_powershell(value),
+            Syntax::Ion => unquote_ion(value),
+        };
+        Some((name, unquoted.unwrap_or_else(|| value.to_string())))
+    }
+
+    /// The value in double quotes, its reference to the variable itself being left unescaped, if it has one.
+    /// PowerShell (whose references are `$env:VAR`) and values needing escapes the shell has not in double
+    /// quotes are left to the usual quoting.
+    fn quote_self_reference(&self, var: &str, value: &str) -> Option<String> {
+        let (i, len) = self_reference(value, var)?;
+        let special: &[char] = match self {
+            Syntax::Posix | Syntax::Fish => &['"', '\\', '$', '`'],
+            Syntax::Ion => &['"', '\\', '$', '@'],
+            Syntax::Csh | Syntax::PowerShell => &[],
+        };
+        if *self == Syntax::PowerShell || value.chars().any(|c| c.is_ascii_control()) {
+            return None;
+        }
+        let (before, after) = (&value[..i], &value[i + len..]);
+        if *self == Syntax::Csh && (before.contains(['"', '$', '!', '`', '\\']) || after.contains(['"', '$', '!', '`', '\\'])) {
+            return None;
+        }
+        let escape = |part: &str| escape_double(part, special);
+        // csh would read `$VAR:...` as a modifier
+        let reference = if *self == Syntax::Csh { format!("${{{}}}", var) } else { value[i..i + len].to_string() };
+        Some(format!("\"{}{}{}\"", escape(before), reference, escape(after)))
+    }
+
+    /// Line appending to the value the variable inherits, separated from it if there is one:
+    /// `export VAR="${VAR:+$VAR<sep>}suffix"`. Only POSIX shells have such an expansion.
+    pub(crate) fn render_append(&self, var: &str, suffix: &str, sep: &str) -> Option<String> {
+        if *self != Syntax::Posix || suffix.chars().chain(sep.chars()).any(|c| c.is_ascii_control()) {
+            return None;
+        }
+        let special = ['"', '\\', '$', '`'];
+        Some(format!("export {0}=\"${{{0}:+${0}{1}}}{2}\"", var, escape_double(sep, &special), escape_double(suffix, &special)))
+    }
+
+    /// Indentation and trailing comment (with the blanks before it) of a line defining a variable.
+    pub(crate) fn decoration<'a>(&self, line: &'a str) -> (&'a str, &'a str) {
+        let indent = &line[..line.len() - line.trim_start().len()];
+        let definition = line.trim_end();
+        let value = match self {
+            Syntax::Posix | Syntax::Ion => definition.split_once('=').map(|(_, value)| value.trim_start()),
+            Syntax::Csh => skip_words(definition, 2),
+            Syntax::Fish => skip_words(definition, 3),
+            Syntax::PowerShell => None,
+        };
+        let comment = value.map_or("", |value| {
+            let rest = &value[word_end(value)..];
+            if rest.trim_start().starts_with('#') { rest } else { "" }
+        });
+        (indent, comment)
+    }
+
+    /// The line only exports the variable, defined by an assignment elsewhere (`export VAR`).
+    pub(crate) fn exports(&self, line: &str, var: &str) -> bool {
+        *self == Syntax::Posix && line.trim().strip_prefix("export ").is_some_and(|name| name.trim() == var)
+    }
+}
+
+/// A value as parsed from a line written by [`Syntax::render_append`]: what it appends.
+pub(crate) fn appended<'a>(value: &'a str, var: &str, sep: &str) -> Option<&'a str> {
+    value.strip_prefix(&format!("${{{0}:+${0}{1}}}", var, sep))
+}
+
+/// Escapes these characters with backslashes, for double quotes.
+fn escape_double(part: &str, special: &[char]) -> String {
+    let mut escaped = String::new();
+    for c in part.chars() {
+        if special.contains(&c) {
+            escaped.push('\\');
+        }
+        escaped.push(c);
+    }
+    escaped
+}
+
+/// Position and length of the first reference to the variable itself (`$VAR` or `${VAR}`) in a value.
+fn self_reference(value: &str, var: &str) -> Option<(usize, usize)> {
+    let braced = format!("${{{}}}", var);
+    if let Some(i) = value.find(&braced) {
+        return Some((i, braced.len()));
+    }
+    let plain = format!("${}", var);
+    let is_name_char = |c: char| c.is_ascii_alphanumeric() || c == '_';
+    value.match_indices(&plain).map(|(i, _)| i).find(|i| !value[i + plain.len()..].starts_with(is_name_char)).map(|i| (i, plain.len()))
+}
+
+/// The new value of a definition referencing the variable itself (e.g. `$PATH:/x`), if it was given flattened
+/// (e.g. the process value with another entry): what the variable inherited when the shell ran the definition,
+/// found from the process value, is replaced back by the reference.
+pub(crate) fn keep_self_reference(old: &str, var: &str, new: &str, process: Option<&str>) -> Option<String> {
+    if self_reference(new, var).is_some() {
+        return None;
+    }
+    let (i, len) = self_reference(old, var)?;
+    let inherited = process?.strip_prefix(&old[..i])?.strip_suffix(&old[i + len..])?;
+    if inherited.is_empty() {
+        return None;
+    }
+    let (head, rest) = new.split_at(new.find(inherited)?);
+    Some(format!("{}{}{}", head, &old[i..i + len], &rest[inherited.len()..]))
+}
+
+/// Byte index where a POSIX shell word ends: its first unquoted blank or `;`, or the end of the string.
+fn word_end(word: &str) -> usize {
+    let mut quote = None;
+    let mut chars = word.char_indices().peekable();
+    while let Some((i, c)) = chars.next() {
+        match (quote, c) {
+            (None, c) if c.is_whitespace() || c == ';' => return i,
+            (None, '$') if chars.peek().is_some_and(|(_, c)| *c == '\'') => {
+                chars.next();
+                quote = Some('$');
+            }
+            (None, '\'' | '"') => quote = Some(c),
+            (None | Some('"' | '$'), '\\') => {
+                chars.next();
+            }
+            (Some('\'' | '$'), '\'') | (Some('"'), '"') => quote = None,
+            _ => (),
+        }
+    }
+    word.len()
+}
+
+/// What follows the first words of a line.
+fn skip_words(line: &str, words: usize) -> Option<&str> {
+    let mut rest = line.trim_start();
+    for _ in 0..words {
+        rest = rest.split_once([' ', '\t'])?.1.trim_start();
+    }
+    Some(rest)
+}
+
+fn split_word(s: &str) -> (&str, &str) {
+    let s = s.trim_start();
+    s.split_once([' ', '\t']).unwrap_or((s, ""))
+}
+
+/// As is when the value only has safe characters, in single quotes otherwise,
+/// or ANSI-C `$'...'` quoting when it contains newlines or other control characters.
+pub(crate) fn quote_posix(value: &str) -> String {
+    if is_safe(value) {
+        return value.to_string();
+    }
+    if !value.chars().any(|c| c.is_ascii_control()) {
+        return format!("'{}'", value.replace('\'', r"'\''"));
+    }
+    let mut quoted = String::from("$'");
+    for c in value.chars() {
+        match c {
+            '\n' => quoted.push_str(r"\n"),
+            '\t' => quoted.push_str(r"\t"),
+            '\r' => quoted.push_str(r"\r"),
+            '\'' => quoted.push_str(r"\'"),
+            '\\' => quoted.push_str(r"\\"),
+            c if c.is_ascii_control() => quoted.push_str(&format!(r"\x{:02x}", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+/// Unquotes a shell word made of unquoted, `'single'`, `"double"` and `$'ANSI-C'` parts.
+/// Returns `None` if quotes are unbalanced. Parameter expansions are kept literally.
+pub(crate) fn unquote_posix(word: &str) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = word.chars().peekable();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => loop {
+                match chars.next()? {
+                    '\'' => break,
+                    c => value.push(c),
+                }
+            },
+            '"' => loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => match chars.next()? {
+                        c @ ('$' | '`' | '"' | '\\') => value.push(c),
+                        '\n' => (),
+                        c => { value.push('\\'); value.push(c) }
+                    },
+                    c => value.push(c),
+                }
+            },
+            '$' if chars.peek() == Some(&'\'') => {
+                chars.next();
+                loop {
+                    match chars.next()? {
+                        '\'' => break,
+                        '\\' => match chars.next()? {
+                            'n' => value.push('\n'),
+                            't' => value.push('\t'),
+                            'r' => value.push('\r'),
+                            'x' => {
+                                let hex: String = [chars.next()?, chars.next()?].iter().collect();
+                                value.push(char::from(u8::from_str_radix(&hex, 16).ok()?));
+                            }
+                            c => value.push(c),
+                        },
+                        c => value.push(c),
+                    }
+                }
+            }
+            '\\' => value.push(chars.next()?),
+            // An unquoted space ends the word, the rest can only be a comment
+            c if c.is_whitespace() => break,
+            c => value.push(c),
+        }
+    }
+    Some(value)
+}
+
+/// csh has no escape for newlines on a single line: they are kept as backslash-newline in single quotes.
+fn quote_csh(value: &str) -> String {
+    if is_safe(value) {
+        return value.to_string();
+    }
+    format!("'{}'", value.replace('\'', r"'\''").replace('!', r"\!").replace('\n', "\\\n"))
+}
+
+/// Single quotes (where only `\'` and `\\` are escapes), control characters being escaped outside them.
+pub(crate) fn quote_fish(value: &str) -> String {
+    if is_safe(value) {
+        return value.to_string();
+    }
+    let mut quoted = String::from("'");
+    for c in value.chars() {
+        match c {
+            '\'' => quoted.push_str(r"\'"),
+            '\\' => quoted.push_str(r"\\"),
+            '\n' => quoted.push_str(r"'\n'"),
+            '\t' => quoted.push_str(r"'\t'"),
+            '\r' => quoted.push_str(r"'\r'"),
+            c if c.is_ascii_control() => quoted.push_str(&format!(r"'\x{:02x}'", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('\'');
+    quoted
+}
+
+pub(crate) fn unquote_fish(word: &str) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => loop {
+                match chars.next()? {
+                    '\'' => break,
+                    '\\' => match chars.next()? {
+                        c @ ('\'' | '\\') => value.push(c),
+                        c => { value.push('\\'); value.push(c) }
+                    },
+                    c => value.push(c),
+                }
+            },
+            '"' => loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => match chars.next()? {
+                        c @ ('"' | '$' | '\\') => value.push(c),
+                        c => { value.push('\\'); value.push(c) }
+                    },
+                    c => value.push(c),
+                }
+            },
+            '\\' => match chars.next()? {
+                'n' => value.push('\n'),
+                't' => value.push('\t'),
+                'r' => value.push('\r'),
+                'x' => {
+                    let hex: String = [chars.next()?, chars.next()?].iter().collect();
+                    value.push(char::from(u8::from_str_radix(&hex, 16).ok()?));
+                }
+                c => value.push(c),
+            },
+            c if c.is_whitespace() => break,
+            c => value.push(c),
+        }
+    }
+    Some(value)
+}
+
+/// Single-quoted (`''` escapes a quote), or double-quoted with backtick escapes for control characters.
+pub(crate) fn quote_powershell(value: &str) -> String {
+    if !value.chars().any(|c| c.is_ascii_control()) {
+        return format!("'{}'", value.replace('\'', "''"));
+    }
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        match c {
+            '\n' => quoted.push_str("`n"),
+            '\t' => quoted.push_str("`t"),
+            '\r' => quoted.push_str("`r"),
+            '\0' => quoted.push_str("`0"),
+            '`' | '"' | '$' => { quoted.push('`'); quoted.push(c) }
+            c if c.is_ascii_control() => quoted.push_str(&format!("$([char]0x{:02x})", c as u32)),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+pub(crate) fn unquote_powershell(word: &str) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = word.chars().peekable();
+    match chars.next()? {
+        '\'' => loop {
+            match chars.next()? {
+                '\'' if chars.peek() == Some(&'\'') => { chars.next(); value.push('\'') }
+                '\'' => break,
+                c => value.push(c),
+            }
+        },
+        '"' => loop {
+            match chars.next()? {
+                '"' => break,
+                '`' => match chars.next()? {
+                    'n' => value.push('\n'),
+                    't' => value.push('\t'),
+                    'r' => value.push('\r'),
+                    '0' => value.push('\0'),
+                    c => value.push(c),
+                },
+                c => value.push(c),
+            }
+        },
+        _ => return None,
+    }
+    Some(value)
+}
+
+/// Single quotes (which have no escapes), or double quotes escaping what would be expanded.
+fn quote_ion(value: &str) -> String {
+    if is_safe(value) {
+        return value.to_string();
+    }
+    if !value.contains('\'') {
+        return format!("'{}'", value);
+    }
+    let mut quoted = String::from("\"");
+    for c in value.chars() {
+        if matches!(c, '"' | '\\' | '$' | '@') {
+            quoted.push('\\');
+        }
+        quoted.push(c);
+    }
+    quoted.push('"');
+    quoted
+}
+
+fn unquote_ion(word: &str) -> Option<String> {
+    let mut value = String::new();
+    let mut chars = word.chars();
+    while let Some(c) = chars.next() {
+        match c {
+            '\'' => loop {
+                match chars.next()? {
+                    '\'' => break,
+                    c => value.push(c),
+                }
+            },
+            '"' => loop {
+                match chars.next()? {
+                    '"' => break,
+                    '\\' => value.push(chars.next()?),
+                    c => value.push(c),
+                }
+            },
+            '\\' => value.push(chars.next()?),
+            c if c.is_whitespace() => break,
+            c => value.push(c),
+        }
+    }
+    Some(value)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn parses_lines() {
+        assert_eq!(Syntax::Posix.parse("export FOO=bar"), Some(("FOO", String::from("bar"))));
+        assert_eq!(Syntax::Posix.parse("  export FOO=\"bar baz\""), Some(("FOO", String::from("bar baz"))));
+        assert_eq!(Syntax::Posix.parse("export FOO='bar' # comment"), Some(("FOO", String::from("bar"))));
+        assert_eq!(Syntax::Posix.parse("# export FOO=bar"), None);
+        assert_eq!(Syntax::Posix.parse("FOO=bar"), Some(("FOO", String::from("bar"))));
+        assert_eq!(Syntax::Posix.parse("FOO='a b' # comment"), Some(("FOO", String::from("a b"))));
+        assert_eq!(Syntax::Posix.parse("FOO=bar; export FOO"), Some(("FOO", String::from("bar"))));
+        assert_eq!(Syntax::Posix.parse("FOO=$'it\\'s' make"), None);
+        assert_eq!(Syntax::Posix.parse("FOO=bar make install"), None);
+        assert!(Syntax::Posix.exports("export FOO", "FOO") && !Syntax::Fish.exports("export FOO", "FOO"));
+        assert_eq!(Syntax::Csh.parse("setenv FOO 'a b'"), Some(("FOO", String::from("a b"))));
+        assert_eq!(Syntax::Fish.parse("set -gx FOO bar"), Some(("FOO", String::from("bar"))));
+        assert_eq!(Syntax::PowerShell.parse("$env:FOO = 'it''s'"), Some(("FOO", String::from("it's"))));
+        assert_eq!(Syntax::Ion.parse("export FOO = \"it's @x\""), Some(("FOO", String::from("it's @x"))));
+    }
+
+    #[test]
+    fn quote_roundtrip() {
+        for value in ["plain", "two words", "it's", "a\nb\tc", "$HOME", ""] {
+            for syntax in [Syntax::Posix, Syntax::Fish, Syntax::PowerShell, Syntax::Ion] {
+                assert_eq!(syntax.parse(&syntax.render("V", value)), Some(("V", value.to_string())));
+            }
+        }
+        assert_eq!(quote_posix("two words"), "'two words'");
+        assert_eq!(unquote_posix("'it'\\''s'").as_deref(), Some("it's"));
+    }
+
+    #[test]
+    fn keeps_self_references() {
+        assert_eq!(Syntax::Posix.render("PATH", "$PATH:/opt/my bin"), "export PATH=\"$PATH:/opt/my bin\"");
+        assert_eq!(Syntax::Posix.render("PATH", "${PATH}:$HOME/bin"), "export PATH=\"${PATH}:\\$HOME/bin\"");
+        assert_eq!(Syntax::Posix.render("PATH", "$PATHS"), "export PATH='$PATHS'");
+        assert_eq!(Syntax::Fish.render("PATH", "$PATH:/x"), "set -gx PATH \"$PATH:/x\"");
+        assert_eq!(Syntax::Csh.render("PATH", "$PATH:/x"), "setenv PATH \"${PATH}:/x\"");
+        for syntax in [Syntax::Posix, Syntax::Fish, Syntax::Ion] {
+            assert_eq!(syntax.parse(&syntax.render("PATH", "$PATH:/x")), Some(("PATH", String::from("$PATH:/x"))));
+        }
+        let process = Some("/usr/bin:/x");
+        assert_eq!(keep_self_reference("$PATH:/x", "PATH", "/usr/bin:/x:/y", process).as_deref(), Some("$PATH:/x:/y"));
+        assert_eq!(keep_self_reference("/x:$PATH", "PATH", "/y:/usr/bin", Some("/x:/usr/bin")).as_deref(), Some("/y:$PATH"));
+        assert_eq!(keep_self_reference("$PATH:/x", "PATH", "/opt", process), None);
+        assert_eq!(keep_self_reference("/x", "PATH", "/usr/bin:/y", process), None);
+    }
+
+    #[test]
+    fn finds_decoration() {
+        assert_eq!(Syntax::Posix.decoration("  export A='x # y'  # note"), ("  ", "  # note"));
+        assert_eq!(Syntax::Csh.decoration("\tsetenv A 1 # note"), ("\t", " # note"));
+        assert_eq!(Syntax::Fish.decoration("set -gx A \"1 2\""), ("", ""));
+    }
+
+    #[test]
+    fn renders_appends() {
+        let line = Syntax::Posix.render_append("PATH", "/opt/my bin", ":").unwrap();
+        assert_eq!(line, "export PATH=\"${PATH:+$PATH:}/opt/my bin\"");
+        let (_, value) = Syntax::Posix.parse(&line).unwrap();
+        assert_eq!(appended(&value, "PATH", ":"), Some("/opt/my bin"));
+        assert_eq!(Syntax::Fish.render_append("PATH", "/opt", ":"), None);
+    }
+
+    #[test]
+    fn renders_commands() {
+        let command = ["age", "-d", "/home/me/my file"];
+        assert_eq!(Syntax::Posix.render_command("V", &command), "export V=\"$(age -d '/home/me/my file')\"");
+        assert_eq!(Syntax::Fish.render_command("V", &command), "set -gx V (age -d '/home/me/my file' | string collect)");
+        assert_eq!(
+            Syntax::PowerShell.render_command("V", &command),
+            "$env:V = (& 'age' '-d' '/home/me/my file') -join \"`n\""
+        );
+    }
+}
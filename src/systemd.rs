@@ -0,0 +1,109 @@
+//! systemd drop-ins giving services the persisted variables.
+
+use crate::{EnvError, Scope, Target};
+use std::{fs, path::PathBuf};
+
+const DROPIN: &str = "globalenv.conf";
+
+/// Directory of the drop-ins of the unit: system units for [`Scope::System`] (and
+/// [`Scope::Service`]), user units otherwise.
+fn dropin_dir(target: &Target, unit: &str) -> Result<PathBuf, EnvError> {
+    if unit.is_empty() {
+        return Err(EnvError::InvalidName(String::from("empty unit name")));
+    }
+    if unit.contains('/') {
+        return Err(EnvError::InvalidName(String::from("unit name contains '/'")));
+    }
+    let unit = if unit.contains('.') { unit.to_string() } else { format!("{}.service", unit) };
+    let units = match target.get_scope() {
+        Scope::System | Scope::Service => PathBuf::from("/etc/systemd/system"),
+        Scope::User | Scope::Project(_) => target.get_home()?.join(".config/systemd/user"),
+    };
+    Ok(units.join(format!("{}.d", unit)))
+}
+
+/// Double quotes with C-style escapes, `%` being doubled as it introduces unit specifiers.
+fn quote(s: &str) -> String {
+    let mut quoted = String::from("\"");
+    for c in s.chars() {
+        match c {
+            '"' => quoted.push_str("\\\""),
+            '\\' => quoted.push_str("\\\\"),
+            '\n' => quoted.push_str("\\n"),
+            '\t' => quoted.push_str("\\t"),
+            '%' => quoted.push_str("%%"),
+            c => quoted.push(c),
+        }
+    }
+    quoted.push('"');
+    quoted
+}
+
+pub(crate) fn render(vars: &[(String, String)]) -> String {
+    let mut dropin = String::from("# Generated by globalenv\n[Service]\n");
+    for (name, value) in vars {
+        dropin.push_str(&format!("Environment={}\n", quote(&format!("{}={}", name, value))));
+    }
+    dropin
+}
+
+/// Writes a drop-in setting the variables in the environment of a user service
+/// (`~/.config/systemd/user/<unit>.d/globalenv.conf`). The unit is a service if it has no suffix.
+/// It applies after `systemctl --user daemon-reload` and a restart of the service. The names are checked as
+/// [`set_var`](crate::set_var) does, nothing being written if one is rejected.
+/// Example:
+/// ```rust,no_run
+/// let vars: Vec<(String, String)> = globalenv::managed_vars().unwrap().into_iter().map(Into::into).collect();
+/// globalenv::write_systemd_dropin("myapp", &vars).unwrap();
+/// ```
+pub fn write_systemd_dropin(unit: &str, vars: &[(String, String)]) -> Result<(), EnvError> {
+    write_systemd_dropin_in(&Target::default(), unit, vars)
+}
+
+/// Writes the drop-in of a system service (`/etc/systemd/system/<unit>.d/globalenv.conf`) if the
+/// target has the system scope, of a user service otherwise.
+pub fn write_systemd_dropin_in(target: &Target, unit: &str, vars: &[(String, String)]) -> Result<(), EnvError> {
+    for (var, _) in vars {
+        target.check(var)?;
+    }
+    let dir = dropin_dir(target, unit)?;
+    target.create_dir(&dir)?;
+    fs::write(dir.join(DROPIN), render(vars))?;
+    target.own(&dir.join(DROPIN))
+}
+
+/// Removes the drop-in written by [`write_systemd_dropin`].
+pub fn remove_systemd_dropin(unit: &str) -> Result<(), EnvError> {
+    remove_systemd_dropin_in(&Target::default(), unit)
+}
+
+/// Removes the drop-in written by [`write_systemd_dropin_in`], and its directory if it is left empty.
+pub fn remove_systemd_dropin_in(target: &Target, unit: &str) -> Result<(), EnvError> {
+    let dir = dropin_dir(target, unit)?;
+    match fs::remove_file(dir.join(DROPIN)) {
+        Err(e) if e.kind() != std::io::ErrorKind::NotFound => return Err(e.into()),
+        _ => (),
+    }
+    // Fails if other drop-ins remain
+    let _ = fs::remove_dir(dir);
+    Ok(())
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn renders_environment_lines() {
+        let vars = vec![(String::from("A"), String::from("1")), (String::from("B"), String::from("50% \"x\""))];
+        assert_eq!(
+            render(&vars),
+            "# Generated by globalenv\n[Service]\nEnvironment=\"A=1\"\nEnvironment=\"B=50%% \\\"x\\\"\"\n"
+        );
+        let dir = dropin_dir(&Target::new().scope(Scope::System), "myapp").unwrap();
+        assert_eq!(dir, PathBuf::from("/etc/systemd/system/myapp.service.d"));
+        assert_eq!(dropin_dir(&Target::new(), "../myapp"), Err(EnvError::InvalidName(String::from("unit name contains '/'"))));
+        let vars = vec![(String::from("A=B"), String::new())];
+        assert!(matches!(write_systemd_dropin_in(&Target::new(), "myapp", &vars), Err(EnvError::InvalidName(_))));
+    }
+}
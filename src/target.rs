@@ -0,0 +1,657 @@
+//! Resolution of where a variable is persisted: registry hive, shell startup file or explicit file.
+
+use crate::{syntax::Syntax, EnvError, Policy};
+use std::{env, fmt, path::{Path, PathBuf}, str::FromStr, sync::RwLock};
+
+/// Shells whose startup files can be edited.
+#[derive(Debug, PartialEq, Eq, Clone, Copy)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Shell {
+    /// POSIX sh, e.g. the default login shell of FreeBSD users
+    Sh,
+    /// ksh (OpenBSD's ksh, ksh93, mksh...)
+    Ksh,
+    Bash,
+    Zsh,
+    /// tcsh and csh
+    Tcsh,
+    Fish,
+    PowerShell,
+    /// ion, the shell of Redox
+    Ion,
+}
+
+impl Shell {
+    /// Detects the current user's shell from the `SHELL` environment variable. The shell is recognized
+    /// by its name wherever it is installed (`/bin`, `/usr/bin`, `/usr/local/bin` for the BSD ports...).
+    /// In Termux, where `SHELL` is usually not set, bash is assumed. Elsewhere on Unix (cron jobs,
+    /// containers...), the login shell of the user's passwd entry is used, and if it is not a supported one
+    /// (e.g. `nologin`), the default shell of the platform: zsh on macOS, sh on the BSDs, ion on Redox and
+    /// bash on the others.
+    pub fn detect() -> Result<Shell, EnvError> {
+        match env::var("SHELL") {
+            Ok(shell) => Shell::from_path(&shell),
+            Err(_) if cfg!(target_os = "android") && termux_prefix().is_some() => Ok(Shell::Bash),
+            #[cfg(target_family = "unix")]
+            Err(_) => Ok(login_shell(crate::passwd::current().map(|account| account.shell).as_deref())),
+            #[cfg(not(target_family = "unix"))]
+            Err(e) => Err(e.into()),
+        }
+    }
+
+    pub(crate) fn from_path(shell: &str) -> Result<Shell, EnvError> {
+        let name = Path::new(shell).file_name().and_then(|n| n.to_str()).ok_or(EnvError::UnsupportedShell)?;
+        // Login shells may be reported as "-sh"
+        name.trim_start_matches('-').parse()
+    }
+
+    /// Name of the startup file, relative to the home directory, in which variables are persisted.
+    /// For tcsh, `.cshrc` is used instead unless a `.tcshrc` exists: a new `.tcshrc` would hide an existing
+    /// `.cshrc` (as FreeBSD's root has), and the original csh (NetBSD's) only reads `.cshrc`.
+    pub fn env_file(&self) -> &'static str {
+        match self {
+            Shell::Sh | Shell::Ksh => ".profile",
+            Shell::Bash => ".bashrc",
+            Shell::Zsh => ".zshenv",
+            Shell::Tcsh => ".tcshrc",
+            Shell::Fish => ".config/fish/config.fish",
+            Shell::Ion => ".config/ion/initrc",
+            #[cfg(target_os = "windows")]
+            Shell::PowerShell => r"Documents\PowerShell\Microsoft.PowerShell_profile.ps1",
+            #[cfg(not(target_os = "windows"))]
+            Shell::PowerShell => ".config/powershell/Microsoft.PowerShell_profile.ps1",
+        }
+    }
+
+    pub(crate) fn syntax(&self) -> Syntax {
+        match self {
+            Shell::Sh | Shell::Ksh | Shell::Bash | Shell::Zsh => Syntax::Posix,
+            Shell::Tcsh => Syntax::Csh,
+            Shell::Fish => Syntax::Fish,
+            Shell::PowerShell => Syntax::PowerShell,
+            Shell::Ion => Syntax::Ion,
+        }
+    }
+
+    /// Guesses the syntax of a file from its name, the POSIX one being the default.
+    pub(crate) fn syntax_of(path: &Path) -> Syntax {
+        let name = path.file_name().and_then(|n| n.to_str()).unwrap_or_default();
+        if name.ends_with(".fish") {
+            Syntax::Fish
+        } else if name == "initrc" || name.ends_with(".ion") {
+            Syntax::Ion
+        } else if name.ends_with(".ps1") {
+            Syntax::PowerShell
+        } else if name.ends_with("cshrc") || name == ".login" || name.ends_with(".csh") {
+            Syntax::Csh
+        } else {
+            Syntax::Posix
+        }
+    }
+}
+
+impl fmt::Display for Shell {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Shell::Sh => "sh",
+            Shell::Ksh => "ksh",
+            Shell::Bash => "bash",
+            Shell::Zsh => "zsh",
+            Shell::Tcsh => "tcsh",
+            Shell::Fish => "fish",
+            Shell::PowerShell => "powershell",
+            Shell::Ion => "ion",
+        })
+    }
+}
+
+impl FromStr for Shell {
+    type Err = EnvError;
+
+    fn from_str(s: &str) -> Result<Shell, EnvError> {
+        match s {
+            "sh" => Ok(Shell::Sh),
+            "ksh" | "ksh93" | "mksh" | "oksh" | "pdksh" => Ok(Shell::Ksh),
+            "bash" => Ok(Shell::Bash),
+            "zsh" => Ok(Shell::Zsh),
+            "tcsh" | "csh" => Ok(Shell::Tcsh),
+            "fish" => Ok(Shell::Fish),
+            "powershell" | "pwsh" => Ok(Shell::PowerShell),
+            "ion" => Ok(Shell::Ion),
+            _ => Err(EnvError::UnsupportedShell),
+        }
+    }
+}
+
+/// Whether a variable is set for the current user only, for the whole machine, or for a project.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Scope {
+    /// HKCU\Environment on Windows, the shell startup file in `$HOME` on Unix
+    #[default]
+    User,
+    /// HKLM\SYSTEM\CurrentControlSet\Control\Session Manager\Environment on Windows, `/etc/environment` on Linux,
+    /// `/etc/profile` on other Unix systems (illumos, the BSDs...), which have no `/etc/environment`
+    System,
+    /// HKU\.DEFAULT\Environment on Windows, the profile of LocalSystem: the variables of the services running
+    /// as SYSTEM (e.g. agents) and of the processes they start. The system scope on Unix.
+    Service,
+    /// The `.envrc` (if there is one, for direnv) or `.env` file of this project directory, on every platform:
+    /// the variables of a repository, managed (set, read, listed...) as the global ones are
+    Project(PathBuf),
+}
+
+impl fmt::Display for Scope {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Scope::User => "user",
+            Scope::System => "system",
+            Scope::Service => "service",
+            Scope::Project(_) => "project",
+        })
+    }
+}
+
+/// Describes where variables are persisted. The default target is the current user's scope (or the one set with
+/// [`GlobalEnvConfig::set_defaults`](crate::GlobalEnvConfig::set_defaults)), with the shell detected from `SHELL`
+/// on Unix (once per process, see [`invalidate`]).
+/// Example:
+/// ```rust
+/// use globalenv::{Shell, Target};
+/// let target = Target::new().shell(Shell::Zsh);
+/// ```
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub struct Target {
+    scope: Scope,
+    shell: Option<Shell>,
+    file: Option<PathBuf>,
+    tmux: bool,
+    direnv: bool,
+    policy: Policy,
+    allow_protected: bool,
+    home: Option<PathBuf>,
+    /// User and group ids of the account, given the files created for it
+    owner: Option<(u32, u32)>,
+}
+
+/// Resolved storage of a target.
+pub(crate) enum Store {
+    File(PathBuf, Syntax),
+    #[cfg(target_os = "windows")]
+    Registry(Scope),
+}
+
+impl Store {
+    /// Syntax of the file, `None` for the registry.
+    pub(crate) fn syntax(&self) -> Option<Syntax> {
+        match self {
+            Store::File(_, syntax) => Some(*syntax),
+            #[cfg(target_os = "windows")]
+            Store::Registry(_) => None,
+        }
+    }
+}
+
+impl Default for Target {
+    fn default() -> Target {
+        Target {
+            scope: crate::GlobalEnvConfig::defaults().scope.clone(),
+            shell: None,
+            file: None,
+            tmux: false,
+            direnv: false,
+            policy: Policy::default(),
+            allow_protected: false,
+            home: None,
+            owner: None,
+        }
+    }
+}
+
+impl Target {
+    pub fn new() -> Target {
+        Target::default()
+    }
+
+    /// Targets another account, e.g. from a provisioning script run as root: its home directory and login
+    /// shell are read from the passwd database instead of the `HOME` and `SHELL` of the process (an
+    /// unsupported login shell falling back to the platform's default, see [`Shell::detect`]).
+    /// Example:
+    /// ```rust,no_run
+    /// use globalenv::{set_var_in, Target};
+    /// set_var_in(&Target::for_user("deploy").unwrap(), "RAILS_ENV", "production").unwrap();
+    /// ```
+    #[cfg(target_family = "unix")]
+    pub fn for_user(name: &str) -> Result<Target, EnvError> {
+        Ok(Target::for_account(crate::passwd::by_name(name).ok_or(EnvError::VarError)?))
+    }
+
+    /// Targets the account with this user id, see [`Target::for_user`].
+    #[cfg(target_family = "unix")]
+    pub fn for_uid(uid: u32) -> Result<Target, EnvError> {
+        Ok(Target::for_account(crate::passwd::by_uid(uid).ok_or(EnvError::VarError)?))
+    }
+
+    /// Targets the user who ran `sudo` (from `SUDO_USER`) when running as root under it, rather than root
+    /// whose `HOME` and `SHELL` sudo may have kept; the current user otherwise. The files created for them
+    /// (e.g. by [`migrate_in`](crate::migrate_in)) are given to them, and rewritten ones keep their owner.
+    /// Example:
+    /// ```rust,no_run
+    /// use globalenv::{set_var_in, Target};
+    /// // sudo my-installer
+    /// set_var_in(&Target::invoking_user().unwrap(), "MYAPP_HOME", "/opt/myapp").unwrap();
+    /// ```
+    #[cfg(target_family = "unix")]
+    pub fn invoking_user() -> Result<Target, EnvError> {
+        match env::var("SUDO_USER") {
+            Ok(user) if unsafe { libc::geteuid() } == 0 && user != "root" => Target::for_user(&user),
+            _ => Ok(Target::new()),
+        }
+    }
+
+    #[cfg(target_family = "unix")]
+    fn for_account(account: crate::passwd::Account) -> Target {
+        let shell = login_shell(Some(&account.shell));
+        Target { home: Some(account.home), owner: Some((account.uid, account.gid)), ..Target::new().shell(shell) }
+    }
+
+    /// Sets the scope (user or system).
+    pub fn scope(mut self, scope: Scope) -> Target {
+        self.scope = scope;
+        self
+    }
+
+    /// Uses the startup file of this shell instead of the detected one.
+    /// On Windows, this targets the shell's file in the home directory instead of the registry.
+    pub fn shell(mut self, shell: Shell) -> Target {
+        self.shell = Some(shell);
+        self
+    }
+
+    /// Persists variables in this file, whatever the scope and shell.
+    pub fn file<P: Into<PathBuf>>(mut self, file: P) -> Target {
+        self.file = Some(file.into());
+        self
+    }
+
+    /// After a successful set or unset, also updates the global environment of the running tmux
+    /// server (if any), so new panes of existing sessions see the change.
+    pub fn tmux(mut self, tmux: bool) -> Target {
+        self.tmux = tmux;
+        self
+    }
+
+    /// After writing an `.envrc` (see [`Scope::Project`]), runs `direnv allow` on it, so direnv loads it. If direnv
+    /// doesn't approve it (e.g. it isn't installed), the change is still written and applied, and the operation
+    /// returns [`EnvError::NotApproved`](crate::EnvError::NotApproved) telling why.
+    pub fn direnv(mut self, direnv: bool) -> Target {
+        self.direnv = direnv;
+        self
+    }
+
+    /// Only lets the policy's permitted variables be set or unset.
+    pub fn policy(mut self, policy: Policy) -> Target {
+        self.policy = policy;
+        self
+    }
+
+    /// Lets the variables protected by the policy be set or unset.
+    pub fn allow_protected(mut self, allow_protected: bool) -> Target {
+        self.allow_protected = allow_protected;
+        self
+    }
+
+    /// Human-readable location of the target (file path or registry key).
+    pub fn location(&self) -> Result<String, EnvError> {
+        Ok(match self.store()? {
+            Store::File(path, _) => path.display().to_string(),
+            #[cfg(target_os = "windows")]
+            Store::Registry(scope) => crate::registry::key_path(&scope),
+        })
+    }
+
+    pub(crate) fn get_scope(&self) -> Scope {
+        self.scope.clone()
+    }
+
+    pub(crate) fn get_shell(&self) -> Option<Shell> {
+        self.shell
+    }
+
+    /// Home directory of the target's account.
+    pub(crate) fn get_home(&self) -> Result<PathBuf, EnvError> {
+        match &self.home {
+            Some(home) => Ok(home.clone()),
+            None => home_dir(),
+        }
+    }
+
+    /// The target is another account's (see [`Target::for_user`]), whose home directory is not `HOME`.
+    pub(crate) fn has_home(&self) -> bool {
+        self.home.is_some()
+    }
+
+    /// Gives a file (or directory) created in the home directory of the target's account to it, if the target
+    /// is another one's.
+    pub(crate) fn own(&self, path: &Path) -> Result<(), EnvError> {
+        #[cfg(target_family = "unix")]
+        if let (Some((uid, gid)), Some(home)) = (self.owner, &self.home) {
+            if path.starts_with(home) {
+                std::os::unix::fs::chown(path, Some(uid), Some(gid))?;
+            }
+        }
+        #[cfg(not(target_family = "unix"))]
+        let _ = path;
+        Ok(())
+    }
+
+    /// Creates the directory and its missing parents, see [`Target::own`].
+    pub(crate) fn create_dir(&self, dir: &Path) -> Result<(), EnvError> {
+        let missing: Vec<&Path> = dir.ancestors().take_while(|d| !d.exists()).collect();
+        std::fs::create_dir_all(dir)?;
+        for dir in missing {
+            self.own(dir)?;
+        }
+        Ok(())
+    }
+
+    /// The target is an explicit file.
+    pub(crate) fn has_file(&self) -> bool {
+        self.file.is_some()
+    }
+
+    pub(crate) fn get_tmux(&self) -> bool {
+        self.tmux
+    }
+
+    pub(crate) fn get_direnv(&self) -> bool {
+        self.direnv
+    }
+
+    /// Fails if the name can't be the one of a variable, or if the policy doesn't permit modifying the variable.
+    /// Files only take POSIX names, which every shell syntax reads back.
+    pub(crate) fn check(&self, var: &str) -> Result<(), EnvError> {
+        let registry = cfg!(target_os = "windows") && self.file.is_none() && self.shell.is_none() && !matches!(self.scope, Scope::Project(_));
+        check_name(var, registry)?;
+        self.policy.check(var, self.allow_protected)
+    }
+
+    pub(crate) fn store(&self) -> Result<Store, EnvError> {
+        if let Some(file) = &self.file {
+            let syntax = self.shell.map_or_else(|| Shell::syntax_of(file), |s| s.syntax());
+            return Ok(Store::File(file.clone(), syntax));
+        }
+        if let Scope::Project(dir) = &self.scope {
+            return Ok(Store::File(project_file(dir), Syntax::Posix));
+        }
+        #[cfg(target_os = "windows")]
+        {
+            match self.shell {
+                Some(shell) => home_file(&self.get_home()?, shell),
+                None => Ok(Store::Registry(self.scope.clone())),
+            }
+        }
+        #[cfg(target_family = "unix")]
+        {
+            match self.scope {
+                Scope::System | Scope::Service => Ok(Store::File(system_file(), Syntax::Posix)),
+                _ => match self.shell {
+                    Some(shell) => home_file(&self.get_home()?, shell),
+                    None => detected_home_file(),
+                },
+            }
+        }
+        #[cfg(not(any(target_os = "windows", target_family = "unix")))]
+        Err(EnvError::UnsupportedPlatform)
+    }
+}
+
+/// File of the project scope, `.envrc` when direnv is used.
+fn project_file(dir: &Path) -> PathBuf {
+    match dir.join(".envrc") {
+        envrc if envrc.exists() => envrc,
+        _ => dir.join(".env"),
+    }
+}
+
+/// File of the system scope, read by every session (through pam_env on Linux).
+#[cfg(target_os = "linux")]
+const SYSTEM_FILE: &str = "/etc/environment";
+#[cfg(all(target_family = "unix", not(target_os = "linux")))]
+const SYSTEM_FILE: &str = "/etc/profile";
+
+/// The system scope of Termux is its prefix, Android's `/etc` being read-only.
+#[cfg(target_family = "unix")]
+pub(crate) fn system_file() -> PathBuf {
+    match termux_prefix() {
+        Some(prefix) if cfg!(target_os = "android") => prefix.join("etc/profile"),
+        _ => PathBuf::from(SYSTEM_FILE),
+    }
+}
+
+/// Installation prefix of Termux (`/data/data/com.termux/files/usr`), if running in it.
+fn termux_prefix() -> Option<PathBuf> {
+    match env::var_os("PREFIX") {
+        Some(prefix) if prefix.to_string_lossy().contains("com.termux") => Some(PathBuf::from(prefix)),
+        _ => env::var_os("TERMUX_VERSION").map(|_| PathBuf::from("/data/data/com.termux/files/usr")),
+    }
+}
+
+/// Home directory of the current user.
+pub(crate) fn home_dir() -> Result<PathBuf, EnvError> {
+    #[cfg(target_os = "windows")]
+    let homedir = env::var("HOME").or_else(|_| env::var("USERPROFILE"))?;
+    #[cfg(not(target_os = "windows"))]
+    let homedir = env::var("HOME")?;
+    Ok(PathBuf::from(homedir))
+}
+
+/// Startup file of the detected shell, resolved once as every operation on the default target needs it.
+static DETECTED: RwLock<Option<Result<(PathBuf, Syntax), EnvError>>> = RwLock::new(None);
+
+#[cfg(target_family = "unix")]
+fn detected_home_file() -> Result<Store, EnvError> {
+    let cached = DETECTED.read().unwrap_or_else(|e| e.into_inner()).clone();
+    let detected = match cached {
+        Some(detected) => detected,
+        None => {
+            let detected = Shell::detect().and_then(|shell| home_file(&home_dir()?, shell)).map(|store| match store {
+                Store::File(path, syntax) => (path, syntax),
+            });
+            *DETECTED.write().unwrap_or_else(|e| e.into_inner()) = Some(detected.clone());
+            detected
+        }
+    };
+    detected.map(|(path, syntax)| Store::File(path, syntax))
+}
+
+/// Forgets the shell detected from `SHELL` (and its startup file in `HOME`), which is otherwise resolved
+/// once per process: to be called after changing these variables, e.g. in tests.
+pub fn invalidate() {
+    *DETECTED.write().unwrap_or_else(|e| e.into_inner()) = None;
+}
+
+#[cfg_attr(not(any(target_os = "windows", target_family = "unix")), allow(dead_code))]
+fn home_file(home: &Path, shell: Shell) -> Result<Store, EnvError> {
+    let envfilepath = match shell {
+        Shell::Tcsh => csh_file(home),
+        Shell::Sh | Shell::Bash if cfg!(target_os = "haiku") => home.join(HAIKU_PROFILE),
+        #[cfg(target_family = "unix")]
+        Shell::Bash if cfg!(target_os = "android") && termux_prefix().is_some() => bash_login_file(home),
+        _ => home.join(shell.env_file()),
+    };
+    // Hook mode, see install_hook
+    if shell.syntax() == Syntax::Posix {
+        if let Some(hook_file) = crate::hook::hooked(home, &envfilepath) {
+            return Ok(Store::File(hook_file, Syntax::Posix));
+        }
+    }
+    Ok(Store::File(envfilepath, shell.syntax()))
+}
+
+/// Whether the names are the ones of the same variable: on Windows, they are case-insensitive (e.g. `Path` and
+/// `PATH`).
+pub(crate) fn same_name(a: &str, b: &str) -> bool {
+    if cfg!(target_os = "windows") { a.eq_ignore_ascii_case(b) } else { a == b }
+}
+
+/// Shell of the passwd entry, or the platform's default.
+#[cfg_attr(not(target_family = "unix"), allow(dead_code))]
+fn login_shell(shell: Option<&str>) -> Shell {
+    match shell.map(Shell::from_path) {
+        Some(Ok(shell)) => shell,
+        _ if cfg!(target_os = "macos") => Shell::Zsh,
+        _ if cfg!(any(target_os = "freebsd", target_os = "openbsd", target_os = "netbsd", target_os = "dragonfly")) => Shell::Sh,
+        _ if cfg!(target_os = "redox") => Shell::Ion,
+        _ => Shell::Bash,
+    }
+}
+
+/// Longest name of a registry value.
+const MAX_VALUE_NAME: usize = 16383;
+
+/// Why a variable can't have this name, if it can't. Such names would otherwise fail in the registry (or be
+/// truncated at the NUL) and make the process environment panic.
+pub(crate) fn invalid_name(var: &str, registry: bool) -> Option<&'static str> {
+    if var.is_empty() {
+        Some("empty")
+    } else if var.contains('=') {
+        Some("contains '='")
+    } else if var.contains('\0') {
+        Some("contains a NUL character")
+    } else if registry && var.encode_utf16().count() > MAX_VALUE_NAME {
+        Some("longer than the 16383 characters of a registry value name")
+    } else {
+        None
+    }
+}
+
+/// Fails if the name can't be the one of a variable of the registry, or of a file (a POSIX name).
+pub(crate) fn check_name(var: &str, registry: bool) -> Result<(), EnvError> {
+    let invalid = invalid_name(var, registry).or(if registry { None } else { crate::name::invalid_posix(var) });
+    match invalid {
+        Some(reason) => Err(EnvError::InvalidName(String::from(reason))),
+        None => Ok(()),
+    }
+}
+
+/// User profile of Haiku, sourced by its `/etc/profile` for every Terminal (login) shell.
+pub(crate) const HAIKU_PROFILE: &str = "config/settings/profile";
+
+/// Termux sessions are login shells, which read the first existing of these files (and not `.bashrc`).
+#[cfg(target_family = "unix")]
+fn bash_login_file(home: &Path) -> PathBuf {
+    [".bash_profile", ".bash_login"]
+        .iter()
+        .map(|name| home.join(name))
+        .find(|path| path.exists())
+        .unwrap_or_else(|| home.join(".profile"))
+}
+
+#[cfg_attr(not(any(target_os = "windows", target_family = "unix")), allow(dead_code))]
+fn csh_file(home: &Path) -> PathBuf {
+    let tcshrc = home.join(Shell::Tcsh.env_file());
+    if tcshrc.exists() { tcshrc } else { home.join(".cshrc") }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn detects_shell_from_path() {
+        assert_eq!(Shell::from_path("/usr/local/bin/bash"), Ok(Shell::Bash));
+        assert_eq!(Shell::from_path("/bin/csh"), Ok(Shell::Tcsh));
+        assert_eq!(Shell::from_path("/bin/sh"), Ok(Shell::Sh));
+        assert_eq!(Shell::from_path("-zsh"), Ok(Shell::Zsh));
+        assert_eq!(Shell::from_path("/bin/ksh"), Ok(Shell::Ksh));
+        assert_eq!(Shell::from_path("/usr/pkg/bin/bash"), Ok(Shell::Bash));
+        assert_eq!(Shell::from_path("/usr/bin/ion"), Ok(Shell::Ion));
+        assert_eq!(Shell::from_path("/usr/bin/nu"), Err(EnvError::UnsupportedShell));
+    }
+
+    #[test]
+    fn rejects_invalid_names() {
+        assert_eq!(invalid_name("A=B", false), Some("contains '='"));
+        assert_eq!(invalid_name("A\0", false), Some("contains a NUL character"));
+        assert_eq!(invalid_name("", true), Some("empty"));
+        assert!(invalid_name(&"A".repeat(16384), true).is_some());
+        assert_eq!(invalid_name(&"A".repeat(16384), false), None);
+        assert_eq!(Target::new().check("A=B"), Err(EnvError::InvalidName(String::from("contains '='"))));
+        let file = Target::new().file(std::env::temp_dir().join("globalenv_invalid_names"));
+        for var in ["A B", "A-B", "1A"] {
+            assert!(matches!(crate::set_var_in(&file, var, "1"), Err(EnvError::InvalidName(_))));
+            assert!(matches!(crate::unset_var_in(&file, var), Err(EnvError::InvalidName(_))));
+        }
+        assert_eq!(file.check("MY_VAR_1"), Ok(()));
+    }
+
+    #[test]
+    fn compares_names() {
+        assert!(same_name("PATH", "PATH"));
+        assert_eq!(same_name("Path", "PATH"), cfg!(target_os = "windows"));
+    }
+
+    #[test]
+    fn falls_back_to_login_shell() {
+        assert_eq!(login_shell(Some("/usr/local/bin/fish")), Shell::Fish);
+        let default = login_shell(None);
+        assert_eq!(login_shell(Some("/usr/sbin/nologin")), default);
+        if cfg!(target_os = "linux") {
+            assert_eq!(default, Shell::Bash);
+        }
+    }
+
+    #[test]
+    fn resolves_project_file() {
+        let dir = env::temp_dir().join("globalenv_project");
+        let _ = std::fs::remove_dir_all(&dir);
+        std::fs::create_dir_all(&dir).unwrap();
+        let target = Target::new().scope(Scope::Project(dir.clone()));
+        assert_eq!(target.location().unwrap(), dir.join(".env").display().to_string());
+        // A new project has no file yet
+        assert_eq!(crate::get_var_in(&target, "PROJECT_ROOT"), Ok(None));
+        crate::set_var_in(&target, "PROJECT_ROOT", "/src/app").unwrap();
+        assert!(std::fs::read_to_string(dir.join(".env")).unwrap().contains("export PROJECT_ROOT=/src/app\n"));
+        std::fs::write(dir.join(".envrc"), "").unwrap();
+        assert_eq!(target.location().unwrap(), dir.join(".envrc").display().to_string());
+        crate::set_var_in(&target, "PROJECT_ROOT", "/src/app").unwrap();
+        assert_eq!(crate::get_var_in(&target, "PROJECT_ROOT").unwrap().as_deref(), Some("/src/app"));
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn csh_file_defaults_to_cshrc() {
+        let home = env::temp_dir().join("globalenv_csh_home");
+        std::fs::create_dir_all(&home).unwrap();
+        let _ = std::fs::remove_file(home.join(".tcshrc"));
+        assert_eq!(csh_file(&home), home.join(".cshrc"));
+        std::fs::write(home.join(".tcshrc"), "").unwrap();
+        assert_eq!(csh_file(&home), home.join(".tcshrc"));
+        std::fs::remove_dir_all(&home).unwrap();
+    }
+
+    #[cfg(target_os = "linux")]
+    #[test]
+    fn targets_other_account() {
+        let root = Target::for_uid(0).unwrap();
+        assert_eq!(root, Target::for_user("root").unwrap());
+        assert!(root.get_home().unwrap().is_absolute() && root.get_shell().is_some());
+        assert_eq!(Target::for_user("no such user"), Err(EnvError::VarError));
+        if env::var_os("SUDO_USER").is_none() {
+            assert_eq!(Target::invoking_user(), Ok(Target::new()));
+        }
+    }
+
+    #[cfg(target_family = "unix")]
+    #[test]
+    fn termux_login_file() {
+        let home = env::temp_dir().join("globalenv_termux_home");
+        std::fs::create_dir_all(&home).unwrap();
+        assert_eq!(bash_login_file(&home), home.join(".profile"));
+        std::fs::write(home.join(".bash_login"), "").unwrap();
+        assert_eq!(bash_login_file(&home), home.join(".bash_login"));
+        std::fs::remove_dir_all(&home).unwrap();
+    }
+}
@@ -0,0 +1,85 @@
+//! Placeholders in values (`{home}`, `{config_dir}`...), resolved for the platform before the value is written.
+
+use crate::{EnvError, Target};
+use std::{env, path::PathBuf};
+
+/// An absolute directory from this variable, unless the target is another account's (whose environment this isn't).
+fn dir_var(target: &Target, var: &str) -> Option<PathBuf> {
+    env::var_os(var).map(PathBuf::from).filter(|dir| dir.is_absolute() && !target.has_home())
+}
+
+fn placeholder(target: &Target, name: &str) -> Result<String, EnvError> {
+    let home = || target.get_home();
+    let dir = match name {
+        "home" => home()?,
+        "config_dir" if cfg!(target_os = "windows") => dir_var(target, "APPDATA").unwrap_or(home()?.join(r"AppData\Roaming")),
+        "config_dir" if cfg!(target_os = "macos") => home()?.join("Library/Application Support"),
+        "config_dir" => dir_var(target, "XDG_CONFIG_HOME").unwrap_or(home()?.join(".config")),
+        "data_dir" if cfg!(target_os = "windows") => dir_var(target, "LOCALAPPDATA").unwrap_or(home()?.join(r"AppData\Local")),
+        "data_dir" if cfg!(target_os = "macos") => home()?.join("Library/Application Support"),
+        "data_dir" => dir_var(target, "XDG_DATA_HOME").unwrap_or(home()?.join(".local/share")),
+        "exe_dir" => env::current_exe()?.parent().map(PathBuf::from).ok_or(EnvError::VarError)?,
+        "temp_dir" => env::temp_dir(),
+        "path_sep" => return Ok(String::from(if cfg!(target_os = "windows") { ";" } else { ":" })),
+        _ => return Err(EnvError::ValidationError(format!("unknown placeholder {{{}}}", name))),
+    };
+    Ok(dir.to_string_lossy().into_owned())
+}
+
+/// The value with its placeholders replaced, `{{` and `}}` being literal braces.
+fn render_with(value: &str, mut resolve: impl FnMut(&str) -> Result<String, EnvError>) -> Result<String, EnvError> {
+    let mut rendered = String::new();
+    let mut rest = value;
+    while let Some(i) = rest.find(['{', '}']) {
+        rendered.push_str(&rest[..i]);
+        let (brace, after) = (&rest[i..i + 1], &rest[i + 1..]);
+        if let Some(after) = after.strip_prefix(brace) {
+            rendered.push_str(brace);
+            rest = after;
+            continue;
+        }
+        let end = after.find('}').filter(|_| brace == "{");
+        let Some(end) = end else { return Err(EnvError::ValidationError(String::from("unmatched brace"))) };
+        rendered.push_str(&resolve(&after[..end])?);
+        rest = &after[end + 1..];
+    }
+    rendered.push_str(rest);
+    Ok(rendered)
+}
+
+/// Resolves the placeholders of a value for the current platform, so that one value spec fits them all:
+/// `{home}`, `{config_dir}` (`%APPDATA%`, `~/Library/Application Support`, `$XDG_CONFIG_HOME`), `{data_dir}`
+/// (`%LOCALAPPDATA%`, `~/Library/Application Support`, `$XDG_DATA_HOME`), `{exe_dir}` (the directory of the
+/// current executable), `{temp_dir}` and `{path_sep}` (`;` or `:`). Braces are doubled to be literal (`{{`).
+/// [`SetOptions::template`](crate::SetOptions::template) resolves them when setting a variable.
+/// Example:
+/// ```rust
+/// let value = globalenv::render_template("{home}/.myapp{path_sep}{exe_dir}").unwrap();
+/// assert!(!value.contains('{'));
+/// ```
+pub fn render_template(value: &str) -> Result<String, EnvError> {
+    render_template_in(&Target::default(), value)
+}
+
+/// Resolves the placeholders of a value for the target's account.
+pub fn render_template_in(target: &Target, value: &str) -> Result<String, EnvError> {
+    render_with(value, |name| placeholder(target, name))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn replaces_placeholders() {
+        let resolve = |name: &str| match name {
+            "home" => Ok(String::from("/home/me")),
+            _ => Err(EnvError::ValidationError(format!("unknown placeholder {{{}}}", name))),
+        };
+        assert_eq!(render_with("{home}/bin", resolve), Ok(String::from("/home/me/bin")));
+        assert_eq!(render_with("{{home}} {home}", resolve), Ok(String::from("{home} /home/me")));
+        assert_eq!(render_with("{hom}", resolve), Err(EnvError::ValidationError(String::from("unknown placeholder {hom}"))));
+        assert!(render_with("{home", resolve).is_err() && render_with("a}b", resolve).is_err());
+        assert_eq!(render_template_in(&Target::new(), "{path_sep}").unwrap().len(), 1);
+    }
+}
@@ -0,0 +1,15 @@
+//! Propagation of changes to a running tmux server.
+
+use std::process::{Command, Stdio};
+
+/// Sets (or, without value, removes) the variable in the global environment of the tmux server.
+/// Best effort: nothing happens when tmux isn't installed or no server is running.
+pub(crate) fn set_environment(var: &str, value: Option<&str>) {
+    let mut tmux = Command::new("tmux");
+    tmux.args(["set-environment", "-g"]);
+    match value {
+        Some(value) => tmux.args([var, value]),
+        None => tmux.args(["-r", var]),
+    };
+    let _ = tmux.stdin(Stdio::null()).stdout(Stdio::null()).stderr(Stdio::null()).status();
+}
@@ -0,0 +1,128 @@
+//! Registry values keeping their type, e.g. numbers stored as `REG_DWORD` for the applications reading them so.
+
+use crate::{meta_of, registry, set_var_in, target::Store, EnvError, SetOptions, Target};
+use std::fmt;
+
+/// Registry value with its type.
+#[derive(Debug, PartialEq, Eq, Clone)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum TypedValue {
+    /// `REG_SZ` (or `REG_EXPAND_SZ` and `REG_MULTI_SZ` when read)
+    String(String),
+    /// `REG_DWORD`
+    Dword(u32),
+}
+
+impl TypedValue {
+    /// The number, of a `REG_DWORD` or of a string holding one.
+    pub fn as_u32(&self) -> Option<u32> {
+        match self {
+            TypedValue::String(value) => value.trim().parse().ok(),
+            TypedValue::Dword(value) => Some(*value),
+        }
+    }
+}
+
+impl fmt::Display for TypedValue {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            TypedValue::String(value) => f.write_str(value),
+            TypedValue::Dword(value) => write!(f, "{}", value),
+        }
+    }
+}
+
+impl From<u32> for TypedValue {
+    fn from(value: u32) -> TypedValue {
+        TypedValue::Dword(value)
+    }
+}
+
+impl From<&str> for TypedValue {
+    fn from(value: &str) -> TypedValue {
+        TypedValue::String(value.to_string())
+    }
+}
+
+impl From<String> for TypedValue {
+    fn from(value: String) -> TypedValue {
+        TypedValue::String(value)
+    }
+}
+
+/// Key of the target's hive typed values are stored in.
+#[derive(Debug, PartialEq, Eq, Clone, Default)]
+#[cfg_attr(feature = "serde", derive(serde::Serialize, serde::Deserialize))]
+pub enum Namespace {
+    /// The environment key of the scope. Windows only gives processes its string values, a `REG_DWORD` being
+    /// left to the applications reading the key
+    #[default]
+    Environment,
+    /// Another key of the scope's hive, relative to it (e.g. `Software\Vendor\App`), created if needed
+    Key(String),
+}
+
+impl Namespace {
+    fn key(&self) -> Option<&str> {
+        match self {
+            Namespace::Environment => None,
+            Namespace::Key(key) => Some(key),
+        }
+    }
+}
+
+/// Sets a variable keeping the type of its value: a number is stored as a `REG_DWORD` instead of a string.
+/// A string value is set as with [`set_var`](crate::set_var), in the current process too.
+/// Example:
+/// ```rust,no_run
+/// use globalenv::{set_var_typed, set_var_typed_in, Namespace, Target};
+/// set_var_typed("APP_WORKERS", 8).unwrap();
+/// let app = Namespace::Key(String::from(r"Software\Vendor\App"));
+/// set_var_typed_in(&Target::new(), &app, "Verbose", 1).unwrap();
+/// ```
+pub fn set_var_typed<V: Into<TypedValue>>(var: &str, value: V) -> Result<(), EnvError> {
+    set_var_typed_in(&Target::default(), &Namespace::Environment, var, value)
+}
+
+/// Sets a typed value in the namespace of the given target's scope. A file target gets the value as a string.
+pub fn set_var_typed_in<V: Into<TypedValue>>(target: &Target, namespace: &Namespace, var: &str, value: V) -> Result<(), EnvError> {
+    let value = value.into();
+    target.check(var)?;
+    let store = target.store()?;
+    match (&store, namespace, &value) {
+        (Store::File(..), Namespace::Environment, _) | (_, Namespace::Environment, TypedValue::String(_)) => {
+            set_var_in(target, var, &value.to_string())
+        }
+        (Store::Registry(_), Namespace::Environment, _) => {
+            let meta = SetOptions::new().meta(&meta_of(&store, var)?, true);
+            registry::set_typed(&target.get_scope(), None, var, &value, &meta)
+        }
+        (_, Namespace::Key(key), _) => registry::set_typed(&target.get_scope(), Some(key), var, &value, &Default::default()),
+    }
+}
+
+/// Gets a variable with the type of its value, which [`TypedValue::as_u32`] converts back to a number.
+pub fn get_var_typed(var: &str) -> Result<Option<TypedValue>, EnvError> {
+    get_var_typed_in(&Target::default(), &Namespace::Environment, var)
+}
+
+/// Gets a typed value from the namespace of the given target's scope, the value of a file target being a string.
+pub fn get_var_typed_in(target: &Target, namespace: &Namespace, var: &str) -> Result<Option<TypedValue>, EnvError> {
+    match (target.store()?, namespace.key()) {
+        (Store::File(..), None) => Ok(crate::get_var_in(target, var)?.map(TypedValue::String)),
+        (_, key) => registry::get_typed(&target.get_scope(), key, var),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn converts_typed_values() {
+        assert_eq!(TypedValue::from(8), TypedValue::Dword(8));
+        assert_eq!(TypedValue::from("8").as_u32(), Some(8));
+        assert_eq!(TypedValue::from("eight").as_u32(), None);
+        assert_eq!(TypedValue::Dword(4096).to_string(), "4096");
+    }
+}
@@ -0,0 +1,99 @@
+//! Environment of X11 sessions: the variables of applications launched from the desktop, which do not read
+//! the startup files of the interactive shells.
+
+use crate::{envfile::Endings, set_var_in, unset_var_report_in, EnvError, Shell, Target};
+use std::{fs, path::Path};
+
+/// Startup file of X11 sessions, sourced by the display managers (GDM, LightDM, SDDM...)
+const XPROFILE: &str = ".xprofile";
+/// Startup file of X11 sessions on Debian and its derivatives, sourced by `/etc/X11/Xsession`
+const XSESSIONRC: &str = ".xsessionrc";
+/// Line sourcing `~/.xprofile` from `~/.xsessionrc`.
+const SOURCE_LINE: &str = r#"[ -f "$HOME/.xprofile" ] && . "$HOME/.xprofile" # globalenv"#;
+
+/// Creates `~/.xprofile` if needed, and makes `~/.xsessionrc` source it where it is the file the session reads.
+fn prepare(target: &Target, home: &Path, debian: bool) -> Result<(), EnvError> {
+    let xprofile = home.join(XPROFILE);
+    if !xprofile.exists() {
+        fs::write(&xprofile, "")?;
+        target.own(&xprofile)?;
+    }
+    if !debian {
+        return Ok(());
+    }
+    let xsessionrc = home.join(XSESSIONRC);
+    let mut content = fs::read_to_string(&xsessionrc).unwrap_or_default();
+    if content.lines().any(|l| l.trim() == SOURCE_LINE) {
+        return Ok(());
+    }
+    let newline = Endings::of(&content).newline();
+    if !content.is_empty() && !content.ends_with('\n') {
+        content.push_str(newline);
+    }
+    content.push_str(SOURCE_LINE);
+    content.push_str(newline);
+    let created = !xsessionrc.exists();
+    fs::write(&xsessionrc, content)?;
+    if created {
+        target.own(&xsessionrc)?;
+    }
+    Ok(())
+}
+
+/// The target persisting in `~/.xprofile`, keeping the policy and account of the given one.
+fn xprofile_target(target: &Target) -> Result<Target, EnvError> {
+    Ok(target.clone().file(target.get_home()?.join(XPROFILE)).shell(Shell::Sh))
+}
+
+/// Sets a variable for the X11 sessions of the user, i.e. for the applications launched from the desktop:
+/// it is exported from `~/.xprofile`, which display managers source when the session starts. On Debian-style
+/// systems (with `/etc/X11/Xsession.d`), `~/.xsessionrc` gets a line sourcing it. It applies from the next
+/// session, and in the current process.
+/// Example:
+/// ```rust,no_run
+/// globalenv::set_var_x11("QT_QPA_PLATFORMTHEME", "gtk3").unwrap();
+/// ```
+pub fn set_var_x11(var: &str, value: &str) -> Result<(), EnvError> {
+    set_var_x11_in(&Target::default(), var, value)
+}
+
+/// Sets a variable for the X11 sessions of the target's user.
+pub fn set_var_x11_in(target: &Target, var: &str, value: &str) -> Result<(), EnvError> {
+    target.check(var)?;
+    prepare(target, &target.get_home()?, Path::new("/etc/X11/Xsession.d").is_dir())?;
+    set_var_in(&xprofile_target(target)?, var, value)
+}
+
+/// Unsets a variable from the X11 sessions of the user, returning whether `~/.xprofile` set it.
+pub fn unset_var_x11(var: &str) -> Result<bool, EnvError> {
+    unset_var_x11_in(&Target::default(), var)
+}
+
+/// Unsets a variable from the X11 sessions of the target's user.
+pub fn unset_var_x11_in(target: &Target, var: &str) -> Result<bool, EnvError> {
+    let xprofile = xprofile_target(target)?;
+    if !target.get_home()?.join(XPROFILE).exists() {
+        target.check(var)?;
+        std::env::remove_var(var);
+        return Ok(false);
+    }
+    unset_var_report_in(&xprofile, var)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn sources_xprofile_from_xsessionrc() {
+        let home = std::env::temp_dir().join("globalenv_x11_home");
+        fs::create_dir_all(&home).unwrap();
+        fs::write(home.join(XSESSIONRC), "xset r rate 200 30").unwrap();
+        let _ = fs::remove_file(home.join(XPROFILE));
+        prepare(&Target::new(), &home, true).unwrap();
+        prepare(&Target::new(), &home, true).unwrap();
+        assert!(home.join(XPROFILE).exists());
+        assert_eq!(fs::read_to_string(home.join(XSESSIONRC)).unwrap(), format!("xset r rate 200 30\n{}\n", SOURCE_LINE));
+        fs::remove_dir_all(&home).unwrap();
+    }
+}